@@ -0,0 +1,5 @@
+-- Restore the original foreign key without cascading deletes
+ALTER TABLE records_tags DROP CONSTRAINT IF EXISTS records_tags_record_id_fkey;
+
+ALTER TABLE records_tags ADD CONSTRAINT records_tags_record_id_fkey
+    FOREIGN KEY (record_id) REFERENCES records (id);
@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS records_user_created_at_idx;
+DROP INDEX IF EXISTS records_user_artist_idx;
+DROP INDEX IF EXISTS records_user_title_idx;
+DROP INDEX IF EXISTS records_user_release_date_idx;
+
+ALTER TABLE records
+DROP COLUMN created_at;
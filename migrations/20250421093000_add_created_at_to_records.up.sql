@@ -0,0 +1,9 @@
+-- Track when a record was added to the collection
+ALTER TABLE records
+ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+-- Indexes backing the keyset pagination of a user's collection
+CREATE INDEX records_user_created_at_idx ON records (user_id, created_at, id);
+CREATE INDEX records_user_artist_idx ON records (user_id, artist, id);
+CREATE INDEX records_user_title_idx ON records (user_id, title, id);
+CREATE INDEX records_user_release_date_idx ON records (user_id, release_date, id);
@@ -0,0 +1,8 @@
+ALTER TABLE records
+DROP COLUMN catalog_number,
+DROP COLUMN format,
+DROP COLUMN rating,
+DROP COLUMN collection_folder,
+DROP COLUMN media_condition,
+DROP COLUMN sleeve_condition,
+DROP COLUMN notes;
@@ -0,0 +1,9 @@
+-- Keep the collection details of a Discogs export
+ALTER TABLE records
+ADD COLUMN catalog_number VARCHAR(255),
+ADD COLUMN format VARCHAR(255),
+ADD COLUMN rating INTEGER CHECK (rating BETWEEN 0 AND 5),
+ADD COLUMN collection_folder VARCHAR(255),
+ADD COLUMN media_condition VARCHAR(255),
+ADD COLUMN sleeve_condition VARCHAR(255),
+ADD COLUMN notes TEXT;
@@ -0,0 +1,19 @@
+-- Metadata lookups of a user's records, processed in the background
+CREATE TABLE IF NOT EXISTS enrichment_jobs (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    -- Records to look up, processed in order
+    record_ids INTEGER[] NOT NULL,
+    total_records INTEGER NOT NULL,
+    processed_records INTEGER NOT NULL DEFAULT 0,
+    enriched_count INTEGER NOT NULL DEFAULT 0,
+    unchanged_count INTEGER NOT NULL DEFAULT 0,
+    failed_count INTEGER NOT NULL DEFAULT 0,
+    errors JSONB NOT NULL DEFAULT '[]',
+    failure TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX enrichment_jobs_status_idx ON enrichment_jobs (status, id);
@@ -0,0 +1,3 @@
+-- MusicBrainz release group of the record
+ALTER TABLE records
+ADD COLUMN musicbrainz_id VARCHAR(36);
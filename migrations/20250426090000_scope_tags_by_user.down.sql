@@ -0,0 +1,24 @@
+-- Merge the tags sharing a slug back into the oldest one
+CREATE TEMPORARY TABLE kept_tags AS
+SELECT slug, MIN(id) AS id FROM tags GROUP BY slug;
+
+UPDATE records_tags rt
+SET tag_id = kept.id
+FROM tags t, kept_tags kept
+WHERE t.id = rt.tag_id
+    AND kept.slug = t.slug
+    AND kept.id <> t.id;
+
+DELETE FROM tags t
+USING kept_tags kept
+WHERE kept.slug = t.slug AND kept.id <> t.id;
+
+DROP TABLE kept_tags;
+
+ALTER TABLE tags
+DROP CONSTRAINT tags_user_slug_unique,
+DROP CONSTRAINT tags_user_id_fkey,
+DROP COLUMN user_id;
+
+ALTER TABLE tags
+ADD CONSTRAINT tags_slug_unique UNIQUE (slug);
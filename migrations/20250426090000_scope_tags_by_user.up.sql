@@ -0,0 +1,42 @@
+-- Tags belong to a user instead of being shared by everyone
+ALTER TABLE tags DROP CONSTRAINT IF EXISTS tags_slug_unique;
+ALTER TABLE tags ADD COLUMN user_id INTEGER;
+
+-- Users of each tag, through their records
+CREATE TEMPORARY TABLE tag_owners AS
+SELECT DISTINCT rt.tag_id, r.user_id
+FROM records_tags rt
+JOIN records r ON r.id = rt.record_id;
+
+-- The first user keeps the tag
+UPDATE tags t
+SET user_id = owners.user_id
+FROM (SELECT tag_id, MIN(user_id) AS user_id FROM tag_owners GROUP BY tag_id) owners
+WHERE owners.tag_id = t.id;
+
+-- The others get their own copy, with the same name
+INSERT INTO tags (name, slug, user_id)
+SELECT t.name, t.slug, o.user_id
+FROM tag_owners o
+JOIN tags t ON t.id = o.tag_id
+WHERE o.user_id <> t.user_id;
+
+UPDATE records_tags rt
+SET tag_id = copy.id
+FROM records r, tags original, tags copy
+WHERE r.id = rt.record_id
+    AND original.id = rt.tag_id
+    AND original.user_id <> r.user_id
+    AND copy.user_id = r.user_id
+    AND copy.slug = original.slug;
+
+DROP TABLE tag_owners;
+
+-- DATA LOSS: tags on no record have no owner to give them to, they are deleted and the down
+-- migration does not bring them back. They only held a name, export them first to keep them
+DELETE FROM tags WHERE user_id IS NULL;
+
+ALTER TABLE tags
+ALTER COLUMN user_id SET NOT NULL,
+ADD CONSTRAINT tags_user_id_fkey FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+ADD CONSTRAINT tags_user_slug_unique UNIQUE (user_id, slug);
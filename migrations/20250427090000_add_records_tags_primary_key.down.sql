@@ -0,0 +1,5 @@
+ALTER TABLE records_tags
+DROP CONSTRAINT records_tags_tag_id_fkey,
+ADD CONSTRAINT records_tags_tag_id_fkey FOREIGN KEY (tag_id) REFERENCES tags (id);
+
+ALTER TABLE records_tags DROP CONSTRAINT records_tags_pkey;
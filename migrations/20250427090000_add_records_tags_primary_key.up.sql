@@ -0,0 +1,13 @@
+-- A tag is attached at most once to a record
+DELETE FROM records_tags a
+USING records_tags b
+WHERE a.ctid < b.ctid
+    AND a.record_id = b.record_id
+    AND a.tag_id = b.tag_id;
+
+ALTER TABLE records_tags ADD PRIMARY KEY (record_id, tag_id);
+
+-- Deleting a tag detaches it from its records
+ALTER TABLE records_tags
+DROP CONSTRAINT records_tags_tag_id_fkey,
+ADD CONSTRAINT records_tags_tag_id_fkey FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE;
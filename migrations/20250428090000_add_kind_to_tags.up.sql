@@ -0,0 +1,10 @@
+ALTER TABLE tags ADD COLUMN kind VARCHAR(20) NOT NULL DEFAULT 'custom';
+
+-- Imports used to add the Discogs genres as plain tags, their names are a fixed list
+UPDATE tags
+SET kind = 'genre'
+WHERE name IN (
+    'Blues', 'Brass & Military', 'Children''s', 'Classical', 'Electronic',
+    'Folk, World, & Country', 'Funk / Soul', 'Hip Hop', 'Jazz', 'Latin',
+    'Non-Music', 'Pop', 'Reggae', 'Rock', 'Stage & Screen'
+);
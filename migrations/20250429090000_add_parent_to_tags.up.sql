@@ -0,0 +1,4 @@
+-- Tags can be nested under another tag of the same user (Electronic > House > Deep House)
+ALTER TABLE tags ADD COLUMN parent_id INTEGER REFERENCES tags(id) ON DELETE SET NULL;
+
+CREATE INDEX tags_parent_id_idx ON tags (parent_id);
@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS records_tags_tag_id_idx;
+
+DROP INDEX IF EXISTS tags_name_fold_trgm_idx;
+
+DROP INDEX IF EXISTS tags_slug_trgm_idx;
+
+DROP EXTENSION IF EXISTS pg_trgm;
@@ -0,0 +1,10 @@
+-- Tag suggestions match slugs by prefix and by trigram similarity
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX tags_slug_trgm_idx ON tags USING gin (slug gin_trgm_ops);
+
+-- Slugs drop the "&" of names, the name with it read as "n" lets "rnb" find "R&B / Soul"
+CREATE INDEX tags_name_fold_trgm_idx ON tags USING gin ((replace(lower(name), '&', 'n')) gin_trgm_ops);
+
+-- Usage counts look the records of a tag up by tag_id
+CREATE INDEX records_tags_tag_id_idx ON records_tags (tag_id);
@@ -1,3 +1,4 @@
+use crate::providers::providers::Providers;
 use crate::repositories::repositories::Repositories;
 use crate::use_cases::use_cases::UseCases;
 use rocket::State;
@@ -8,16 +9,22 @@ pub type AppState = State<Arc<App>>;
 pub struct App {
     pub use_cases: UseCases,
     pub repos: Repositories,
+    pub providers: Providers,
 }
 
 impl App {
-    pub fn new(use_cases: UseCases, repos: Repositories) -> Self {
-        Self { use_cases, repos }
+    pub fn new(use_cases: UseCases, repos: Repositories, providers: Providers) -> Self {
+        Self {
+            use_cases,
+            repos,
+            providers,
+        }
     }
 }
 
 pub fn create_app() -> Arc<App> {
     let repos = Repositories::new();
     let use_cases = UseCases::new();
-    Arc::new(App::new(use_cases, repos))
+    let providers = Providers::new();
+    Arc::new(App::new(use_cases, repos, providers))
 }
@@ -13,4 +13,11 @@ pub struct DatabaseConfig {
 #[derive(Deserialize, Debug)]
 pub struct Config {
     pub databases: Map<String, DatabaseConfig>,
+    /// Seconds search results stay in the cache, 0 disables caching
+    #[serde(default = "default_search_cache_ttl")]
+    pub search_cache_ttl: u64,
+}
+
+fn default_search_cache_ttl() -> u64 {
+    3600
 }
@@ -1,9 +1,12 @@
+use crate::controllers::user_controller::read_backup;
 use crate::db::ConnectionDb;
+use crate::dto::backup_dto::RestoreReport;
 use crate::dto::user_dto::{UserLoginInput, UserRegisterInput};
 use crate::error::app_error::AppError;
 use crate::models::jwt_model::JwtClaim;
 use crate::utils::NetworkResponse;
 use crate::{app::AppState, models::jwt_model::Jwt};
+use rocket::data::Data;
 use rocket::serde::json::Json;
 use tracing::instrument;
 use validator::Validate;
@@ -65,8 +68,25 @@ async fn me(
     Ok(Json(jwt_claim))
 }
 
+/// Creates a new account from a backup made on another instance
+#[post("/restore", data = "<data>")]
+#[instrument(name = "auth_controller/restore", skip_all)]
+async fn restore(
+    app: &AppState,
+    mut db: ConnectionDb,
+    data: Data<'_>,
+) -> Result<Json<RestoreReport>, AppError> {
+    let backup = read_backup(data).await?;
+    let report = app
+        .use_cases
+        .backup
+        .restore(&app.repos, &mut db, None, backup)
+        .await?;
+    Ok(Json(report))
+}
+
 pub fn routes() -> Vec<rocket::Route> {
-    routes![log_in, register, me]
+    routes![log_in, register, me, restore]
 }
 
 // #[cfg(test)]
@@ -3,10 +3,12 @@ use crate::db::ConnectionDb;
 use crate::error::app_error::AppError;
 use crate::models::collection_model::CollectionToken;
 use crate::models::jwt_model::JwtClaim;
-use crate::models::record_model::Record;
+use crate::dto::record_dto::{RecordFilter, RecordPagination, RecordSort, SortDirection};
+use crate::models::record_model::{Record, RecordPage};
 use crate::templating;
 use crate::utils::Either;
 use crate::utils::NetworkResponse;
+use rocket::http::uri::Origin;
 use rocket::{delete, get, http::Status, post, response::content::RawHtml, serde::json::Json};
 use serde::Serialize;
 use tracing::instrument;
@@ -82,24 +84,36 @@ async fn delete_token(
 struct CollectionViewData {
     user_name: String,
     records: Vec<Record>,
-    records_count: usize,
+    records_count: i64,
+    next_url: Option<String>,
 }
 
-/// Gets a collection by its token in JSON format
-#[get("/<token>?<owned>&<wanted>&<format>")]
+/// Gets a page of a collection by its token in JSON format
+#[get("/<token>?<format>&<limit>&<after>&<sort>&<direction>&<filter..>")]
 #[instrument(name = "collection_controller/get_collection", skip_all)]
+#[allow(clippy::too_many_arguments)]
 async fn get_collection(
     app: &AppState,
     mut db: ConnectionDb,
+    uri: &Origin<'_>,
     token: String,
     format: Option<String>,
-    owned: Option<bool>,
-    wanted: Option<bool>,
-) -> Result<Either<Json<Vec<Record>>, RawHtml<String>>, AppError> {
-    let records = app
+    limit: Option<i64>,
+    after: Option<String>,
+    sort: Option<RecordSort>,
+    direction: Option<SortDirection>,
+    filter: RecordFilter,
+) -> Result<Either<Json<RecordPage>, RawHtml<String>>, AppError> {
+    let pagination = RecordPagination {
+        limit,
+        after,
+        sort,
+        direction,
+    };
+    let page = app
         .use_cases
         .collection
-        .get_collection_by_token(&app.repos, &mut db, &token, owned, wanted)
+        .get_collection_by_token(&app.repos, &mut db, &token, filter, pagination)
         .await?;
 
     match format.as_deref() {
@@ -116,11 +130,28 @@ async fn get_collection(
                 None => return Err(AppError::NotFound),
             };
 
+            // Link to the next page, keeping the current query parameters
+            let next_url = page.next_cursor.as_ref().map(|cursor| {
+                let mut params: Vec<String> = uri
+                    .query()
+                    .map(|query| {
+                        query
+                            .segments()
+                            .filter(|(key, _)| *key != "after")
+                            .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                params.push(format!("after={}", cursor));
+                format!("?{}", params.join("&"))
+            });
+
             // Create data for the template
             let data = CollectionViewData {
                 user_name: user.username,
-                records_count: records.len(),
-                records: records.clone(),
+                records_count: page.total,
+                records: page.items,
+                next_url,
             };
 
             // Render the template using the templating module
@@ -129,7 +160,7 @@ async fn get_collection(
         }
         _ => {
             // Default to JSON format
-            Ok(Either::Left(Json(records)))
+            Ok(Either::Left(Json(page)))
         }
     }
 }
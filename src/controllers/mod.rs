@@ -1,4 +1,5 @@
 pub mod auth_controller;
 pub mod record_controller;
 pub mod user_controller;
-pub mod collection_controller;
\ No newline at end of file
+pub mod collection_controller;
+pub mod tag_controller;
\ No newline at end of file
@@ -1,37 +1,68 @@
 use crate::app::AppState;
-use crate::db::ConnectionDb;
-use crate::dto::record_dto::RecordInput;
+use crate::config::Config;
+use crate::db::{ConnectionCache, ConnectionDb};
+use crate::dto::import_dto::{
+    ImportMode, ImportPreview, ImportReport, ImportRow, ImportRowError, MAX_BACKGROUND_IMPORT_SIZE, MAX_IMPORT_SIZE,
+};
+use crate::dto::metadata_dto::{DiscogsKind, ReleaseCode, ReleaseId};
+use crate::dto::tag_dto::RecordTagsInput;
+use crate::dto::record_dto::{
+    placeholder_cover_url, ExportFormat, RecordFilter, RecordInput, RecordPagination,
+    RecordPatchInput, RecordSort, SortDirection, ADDED_AT_FORMAT, EXPORT_PAGE_SIZE,
+};
 use crate::error::app_error::AppError;
 use crate::models::jwt_model::JwtClaim;
-use crate::models::record_model::Record;
+use crate::models::enrichment_job_model::EnrichmentJob;
+use crate::models::import_job_model::ImportJob;
+use crate::models::record_model::{Record, RecordPage};
+use crate::models::tag_model::{Tag, TagKind, TagResponse};
 use crate::utils::NetworkResponse;
+use rocket::http::{ContentType, Header, Status};
+use rocket::futures::stream::{BoxStream, StreamExt};
+use rocket::response::stream::TextStream;
 use rocket::serde::json::Json;
+use rocket::State;
 use tracing::instrument;
 use validator::Validate;
 use rocket::data::{Data, ToByteUnit};
 use std::io::Cursor;
-use csv::ReaderBuilder;
+use csv::{ReaderBuilder, WriterBuilder};
 
-#[get("/?<owned>&<wanted>")]
+#[get("/?<limit>&<after>&<sort>&<direction>&<filter..>")]
 #[instrument(name = "record_controller/index", skip_all)]
+#[allow(clippy::too_many_arguments)]
 async fn index(
     app: &AppState,
     mut db: ConnectionDb,
     jwt_claim: Result<JwtClaim, NetworkResponse>,
-    owned: Option<bool>,
-    wanted: Option<bool>,
-) -> Result<Json<Vec<Record>>, AppError> {
+    limit: Option<i64>,
+    after: Option<String>,
+    sort: Option<RecordSort>,
+    direction: Option<SortDirection>,
+    filter: RecordFilter,
+) -> Result<Json<RecordPage>, AppError> {
     let user_id = jwt_claim
         .map_err(|_| AppError::Unauthorized)
         .map(|key| key.sub)
         .map_err(|_| AppError::Unauthorized)?;
 
-    let records = app
+    let page = app
         .use_cases
         .record
-        .find_all_by_user_id(&app.repos, &mut db, user_id, owned, wanted)
+        .find_all_by_user_id(
+            &app.repos,
+            &mut db,
+            user_id,
+            filter,
+            RecordPagination {
+                limit,
+                after,
+                sort,
+                direction,
+            },
+        )
         .await?;
-    Ok(Json(records))
+    Ok(Json(page))
 }
 
 #[post("/", data = "<body>")]
@@ -63,54 +94,156 @@ async fn add(
     Ok(Json(created_records))
 }
 
-#[post("/import", data = "<data>")]
-#[instrument(name = "record_controller/import", skip_all)]
-async fn import(
+/// Adds a Discogs release, or a master with `kind=master`, to the collection or the wantlist
+#[post("/from-discogs/<id>?<kind>&<wanted>")]
+#[instrument(name = "record_controller/add_from_discogs", skip_all)]
+async fn add_from_discogs(
     app: &AppState,
     mut db: ConnectionDb,
-    data: Data<'_>,
+    id: i64,
+    kind: Option<DiscogsKind>,
+    wanted: Option<bool>,
     jwt_claim: Result<JwtClaim, NetworkResponse>,
-) -> Result<Json<Vec<Record>>, AppError> {
+) -> Result<Json<Record>, AppError> {
     let user_id = jwt_claim
         .map_err(|_| AppError::Unauthorized)
         .map(|key| key.sub)
         .map_err(|_| AppError::Unauthorized)?;
 
-    // Read data with a size limit of 5MB
-    let bytes = match data.open(5.mebibytes()).into_bytes().await {
-        Ok(bytes) => bytes,
-        Err(e) => return Err(AppError::new(500, &format!("Failed to read file: {}", e))),
+    let release_id = ReleaseId::Discogs {
+        kind: kind.unwrap_or_default(),
+        id,
     };
-    
-    if !bytes.is_complete() {
-        return Err(AppError::new(413, "File too large (max 5MB)"));
+    let created_record = app
+        .use_cases
+        .record
+        .create_from_discogs(
+            &app.repos,
+            &mut db,
+            &app.providers,
+            user_id,
+            release_id,
+            wanted.unwrap_or(false),
+        )
+        .await?;
+
+    Ok(Json(created_record))
+}
+
+/// Columns of a Discogs collection export, followed by our own tags column
+const DISCOGS_CSV_HEADERS: [&str; 14] = [
+    "Catalog#",
+    "Artist",
+    "Title",
+    "Label",
+    "Format",
+    "Rating",
+    "Released",
+    "release_id",
+    "CollectionFolder",
+    "Date Added",
+    "Collection Media Condition",
+    "Collection Sleeve Condition",
+    "Collection Notes",
+    "Tags",
+];
+
+/// Separates tag names in the `Tags` column, labels already contain commas
+const CSV_TAG_SEPARATOR: &str = "; ";
+
+/// Tags of an imported row: its label, used as a label tag, and the entries of the `Tags` column
+fn csv_tags(label: &str, tags: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let label = Some(label.trim())
+        .filter(|label| !label.is_empty())
+        .map(|label| Tag::qualify(TagKind::Label.as_str(), label));
+    let candidates = label
+        .into_iter()
+        .chain(tags.split(CSV_TAG_SEPARATOR.trim()).map(|name| name.trim().to_string()));
+
+    for name in candidates.filter(|name| !name.is_empty()) {
+        let slug = Tag::slug_of(&name);
+        if !names.iter().any(|existing| Tag::slug_of(existing) == slug) {
+            names.push(name);
+        }
     }
 
-    // Convert bytes to string
-    let string_data = match std::str::from_utf8(&bytes.value) {
-        Ok(v) => v,
-        Err(_) => return Err(AppError::new(400, "Invalid UTF-8 sequence")),
-    };
+    names
+}
+
+fn csv_line(fields: &[String]) -> String {
+    let mut writer = WriterBuilder::new().from_writer(Vec::new());
+    // Writing to memory cannot fail
+    let _ = writer.write_record(fields);
+    String::from_utf8(writer.into_inner().unwrap_or_default()).unwrap_or_default()
+}
+
+/// Discogs collection row of a record, tags go to the `Tags` column
+fn discogs_csv_row(record: &Record) -> String {
+    // Only release urls carry a release_id, masters cannot be imported back
+    let release_id = record
+        .discogs_url
+        .as_deref()
+        .and_then(|url| url.rsplit_once("/release/"))
+        .map(|(_, id)| id.chars().take_while(char::is_ascii_digit).collect::<String>())
+        .unwrap_or_default();
+    // Label tags go back to the `Label` column, the others keep their kind prefix
+    let (labels, tags): (Vec<&TagResponse>, Vec<&TagResponse>) = record
+        .tags
+        .iter()
+        .flatten()
+        .partition(|tag| tag.kind == TagKind::Label.as_str());
+    let labels = labels
+        .iter()
+        .map(|tag| tag.name.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ");
+    let tags = tags
+        .iter()
+        .map(|tag| tag.qualified_name())
+        .collect::<Vec<String>>()
+        .join(CSV_TAG_SEPARATOR);
+
+    csv_line(&[
+        record.catalog_number.clone().unwrap_or_default(),
+        record.artist.clone(),
+        record.title.clone(),
+        labels,
+        record.format.clone().unwrap_or_default(),
+        record.rating.map(|rating| rating.to_string()).unwrap_or_default(),
+        record.release_date.format("%Y").to_string(),
+        release_id,
+        record.collection_folder.clone().unwrap_or_default(),
+        record.created_at.format(ADDED_AT_FORMAT).to_string(),
+        record.media_condition.clone().unwrap_or_default(),
+        record.sleeve_condition.clone().unwrap_or_default(),
+        record.notes.clone().unwrap_or_default(),
+        tags,
+    ])
+}
 
-    // Parse CSV
+/// Turns a Discogs collection export into import rows, keeping the reason of unusable ones
+fn parse_discogs_csv(data: &str) -> Vec<ImportRow> {
     let mut reader = ReaderBuilder::new()
         .has_headers(true)
-        .from_reader(Cursor::new(string_data));
+        .from_reader(Cursor::new(data));
+
+    let mut rows = Vec::new();
 
-    // Transform CSV records into RecordInput objects
-    let mut record_inputs = Vec::new();
-    let mut import_errors = Vec::new();
-    
     // Track the current row for error reporting
     let mut row_index = 0;
-    
+
     for result in reader.records() {
         row_index += 1;
-        
+
         let record = match result {
             Ok(record) => record,
             Err(e) => {
-                import_errors.push(format!("Row {}: Error reading CSV record: {}", row_index, e));
+                let message = format!("Error reading CSV record: {}", e);
+                rows.push(ImportRow {
+                    row: row_index,
+                    record: Err(vec![ImportRowError::new(row_index, "csv", &message)]),
+                });
                 continue;
             }
         };
@@ -123,8 +256,11 @@ async fn import(
         if record.len() < 7 {
             tracing::warn!("Row {}: Not enough fields (found {}, expected at least 7): {:?}", 
                 row_index, record.len(), record);
-            import_errors.push(format!("Row {}: Not enough fields (found {}, expected at least 7)", 
-                row_index, record.len()));
+            let message = format!("Not enough fields (found {}, expected at least 7)", record.len());
+            rows.push(ImportRow {
+                row: row_index,
+                record: Err(vec![ImportRowError::new(row_index, "csv", &message)]),
+            });
             continue;
         }
 
@@ -134,9 +270,19 @@ async fn import(
         let release_year = record.get(6).unwrap_or_default().trim();
         let release_id = record.get(7).unwrap_or_default().trim();
 
+        // Collection columns, missing or empty ones are left unset
+        let optional = |index: usize| {
+            record
+                .get(index)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let rating_value = optional(5);
+        let rating = rating_value.as_deref().and_then(|rating| rating.parse::<i32>().ok());
+
         // Default coverUrl - could be updated with a real cover URL from an API call
-        let cover_url = format!("https://via.placeholder.com/300x300?text={}",
-            urlencoding::encode(&format!("{} - {}", artist, title)));
+        let cover_url = placeholder_cover_url(artist, title);
 
         // Format release date as YYYY-01-01 (using January 1st as default day/month)
         let release_date = if !release_year.is_empty() {
@@ -159,52 +305,317 @@ async fn import(
             cover_url,
             discogs_url,
             spotify_url: None, // We don't have Spotify URL from Discogs CSV
+            musicbrainz_id: None,
             owned: Some(true), // Records in the collection are owned
             wanted: Some(false), // Not in wantlist since they're already owned
-            tags: Some(vec![label.to_string()]), // Use label as a tag
+            created_at: optional(9), // Date Added is when it entered the collection
+            catalog_number: optional(0),
+            format: optional(4),
+            rating,
+            collection_folder: optional(8),
+            media_condition: optional(10),
+            sleeve_condition: optional(11),
+            notes: optional(12),
+            tags: Some(csv_tags(label, record.get(13).unwrap_or_default())),
         };
 
-        // Validate the record input
-        if let Err(e) = input.validate() {
-            // Extract validation error messages for this record
-            let validation_errors: Vec<String> = e.field_errors()
+        // Validate the record input, one error per invalid field
+        let mut errors: Vec<ImportRowError> = match input.validate() {
+            Ok(_) => Vec::new(),
+            Err(e) => e
+                .field_errors()
                 .iter()
                 .flat_map(|(field, errors)| {
                     errors.iter().map(|error| {
-                        format!("Field '{}': {}", field, error.message.as_ref().unwrap_or(&"Invalid".into()))
-                    }).collect::<Vec<String>>()
+                        let message = error.message.as_ref().unwrap_or(&"Invalid".into()).to_string();
+                        ImportRowError::new(row_index, field, &message)
+                    })
                 })
-                .collect();
-                
-            let error_msg = format!("Row {}: Validation errors: {}", 
-                row_index, validation_errors.join(", "));
-            import_errors.push(error_msg);
+                .collect(),
+        };
+
+        // The CSV only has the year, its date errors are worded after the Released column
+        if errors.iter().any(|error| error.field == "release_date") {
+            errors.retain(|error| error.field != "release_date");
+            errors.push(ImportRowError::new(row_index, "release_date", "Released is not a valid year"));
+        }
+
+        if rating_value.is_some() && rating.is_none() {
+            errors.push(ImportRowError::new(row_index, "rating", "Rating is not a number"));
+        }
+
+        if !errors.is_empty() {
+            rows.push(ImportRow {
+                row: row_index,
+                record: Err(errors),
+            });
             continue;
         }
 
-        record_inputs.push(input);
+        rows.push(ImportRow {
+            row: row_index,
+            record: Ok(input),
+        });
+    }
+
+    rows
+}
+
+/// Outcome of an import request
+#[derive(Responder)]
+enum ImportResponse {
+    Report(Json<ImportReport>),
+    Preview(Json<ImportPreview>),
+    #[response(status = 202)]
+    Queued(Json<ImportJob>),
+}
+
+#[post("/import?<mode>&<dry_run>&<background>", data = "<data>")]
+#[instrument(name = "record_controller/import", skip_all)]
+#[allow(clippy::too_many_arguments)]
+async fn import(
+    app: &AppState,
+    mut db: ConnectionDb,
+    data: Data<'_>,
+    mode: Option<ImportMode>,
+    dry_run: Option<bool>,
+    background: Option<bool>,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<ImportResponse, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    // Files queued for the import worker don't hold the request while they are imported
+    let dry_run = dry_run.unwrap_or(false);
+    let background = background.unwrap_or(false) && !dry_run;
+    let limit = match background {
+        true => MAX_BACKGROUND_IMPORT_SIZE,
+        false => MAX_IMPORT_SIZE,
+    };
+    let bytes = match data.open(limit.bytes()).into_bytes().await {
+        Ok(bytes) => bytes,
+        Err(e) => return Err(AppError::new(500, &format!("Failed to read file: {}", e))),
+    };
+
+    if !bytes.is_complete() {
+        return Err(AppError::new(413, &format!("File too large (max {}MB)", limit / 1024 / 1024)));
     }
-    
-    // If we have any errors, return them all together
-    if !import_errors.is_empty() {
-        return Err(AppError::new(400, &format!("Import failed with {} errors:\n{}", 
-            import_errors.len(), 
-            import_errors.join("\n"))));
+
+    // Convert bytes to string
+    let string_data = match std::str::from_utf8(&bytes.value) {
+        Ok(v) => v,
+        Err(_) => return Err(AppError::new(400, "Invalid UTF-8 sequence")),
+    };
+
+    let rows = parse_discogs_csv(string_data);
+
+    // A dry run only reports what the import would do
+    if dry_run {
+        let preview = app
+            .use_cases
+            .record
+            .preview_import(&app.repos, &mut db, user_id, rows)
+            .await?;
+
+        return Ok(ImportResponse::Preview(Json(preview)));
     }
-    
-    // If no records were successfully parsed
-    if record_inputs.is_empty() {
-        return Err(AppError::new(400, "No valid records found in the CSV file"));
+
+    // Large files can be processed by the import worker instead
+    if background {
+        let job = app
+            .use_cases
+            .import_job
+            .create(&app.repos, &mut db, user_id, rows, mode.unwrap_or_default())
+            .await?;
+
+        return Ok(ImportResponse::Queued(Json(job)));
     }
 
-    // Create the records in the database
-    let created_records = app
+    // Rows already in the collection are matched on their discogs_url
+    let report = app
         .use_cases
         .record
-        .create_multiple(&app.repos, &mut db, user_id, record_inputs)
+        .import(&app.repos, &mut db, user_id, rows, mode.unwrap_or_default())
         .await?;
 
-    Ok(Json(created_records))
+    Ok(ImportResponse::Report(Json(report)))
+}
+
+/// Streamed export, downloaded as a file
+#[derive(Responder)]
+struct ExportResponse<R> {
+    inner: (ContentType, R),
+    disposition: Header<'static>,
+}
+
+#[get("/export?<format>&<filter..>")]
+#[instrument(name = "record_controller/export", skip_all)]
+async fn export(
+    app: &AppState,
+    db: ConnectionDb,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+    format: Option<ExportFormat>,
+    filter: RecordFilter,
+) -> Result<ExportResponse<TextStream<BoxStream<'static, String>>>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let format = format.unwrap_or_default();
+    let content_type = match format {
+        ExportFormat::Csv => ContentType::CSV,
+        ExportFormat::Json => ContentType::JSON,
+        ExportFormat::Ndjson => ContentType::new("application", "x-ndjson"),
+    };
+    let disposition = Header::new(
+        "Content-Disposition",
+        format!("attachment; filename=\"records.{}\"", format.extension()),
+    );
+
+    // The collection is read page by page so it is never held in memory at once
+    let app = app.inner().clone();
+    let mut db = db;
+    let stream = TextStream! {
+        match format {
+            ExportFormat::Csv => yield csv_line(&DISCOGS_CSV_HEADERS.map(String::from)),
+            ExportFormat::Json => yield "[".to_string(),
+            ExportFormat::Ndjson => {}
+        }
+
+        // On error the stream stops without its terminator, so that a truncated JSON export
+        // cannot be mistaken for a complete one
+        let mut after = None;
+        let mut first = true;
+        let mut complete = false;
+        'pages: loop {
+            let pagination = RecordPagination {
+                limit: Some(EXPORT_PAGE_SIZE),
+                after: after.take(),
+                sort: Some(RecordSort::Added),
+                direction: Some(SortDirection::Asc),
+            };
+            let page = match app
+                .use_cases
+                .record
+                .find_all_by_user_id(&app.repos, &mut db, user_id, filter.clone(), pagination)
+                .await
+            {
+                Ok(page) => page,
+                Err(e) => {
+                    // The response has started, the export can only stop early
+                    tracing::error!("Export of user {} stopped: {}", user_id, e);
+                    break;
+                }
+            };
+
+            for record in &page.items {
+                let line = match format {
+                    ExportFormat::Csv => Ok(discogs_csv_row(record)),
+                    ExportFormat::Json | ExportFormat::Ndjson => serde_json::to_string(record),
+                };
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        tracing::error!("Export of user {} stopped on record {}: {}", user_id, record.id, e);
+                        break 'pages;
+                    }
+                };
+                match format {
+                    ExportFormat::Csv => yield line,
+                    ExportFormat::Json if first => yield line,
+                    ExportFormat::Json => yield format!(",{}", line),
+                    ExportFormat::Ndjson => yield format!("{}\n", line),
+                }
+                first = false;
+            }
+
+            match page.next_cursor {
+                Some(cursor) => after = Some(cursor),
+                None => {
+                    complete = true;
+                    break;
+                }
+            }
+        }
+
+        if complete && format == ExportFormat::Json {
+            yield "]".to_string();
+        }
+    };
+
+    Ok(ExportResponse {
+        inner: (content_type, TextStream(stream.0.boxed())),
+        disposition,
+    })
+}
+
+#[get("/import/jobs/<id>")]
+#[instrument(name = "record_controller/import_job", skip_all, fields(id = %id))]
+async fn import_job(
+    app: &AppState,
+    mut db: ConnectionDb,
+    id: i32,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<Json<ImportJob>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let job = app
+        .use_cases
+        .import_job
+        .find_by_id(&app.repos, &mut db, user_id, id)
+        .await?;
+
+    Ok(Json(job))
+}
+
+/// Queues the metadata lookups of the user's records missing a cover, a Spotify link or tags
+#[post("/enrich")]
+#[instrument(name = "record_controller/enrich", skip_all)]
+async fn enrich(
+    app: &AppState,
+    mut db: ConnectionDb,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<(Status, Json<EnrichmentJob>), AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let job = app
+        .use_cases
+        .enrichment_job
+        .create(&app.repos, &mut db, &app.providers, user_id)
+        .await?;
+
+    Ok((Status::Accepted, Json(job)))
+}
+
+#[get("/enrich/jobs/<id>")]
+#[instrument(name = "record_controller/enrichment_job", skip_all, fields(id = %id))]
+async fn enrichment_job(
+    app: &AppState,
+    mut db: ConnectionDb,
+    id: i32,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<Json<EnrichmentJob>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let job = app
+        .use_cases
+        .enrichment_job
+        .find_by_id(&app.repos, &mut db, user_id, id)
+        .await?;
+
+    Ok(Json(job))
 }
 
 #[get("/<id>")]
@@ -238,14 +649,143 @@ async fn get(
     Ok(Json(record))
 }
 
-#[get("/random?<owned>&<wanted>")]
+#[put("/<id>", data = "<body>")]
+#[instrument(name = "record_controller/update", skip_all)]
+async fn update(
+    app: &AppState,
+    mut db: ConnectionDb,
+    id: i32,
+    body: Json<RecordInput>,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<Json<Record>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let input = body.into_inner();
+    input
+        .validate()
+        .map_err(|e| AppError::ValidationError { errors: e })?;
+
+    let record = app
+        .use_cases
+        .record
+        .update(&app.repos, &mut db, user_id, id, input)
+        .await?;
+
+    Ok(Json(record))
+}
+
+#[patch("/<id>", data = "<body>")]
+#[instrument(name = "record_controller/patch", skip_all)]
+async fn patch(
+    app: &AppState,
+    mut db: ConnectionDb,
+    id: i32,
+    body: Json<RecordPatchInput>,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<Json<Record>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let input = body.into_inner();
+    input
+        .validate()
+        .map_err(|e| AppError::ValidationError { errors: e })?;
+
+    let record = app
+        .use_cases
+        .record
+        .patch(&app.repos, &mut db, user_id, id, input)
+        .await?;
+
+    Ok(Json(record))
+}
+
+#[delete("/<id>")]
+#[instrument(name = "record_controller/delete", skip_all)]
+async fn delete(
+    app: &AppState,
+    mut db: ConnectionDb,
+    id: i32,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<Status, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    app.use_cases
+        .record
+        .delete(&app.repos, &mut db, user_id, id)
+        .await?;
+
+    Ok(Status::NoContent)
+}
+
+/// Adds tags to a record, creating the ones the user doesn't have yet
+#[post("/<id>/tags", data = "<body>")]
+#[instrument(name = "record_controller/add_tags", skip_all)]
+async fn add_tags(
+    app: &AppState,
+    mut db: ConnectionDb,
+    id: i32,
+    body: Json<RecordTagsInput>,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<Json<Record>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let input = body.into_inner();
+    input
+        .validate()
+        .map_err(|e| AppError::ValidationError { errors: e })?;
+
+    let record = app
+        .use_cases
+        .record
+        .add_tags(&app.repos, &mut db, user_id, id, input.tags)
+        .await?;
+
+    Ok(Json(record))
+}
+
+/// Removes a tag from a record
+#[delete("/<id>/tags/<slug>")]
+#[instrument(name = "record_controller/remove_tag", skip_all)]
+async fn remove_tag(
+    app: &AppState,
+    mut db: ConnectionDb,
+    id: i32,
+    slug: &str,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<Json<Record>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let record = app
+        .use_cases
+        .record
+        .remove_tag(&app.repos, &mut db, user_id, id, slug)
+        .await?;
+
+    Ok(Json(record))
+}
+
+#[get("/random?<filter..>")]
 #[instrument(name = "record_controller/random", skip_all)]
 async fn random(
     app: &AppState,
     mut db: ConnectionDb,
     jwt_claim: Result<JwtClaim, NetworkResponse>,
-    owned: Option<bool>,
-    wanted: Option<bool>,
+    filter: RecordFilter,
 ) -> Result<Json<Option<Record>>, AppError> {
     let user_id = jwt_claim
         .map_err(|_| AppError::Unauthorized)
@@ -255,33 +795,110 @@ async fn random(
     let record = app
         .use_cases
         .record
-        .get_random_by_user_id(&app.repos, &mut db, user_id, owned, wanted)
+        .get_random_by_user_id(&app.repos, &mut db, user_id, filter)
         .await?;
     Ok(Json(record))
 }
 
+/// Search results, `X-Cache` tells whether they came from the cache
+#[derive(Responder)]
+struct SearchResponse {
+    inner: Json<Vec<Record>>,
+    cache: Header<'static>,
+}
+
 #[get("/search?<query>")]
 #[instrument(name = "record_controller/search", skip_all)]
 async fn search(
     app: &AppState,
+    mut db: ConnectionDb,
+    config: &State<Config>,
+    cache: Option<ConnectionCache>,
     query: String,
     jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<SearchResponse, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    // Without Redis the search still runs, uncached
+    let mut cache = cache.map(|con| con.into_inner());
+    let results = app
+        .use_cases
+        .record
+        .search(
+            &app.repos,
+            &mut db,
+            &mut cache,
+            &app.providers,
+            user_id,
+            &query,
+            config.search_cache_ttl,
+        )
+        .await?;
+
+    let cache_status = if results.cache_hit { "HIT" } else { "MISS" };
+    Ok(SearchResponse {
+        inner: Json(results.records),
+        cache: Header::new("X-Cache", cache_status),
+    })
+}
+
+#[get("/lookup?<barcode>&<catno>")]
+#[instrument(name = "record_controller/lookup", skip_all)]
+async fn lookup(
+    app: &AppState,
+    mut db: ConnectionDb,
+    barcode: Option<String>,
+    catno: Option<String>,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
 ) -> Result<Json<Vec<Record>>, AppError> {
-    let _user_id = jwt_claim
+    let user_id = jwt_claim
         .map_err(|_| AppError::Unauthorized)
         .map(|key| key.sub)
         .map_err(|_| AppError::Unauthorized)?;
 
-    let records = app.use_cases.record.search(&query).await?;
+    let code = match (barcode, catno) {
+        (Some(barcode), None) => ReleaseCode::Barcode(barcode),
+        (None, Some(catno)) => ReleaseCode::CatalogNumber(catno),
+        _ => return Err(AppError::new(400, "Either barcode or catno is required")),
+    };
+    let records = app
+        .use_cases
+        .record
+        .lookup(&app.repos, &mut db, &app.providers, user_id, code)
+        .await?;
     Ok(Json(records))
 }
 
 pub fn routes() -> Vec<rocket::Route> {
-    routes![index, add, get, random, search, import]
+    routes![
+        index,
+        add,
+        add_from_discogs,
+        get,
+        update,
+        patch,
+        delete,
+        add_tags,
+        remove_tag,
+        random,
+        search,
+        lookup,
+        import,
+        import_job,
+        enrich,
+        enrichment_job,
+        export
+    ]
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{csv_line, discogs_csv_row, parse_discogs_csv, DISCOGS_CSV_HEADERS};
+    use crate::models::tag_model::{Tag, TagResponse};
+    use crate::test::fixture::record::record_fixture;
     use crate::app_err;
     use crate::config::Config;
     use crate::db::Db;
@@ -341,4 +958,72 @@ mod tests {
         let body_str = response.into_string().await.expect("valid body string");
         assert_eq!(body_str, "error");
     }
+
+    #[test]
+    fn test_parse_discogs_csv_reports_field_errors() {
+        let csv = "Catalog#,Artist,Title,Label,Format,Rating,Released,release_id,CollectionFolder,Date Added\n\
+            123,Daft Punk,Discovery,Virgin,LP,4,2001,1,Uncategorized,2025-03-23 10:40:35\n\
+            124,,Homework,Virgin,LP,x,199x,2,,\n\
+            125,Justice\n";
+        let rows = parse_discogs_csv(csv);
+        assert_eq!(rows.len(), 3);
+
+        let record = rows[0].record.as_ref().unwrap();
+        assert_eq!(record.release_date, "2001-01-01");
+        assert_eq!(record.discogs_url.as_deref(), Some("https://www.discogs.com/release/1"));
+        assert_eq!(record.catalog_number.as_deref(), Some("123"));
+        assert_eq!(record.rating, Some(4));
+        assert_eq!(record.created_at.as_deref(), Some("2025-03-23 10:40:35"));
+        assert!(record.notes.is_none());
+
+        let errors = rows[1].record.as_ref().unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+        assert!(errors.iter().all(|error| error.row == 2));
+        assert!(fields.contains(&"artist"));
+        assert!(fields.contains(&"release_date"));
+        assert!(fields.contains(&"rating"));
+
+        let errors = rows[2].record.as_ref().unwrap_err();
+        assert_eq!(errors[0].field, "csv");
+    }
+
+    #[test]
+    fn test_discogs_csv_export_round_trip() {
+        let mut record = record_fixture(1);
+        record.discogs_url = Some("https://www.discogs.com/release/29277010-Daft-Punk-Discovery".to_string());
+        record.catalog_number = Some("0190296617164".to_string());
+        record.format = Some("2xLP, Album, RE, Gat".to_string());
+        record.rating = Some(4);
+        record.notes = Some("Signed, \"mint\"".to_string());
+        let tags = record.tags.get_or_insert_with(Vec::new);
+        tags.push(TagResponse::from(Tag::new(1, "genre:Electronic".to_string())));
+        tags.push(TagResponse::from(Tag::new(1, "label:Daft Life".to_string())));
+
+        let csv = format!(
+            "{}{}",
+            csv_line(&DISCOGS_CSV_HEADERS.map(String::from)),
+            discogs_csv_row(&record)
+        );
+        let rows = parse_discogs_csv(&csv);
+        let input = rows[0].record.as_ref().unwrap();
+
+        assert_eq!(input.title, record.title);
+        assert_eq!(input.artist, record.artist);
+        assert_eq!(input.release_date, "2021-01-01");
+        assert_eq!(input.discogs_url.as_deref(), Some("https://www.discogs.com/release/29277010"));
+        assert_eq!(input.created_at.as_deref(), Some("2021-01-01 00:00:00"));
+        assert_eq!(input.catalog_number, record.catalog_number);
+        assert_eq!(input.format, record.format);
+        assert_eq!(input.rating, Some(4));
+        assert_eq!(input.notes, record.notes);
+        assert_eq!(
+            input.tags,
+            Some(vec![
+                "label:Daft Life".to_string(),
+                "tag1-1".to_string(),
+                "tag1-2".to_string(),
+                "genre:Electronic".to_string(),
+            ])
+        );
+    }
 }
@@ -0,0 +1,156 @@
+use crate::app::AppState;
+use crate::db::ConnectionDb;
+use crate::dto::tag_dto::{TagMergeInput, TagPatchInput};
+use crate::error::app_error::AppError;
+use crate::models::jwt_model::JwtClaim;
+use crate::models::tag_model::{TagNode, TagResponse, TagUsage};
+use crate::utils::NetworkResponse;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{delete, get, patch, post};
+use tracing::instrument;
+use validator::Validate;
+
+/// Lists the tags of the authenticated user with the number of records using them
+#[get("/")]
+#[instrument(name = "tag_controller/index", skip_all)]
+async fn index(
+    app: &AppState,
+    mut db: ConnectionDb,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<Json<Vec<TagUsage>>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let tags = app.use_cases.tag.find_all(&app.repos, &mut db, user_id).await?;
+
+    Ok(Json(tags))
+}
+
+/// Suggests tags of the authenticated user for what is being typed
+#[get("/suggest?<q>&<limit>")]
+#[instrument(name = "tag_controller/suggest", skip_all)]
+async fn suggest(
+    app: &AppState,
+    mut db: ConnectionDb,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+    q: &str,
+    limit: Option<i64>,
+) -> Result<Json<Vec<TagUsage>>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let tags = app
+        .use_cases
+        .tag
+        .suggest(&app.repos, &mut db, user_id, q, limit)
+        .await?;
+
+    Ok(Json(tags))
+}
+
+/// Tags of the authenticated user nested under their parent, with the record counts of each subtree
+#[get("/tree")]
+#[instrument(name = "tag_controller/tree", skip_all)]
+async fn tree(
+    app: &AppState,
+    mut db: ConnectionDb,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<Json<Vec<TagNode>>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let tree = app.use_cases.tag.find_tree(&app.repos, &mut db, user_id).await?;
+
+    Ok(Json(tree))
+}
+
+/// Renames a tag, its slug follows the new name, changes its kind or its parent
+#[patch("/<slug>", data = "<body>")]
+#[instrument(name = "tag_controller/update", skip_all)]
+async fn update(
+    app: &AppState,
+    mut db: ConnectionDb,
+    slug: &str,
+    body: Json<TagPatchInput>,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<Json<TagResponse>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let input = body.into_inner();
+    input
+        .validate()
+        .map_err(|e| AppError::ValidationError { errors: e })?;
+
+    let tag = app
+        .use_cases
+        .tag
+        .update(&app.repos, &mut db, user_id, slug, input)
+        .await?;
+
+    Ok(Json(TagResponse::from(tag)))
+}
+
+/// Merges a tag into another one, its records get the other tag
+#[post("/<slug>/merge", data = "<body>")]
+#[instrument(name = "tag_controller/merge", skip_all)]
+async fn merge(
+    app: &AppState,
+    mut db: ConnectionDb,
+    slug: &str,
+    body: Json<TagMergeInput>,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<Json<TagResponse>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let input = body.into_inner();
+    input
+        .validate()
+        .map_err(|e| AppError::ValidationError { errors: e })?;
+
+    let tag = app
+        .use_cases
+        .tag
+        .merge(&app.repos, &mut db, user_id, slug, &input.into)
+        .await?;
+
+    Ok(Json(TagResponse::from(tag)))
+}
+
+/// Deletes a tag and removes it from the records using it
+#[delete("/<slug>")]
+#[instrument(name = "tag_controller/delete", skip_all)]
+async fn delete(
+    app: &AppState,
+    mut db: ConnectionDb,
+    slug: &str,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+) -> Result<Status, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    app.use_cases
+        .tag
+        .delete(&app.repos, &mut db, user_id, slug)
+        .await?;
+
+    Ok(Status::NoContent)
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![index, suggest, tree, update, merge, delete]
+}
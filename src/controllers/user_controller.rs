@@ -1,10 +1,13 @@
 use crate::app::AppState;
 use crate::db::ConnectionDb;
+use crate::dto::backup_dto::{AccountBackup, RestoreReport, MAX_BACKUP_SIZE};
 use crate::dto::user_dto::UserUpdateInput;
 use crate::error::app_error::AppError;
 use crate::models::jwt_model::JwtClaim;
 use crate::models::user_model::User;
 use crate::utils::NetworkResponse;
+use rocket::data::{Data, ToByteUnit};
+use rocket::http::{ContentType, Header};
 use rocket::serde::json::Json;
 use tracing::instrument;
 
@@ -56,8 +59,84 @@ async fn delete(
     Ok(())
 }
 
+/// Backup file, downloaded as an attachment
+#[derive(Responder)]
+struct BackupResponse {
+    inner: (ContentType, Vec<u8>),
+    disposition: Header<'static>,
+}
+
+#[get("/backup?<gzip>")]
+#[instrument(name = "user_controller/backup", skip_all)]
+async fn backup(
+    app: &AppState,
+    mut db: ConnectionDb,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+    gzip: Option<bool>,
+) -> Result<BackupResponse, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let gzip = gzip.unwrap_or(false);
+    let backup = app.use_cases.backup.export(&app.repos, &mut db, user_id).await?;
+    let bytes = backup
+        .encode(gzip)
+        .map_err(|e| AppError::new(500, &format!("Could not write the backup: {}", e)))?;
+
+    let (content_type, filename) = match gzip {
+        true => (ContentType::new("application", "gzip"), "records-backup.json.gz"),
+        false => (ContentType::JSON, "records-backup.json"),
+    };
+    Ok(BackupResponse {
+        inner: (content_type, bytes),
+        disposition: Header::new(
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", filename),
+        ),
+    })
+}
+
+/// Reads an uploaded backup, gzipped or not
+pub(crate) async fn read_backup(data: Data<'_>) -> Result<AccountBackup, AppError> {
+    let bytes = match data.open(MAX_BACKUP_SIZE.bytes()).into_bytes().await {
+        Ok(bytes) => bytes,
+        Err(e) => return Err(AppError::new(500, &format!("Failed to read file: {}", e))),
+    };
+
+    if !bytes.is_complete() {
+        return Err(AppError::new(413, "File too large (max 64MB)"));
+    }
+
+    AccountBackup::decode(&bytes.value)
+        .map_err(|e| AppError::new(400, &format!("Invalid backup file: {}", e)))
+}
+
+#[post("/restore", data = "<data>")]
+#[instrument(name = "user_controller/restore", skip_all)]
+async fn restore(
+    app: &AppState,
+    mut db: ConnectionDb,
+    jwt_claim: Result<JwtClaim, NetworkResponse>,
+    data: Data<'_>,
+) -> Result<Json<RestoreReport>, AppError> {
+    let user_id = jwt_claim
+        .map_err(|_| AppError::Unauthorized)
+        .map(|key| key.sub)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let backup = read_backup(data).await?;
+    let report = app
+        .use_cases
+        .backup
+        .restore(&app.repos, &mut db, Some(user_id), backup)
+        .await?;
+    Ok(Json(report))
+}
+
 pub fn routes() -> Vec<rocket::Route> {
-    routes![index, update, delete]
+    routes![index, update, delete, backup, restore]
 }
 
 #[cfg(test)]
@@ -1,3 +1,4 @@
+use rocket_db_pools::deadpool_redis;
 use rocket_db_pools::sqlx;
 use rocket_db_pools::Connection;
 use rocket_db_pools::Database;
@@ -7,6 +8,14 @@ use sqlx::{pool::PoolConnection, Postgres};
 pub type ConnectionDb = Connection<Db>;
 pub type DbCon = PoolConnection<Postgres>;
 
+pub type ConnectionCache = Connection<Cache>;
+pub type CacheCon = deadpool_redis::Connection;
+
 #[derive(Database)]
 #[database("records")]
 pub struct Db(sqlx::PgPool);
+
+/// Redis cache, requests go on without it when it is unreachable
+#[derive(Database)]
+#[database("cache")]
+pub struct Cache(deadpool_redis::Pool);
@@ -0,0 +1,185 @@
+use crate::dto::record_dto::{RecordInput, ADDED_AT_FORMAT};
+use crate::dto::user_dto::VALID_USERNAME_REGEX;
+use crate::models::record_model::Record;
+use crate::models::tag_model::TagResponse;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use validator::{Validate, ValidationError};
+
+/// Version written in new backups, restoring a newer version is refused.
+/// Version 2 added the parent of each tag
+pub const BACKUP_VERSION: u32 = 2;
+
+/// Maximum size of a backup once decompressed
+pub const MAX_BACKUP_SIZE: u64 = 64 * 1024 * 1024;
+
+/// First bytes of a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Everything needed to recreate an account on another instance
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AccountBackup {
+    pub version: u32,
+    pub exported_at: chrono::NaiveDateTime,
+    pub profile: BackupProfile,
+    /// Tags of the user, unused ones included
+    pub tags: Vec<BackupTag>,
+    pub records: Vec<BackupRecord>,
+    /// Token sharing the collection, kept so shared links still work
+    pub collection_token: Option<String>,
+}
+
+/// Checked with the rules of a registration when it creates the account
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct BackupProfile {
+    #[validate(email(message = "Invalid email address"))]
+    pub email: String,
+    #[validate(
+        length(min = 3, message = "Username must be at least 3 characters long"),
+        regex(path = "VALID_USERNAME_REGEX", message = "Username can only contain alphanumeric characters and underscores")
+    )]
+    pub username: String,
+    /// Bcrypt hash, lets the account log in with the same password once restored
+    #[validate(custom(function = "validate_password_hash"))]
+    pub password_hash: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Logging in verifies the password against the hash, anything but bcrypt can't be checked
+fn validate_password_hash(password_hash: &str) -> Result<(), ValidationError> {
+    match password_hash.parse::<bcrypt::HashParts>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ValidationError::new("password_hash").with_message("Password hash must be a bcrypt hash".into())),
+    }
+}
+
+/// A tag with its kind, its parent given by slug
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BackupTag {
+    #[serde(flatten)]
+    pub tag: TagResponse,
+    /// Missing from version 1 backups, their tags are restored as roots
+    #[serde(default)]
+    pub parent: Option<String>,
+}
+
+/// A record without the ids of the instance it comes from
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BackupRecord {
+    pub title: String,
+    pub artist: String,
+    pub release_date: chrono::NaiveDate,
+    pub cover_url: String,
+    pub discogs_url: Option<String>,
+    pub spotify_url: Option<String>,
+    /// Missing from backups made before records had one
+    #[serde(default)]
+    pub musicbrainz_id: Option<String>,
+    pub owned: bool,
+    pub wanted: bool,
+    pub created_at: chrono::NaiveDateTime,
+    pub catalog_number: Option<String>,
+    pub format: Option<String>,
+    pub rating: Option<i32>,
+    pub collection_folder: Option<String>,
+    pub media_condition: Option<String>,
+    pub sleeve_condition: Option<String>,
+    pub notes: Option<String>,
+    /// Tag names
+    pub tags: Vec<String>,
+}
+
+impl From<Record> for BackupRecord {
+    fn from(record: Record) -> Self {
+        Self {
+            title: record.title,
+            artist: record.artist,
+            release_date: record.release_date,
+            cover_url: record.cover_url,
+            discogs_url: record.discogs_url,
+            spotify_url: record.spotify_url,
+            musicbrainz_id: record.musicbrainz_id,
+            owned: record.owned,
+            wanted: record.wanted,
+            created_at: record.created_at,
+            catalog_number: record.catalog_number,
+            format: record.format,
+            rating: record.rating,
+            collection_folder: record.collection_folder,
+            media_condition: record.media_condition,
+            sleeve_condition: record.sleeve_condition,
+            notes: record.notes,
+            tags: record
+                .tags
+                .unwrap_or_default()
+                .iter()
+                .map(TagResponse::qualified_name)
+                .collect(),
+        }
+    }
+}
+
+impl From<BackupRecord> for RecordInput {
+    fn from(record: BackupRecord) -> Self {
+        Self {
+            title: record.title,
+            artist: record.artist,
+            release_date: record.release_date.format("%Y-%m-%d").to_string(),
+            cover_url: record.cover_url,
+            discogs_url: record.discogs_url,
+            spotify_url: record.spotify_url,
+            musicbrainz_id: record.musicbrainz_id,
+            owned: Some(record.owned),
+            wanted: Some(record.wanted),
+            created_at: Some(record.created_at.format(ADDED_AT_FORMAT).to_string()),
+            catalog_number: record.catalog_number,
+            format: record.format,
+            rating: record.rating,
+            collection_folder: record.collection_folder,
+            media_condition: record.media_condition,
+            sleeve_condition: record.sleeve_condition,
+            notes: record.notes,
+            tags: Some(record.tags),
+        }
+    }
+}
+
+impl AccountBackup {
+    /// JSON document, gzipped when asked
+    pub fn encode(&self, gzip: bool) -> std::io::Result<Vec<u8>> {
+        let json = serde_json::to_vec(self)?;
+        if !gzip {
+            return Ok(json);
+        }
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&json)?;
+        encoder.finish()
+    }
+
+    /// Reads a backup written by `encode`, gzipped or not
+    pub fn decode(bytes: &[u8]) -> std::io::Result<Self> {
+        let backup = if bytes.starts_with(&GZIP_MAGIC) {
+            let mut json = Vec::new();
+            GzDecoder::new(bytes).take(MAX_BACKUP_SIZE).read_to_end(&mut json)?;
+            serde_json::from_slice(&json)?
+        } else {
+            serde_json::from_slice(bytes)?
+        };
+        Ok(backup)
+    }
+}
+
+/// Outcome of a restore
+#[derive(Deserialize, Serialize, Debug, Default, PartialEq)]
+pub struct RestoreReport {
+    /// Account the backup was restored into
+    pub user_id: i32,
+    pub records: usize,
+    pub tags: usize,
+    /// Token of the restored collection, a new one when the original is used by another account
+    pub collection_token: Option<String>,
+}
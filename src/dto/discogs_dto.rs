@@ -26,6 +26,7 @@ pub struct Urls {
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DiscogsRecord {
+    #[serde(default)]
     pub country: String,
     pub year: Option<String>,
     pub format: Vec<String>,
@@ -38,10 +39,11 @@ pub struct DiscogsRecord {
     pub barcode: Vec<String>,
     #[serde(rename = "user_data")]
     pub user_data: UserData,
-    #[serde(rename = "master_id")]
+    #[serde(rename = "master_id", default)]
     pub master_id: i64,
-    #[serde(rename = "master_url")]
-    pub master_url: String,
+    /// Missing from releases without a master
+    #[serde(rename = "master_url", default)]
+    pub master_url: Option<String>,
     pub uri: String,
     pub catno: String,
     pub title: String,
@@ -68,3 +70,60 @@ pub struct Community {
     pub want: i64,
     pub have: i64,
 }
+
+/// A release or a master from the Discogs database, masters have no labels nor formats
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscogsRelease {
+    pub id: i64,
+    pub title: String,
+    #[serde(default)]
+    pub artists: Vec<DiscogsArtist>,
+    #[serde(default)]
+    pub year: Option<i32>,
+    /// Release date of a pressing, "2001-03-12", with zeroes or missing parts when unknown
+    #[serde(default)]
+    pub released: Option<String>,
+    #[serde(default)]
+    pub images: Vec<DiscogsImage>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    #[serde(default)]
+    pub styles: Vec<String>,
+    #[serde(default)]
+    pub labels: Vec<DiscogsLabel>,
+    #[serde(default)]
+    pub formats: Vec<DiscogsFormat>,
+    /// Pressing a master is usually shown with
+    #[serde(default)]
+    pub main_release: Option<i64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscogsArtist {
+    pub name: String,
+    /// Text joining this artist to the next one, such as "&" or ","
+    #[serde(default)]
+    pub join: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscogsImage {
+    /// "primary" or "secondary"
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub uri: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscogsLabel {
+    pub name: String,
+    #[serde(default)]
+    pub catno: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscogsFormat {
+    pub name: String,
+    #[serde(default)]
+    pub descriptions: Vec<String>,
+}
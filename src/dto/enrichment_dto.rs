@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// A record the enrichment could not look up, or only in part
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct EnrichmentError {
+    pub record_id: i32,
+    pub message: String,
+}
+
+impl EnrichmentError {
+    pub fn new(record_id: i32, message: &str) -> Self {
+        Self {
+            record_id,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Outcome of the lookups of a chunk of records
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct EnrichmentReport {
+    /// Records updated with new metadata
+    pub enriched: usize,
+    /// Records the providers had nothing to add to
+    pub unchanged: usize,
+    /// Records that could not be looked up
+    pub failed: usize,
+    /// Why records failed, or what could not be added to an enriched one
+    pub errors: Vec<EnrichmentError>,
+}
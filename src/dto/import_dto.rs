@@ -0,0 +1,168 @@
+use crate::dto::record_dto::RecordInput;
+use serde::{Deserialize, Serialize};
+
+/// Maximum size of a CSV imported while the request waits
+pub const MAX_IMPORT_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Maximum size of a CSV queued as a background job, the worker imports it in chunks
+pub const MAX_BACKGROUND_IMPORT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// How rows matching an existing `discogs_url` are handled during an import
+#[derive(FromFormField, Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Only insert new records, rows already in the collection are skipped
+    #[default]
+    Create,
+    /// Insert new records and update the ones already in the collection
+    Upsert,
+}
+
+impl ImportMode {
+    /// Value of the `mode` query parameter
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportMode::Create => "create",
+            ImportMode::Upsert => "upsert",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "create" => Some(ImportMode::Create),
+            "upsert" => Some(ImportMode::Upsert),
+            _ => None,
+        }
+    }
+}
+
+/// A problem found on a CSV row, `field` is `csv` when the row itself could not be read
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ImportRowError {
+    pub row: usize,
+    pub field: String,
+    pub message: String,
+}
+
+impl ImportRowError {
+    pub fn new(row: usize, field: &str, message: &str) -> Self {
+        Self {
+            row,
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// A parsed CSV row, `record` holds the errors when the row could not be used
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ImportRow {
+    pub row: usize,
+    pub record: Result<RecordInput, Vec<ImportRowError>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportRowStatus {
+    Created,
+    Updated,
+    Unchanged,
+    Skipped,
+}
+
+/// Outcome of a single CSV row
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ImportRowResult {
+    pub row: usize,
+    pub status: ImportRowStatus,
+    pub record_id: Option<i32>,
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub errors: Vec<ImportRowError>,
+}
+
+/// Summary of an import, with one entry per CSV row
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct ImportReport {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+impl ImportReport {
+    pub fn push(
+        &mut self,
+        row: usize,
+        status: ImportRowStatus,
+        record_id: Option<i32>,
+        message: Option<String>,
+    ) {
+        match status {
+            ImportRowStatus::Created => self.created += 1,
+            ImportRowStatus::Updated => self.updated += 1,
+            ImportRowStatus::Unchanged => self.unchanged += 1,
+            ImportRowStatus::Skipped => self.skipped += 1,
+        }
+
+        self.rows.push(ImportRowResult {
+            row,
+            status,
+            record_id,
+            message,
+            errors: Vec::new(),
+        });
+    }
+
+    /// Errors of the skipped rows, a skip reason is reported on the `discogs_url` field
+    pub fn row_errors(&self) -> Vec<ImportRowError> {
+        self.rows
+            .iter()
+            .filter(|result| result.status == ImportRowStatus::Skipped)
+            .flat_map(|result| {
+                if result.errors.is_empty() {
+                    let message = result.message.clone().unwrap_or_default();
+                    vec![ImportRowError::new(result.row, "discogs_url", &message)]
+                } else {
+                    result.errors.clone()
+                }
+            })
+            .collect()
+    }
+
+    /// Records a row skipped because of the given errors
+    pub fn push_invalid(&mut self, row: usize, errors: Vec<ImportRowError>) {
+        self.push(row, ImportRowStatus::Skipped, None, Some("Invalid row".to_string()));
+        if let Some(result) = self.rows.last_mut() {
+            result.errors = errors;
+        }
+    }
+}
+
+/// A row that a dry run would insert
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ImportPreviewRow {
+    pub row: usize,
+    pub title: String,
+    pub artist: String,
+    pub discogs_url: Option<String>,
+}
+
+/// A row whose `discogs_url` is already in the collection
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ImportConflict {
+    pub row: usize,
+    pub record_id: i32,
+    pub title: String,
+    pub artist: String,
+    pub discogs_url: String,
+}
+
+/// What an import would do, computed without writing anything
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct ImportPreview {
+    pub to_create: Vec<ImportPreviewRow>,
+    pub conflicts: Vec<ImportConflict>,
+    pub errors: Vec<ImportRowError>,
+}
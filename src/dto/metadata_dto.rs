@@ -0,0 +1,99 @@
+use crate::models::tag_model::{Tag, TagKind};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// An album as described by a metadata provider
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ProviderAlbum {
+    pub title: String,
+    pub artist: String,
+    pub release_date: NaiveDate,
+    pub cover_url: Option<String>,
+    pub discogs_url: Option<String>,
+    pub spotify_url: Option<String>,
+    /// MusicBrainz release group id
+    #[serde(default)]
+    pub musicbrainz_id: Option<String>,
+
+    /// Details only known when a single release is fetched
+    #[serde(default)]
+    pub genres: Vec<String>,
+    /// Finer grained than genres, such as "Deep House" for "Electronic"
+    #[serde(default)]
+    pub styles: Vec<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub catalog_number: Option<String>,
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+impl ProviderAlbum {
+    /// Genres, styles and label as tag names prefixed with their kind, such as `genre:Jazz`
+    pub fn tags(&self) -> Vec<String> {
+        let candidates = self
+            .genres
+            .iter()
+            .map(|name| (TagKind::Genre, name))
+            .chain(self.styles.iter().map(|name| (TagKind::Style, name)))
+            .chain(self.label.iter().map(|name| (TagKind::Label, name)));
+
+        let mut tags: Vec<String> = Vec::new();
+        for (kind, name) in candidates {
+            let name = name.trim();
+            let slug = Tag::slugify(name);
+            if !slug.is_empty() && !tags.iter().any(|tag| Tag::slug_of(tag) == slug) {
+                tags.push(Tag::qualify(kind.as_str(), name));
+            }
+        }
+        tags
+    }
+}
+
+/// What a Discogs id points at
+#[derive(FromFormField, Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscogsKind {
+    /// A specific pressing
+    #[default]
+    Release,
+    /// The album grouping every pressing
+    Master,
+}
+
+impl DiscogsKind {
+    /// Path segment of the Discogs website
+    pub fn path(&self) -> &'static str {
+        match self {
+            DiscogsKind::Release => "release",
+            DiscogsKind::Master => "master",
+        }
+    }
+}
+
+/// Code printed on a sleeve, scanned or typed to find the release
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReleaseCode {
+    /// EAN or UPC barcode
+    Barcode(String),
+    /// Catalog number given by the label, such as "7243 8 49606 1 2"
+    CatalogNumber(String),
+}
+
+impl ReleaseCode {
+    /// Lowercased letters and digits only, "7243 8 49606 1 2" and "724384960612" are the same code
+    pub fn normalized(&self) -> String {
+        let (ReleaseCode::Barcode(code) | ReleaseCode::CatalogNumber(code)) = self;
+        code.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    }
+}
+
+/// Reference to a single release on a provider
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReleaseId {
+    Discogs { kind: DiscogsKind, id: i64 },
+}
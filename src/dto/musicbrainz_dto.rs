@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+/// Release groups matching a MusicBrainz search
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MusicBrainzSearch {
+    #[serde(rename = "release-groups", default)]
+    pub release_groups: Vec<MusicBrainzReleaseGroup>,
+}
+
+/// The album grouping every release of it, like a Discogs master
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MusicBrainzReleaseGroup {
+    pub id: String,
+    pub title: String,
+    /// "2001-03-12", "2001-03" or "2001", empty when unknown
+    #[serde(rename = "first-release-date", default)]
+    pub first_release_date: String,
+    #[serde(rename = "artist-credit", default)]
+    pub artist_credit: Vec<MusicBrainzArtistCredit>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MusicBrainzArtistCredit {
+    /// Name as credited on the release group
+    pub name: String,
+    /// Text joining this artist to the next one, such as " & "
+    #[serde(default)]
+    pub joinphrase: String,
+}
+
+/// Images the Cover Art Archive has for a release
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoverArtRoot {
+    #[serde(default)]
+    pub images: Vec<CoverArtImage>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoverArtImage {
+    #[serde(default)]
+    pub front: bool,
+    /// Full size image, often several megabytes
+    pub image: String,
+    #[serde(default)]
+    pub thumbnails: CoverArtThumbnails,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoverArtThumbnails {
+    #[serde(rename = "500", default)]
+    pub medium: Option<String>,
+    #[serde(default)]
+    pub large: Option<String>,
+}
@@ -1,5 +1,15 @@
-use serde::{Deserialize, Serialize};
-use validator::Validate;
+use crate::models::record_model::Record;
+use crate::models::tag_model::{Tag, TagResponse};
+use base64::Engine;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+use validator::{Validate, ValidationError};
+
+/// Default number of records returned by a listing
+pub const DEFAULT_PAGE_LIMIT: i64 = 50;
+/// Maximum number of records a client can request in one page
+pub const MAX_PAGE_LIMIT: i64 = 500;
 
 #[derive(Deserialize, Serialize, FromForm, Debug, Validate)]
 pub struct RecordInput {
@@ -9,10 +19,7 @@ pub struct RecordInput {
     #[validate(length(min = 1, message = "Artist is required"))]
     pub artist: String,
 
-    #[validate(length(
-        min = 8,
-        message = "Release date must be at least 8 characters long (e.g. 2025-01-01)"
-    ))]
+    #[validate(custom(function = "validate_release_date"))]
     pub release_date: String,
 
     #[validate(url(message = "Cover URL is not a valid URL"))]
@@ -24,10 +31,562 @@ pub struct RecordInput {
     #[validate(url(message = "Spotify URL is not a valid URL"))]
     pub spotify_url: Option<String>,
 
+    /// MusicBrainz release group id
+    #[validate(length(equal = 36, message = "MusicBrainz id must be a release group id"))]
+    pub musicbrainz_id: Option<String>,
+
     pub owned: Option<bool>,
 
     pub wanted: Option<bool>,
 
+    /// When the record was added to the collection (e.g. 2025-03-23 10:40:35), defaults to now
+    #[validate(custom(function = "validate_added_at"))]
+    pub created_at: Option<String>,
+
+    pub catalog_number: Option<String>,
+
+    pub format: Option<String>,
+
+    #[validate(range(min = 0, max = 5, message = "Rating must be between 0 and 5"))]
+    pub rating: Option<i32>,
+
+    pub collection_folder: Option<String>,
+
+    pub media_condition: Option<String>,
+
+    pub sleeve_condition: Option<String>,
+
+    pub notes: Option<String>,
+
     /// Tags associated with this record (tag names)
     pub tags: Option<Vec<String>>,
 }
+
+impl RecordInput {
+    /// Parsed `created_at`, `None` when missing or malformed
+    pub fn added_at(&self) -> Option<NaiveDateTime> {
+        self.created_at
+            .as_deref()
+            .and_then(|created_at| NaiveDateTime::parse_from_str(created_at, ADDED_AT_FORMAT).ok())
+    }
+}
+
+/// Partial update of a record, only the provided fields are changed. Optional fields set to
+/// `null` are cleared
+#[derive(Deserialize, Serialize, Debug, Default, Validate)]
+pub struct RecordPatchInput {
+    #[validate(length(min = 1, message = "Title is required"))]
+    pub title: Option<String>,
+
+    #[validate(length(min = 1, message = "Artist is required"))]
+    pub artist: Option<String>,
+
+    #[validate(custom(function = "validate_release_date"))]
+    pub release_date: Option<String>,
+
+    #[validate(url(message = "Cover URL is not a valid URL"))]
+    pub cover_url: Option<String>,
+
+    #[validate(url(message = "Discogs URL is not a valid URL"))]
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub discogs_url: Option<Option<String>>,
+
+    #[validate(url(message = "Spotify URL is not a valid URL"))]
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub spotify_url: Option<Option<String>>,
+
+    /// MusicBrainz release group id
+    #[validate(length(equal = 36, message = "MusicBrainz id must be a release group id"))]
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub musicbrainz_id: Option<Option<String>>,
+
+    pub owned: Option<bool>,
+
+    pub wanted: Option<bool>,
+
+    #[validate(custom(function = "validate_added_at"))]
+    pub created_at: Option<String>,
+
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub catalog_number: Option<Option<String>>,
+
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub format: Option<Option<String>>,
+
+    #[validate(range(min = 0, max = 5, message = "Rating must be between 0 and 5"))]
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub rating: Option<Option<i32>>,
+
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub collection_folder: Option<Option<String>>,
+
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub media_condition: Option<Option<String>>,
+
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub sleeve_condition: Option<Option<String>>,
+
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub notes: Option<Option<String>>,
+
+    /// Replaces the record tags when provided (tag names)
+    pub tags: Option<Vec<String>>,
+}
+
+// Tells a `null` field apart from a missing one
+fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Format of release dates, in inputs and in the database
+pub const RELEASE_DATE_FORMAT: &str = "%Y-%m-%d";
+
+fn validate_release_date(release_date: &str) -> Result<(), ValidationError> {
+    if NaiveDate::parse_from_str(release_date, RELEASE_DATE_FORMAT).is_ok() {
+        return Ok(());
+    }
+
+    Err(ValidationError::new("invalid_release_date")
+        .with_message(Cow::Borrowed("Release date must look like 2025-01-01")))
+}
+
+/// Format of the date a record was added, as found in Discogs exports
+pub const ADDED_AT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+fn validate_added_at(created_at: &str) -> Result<(), ValidationError> {
+    if NaiveDateTime::parse_from_str(created_at, ADDED_AT_FORMAT).is_ok() {
+        return Ok(());
+    }
+
+    Err(ValidationError::new("invalid_added_at")
+        .with_message(Cow::Borrowed("Added date must look like 2025-01-01 12:00:00")))
+}
+
+/// Field used to order a record listing
+#[derive(FromFormField, Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordSort {
+    Artist,
+    Title,
+    #[field(value = "release_date")]
+    ReleaseDate,
+    #[default]
+    Added,
+}
+
+impl RecordSort {
+    /// Value of the `sort` query parameter
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordSort::Artist => "artist",
+            RecordSort::Title => "title",
+            RecordSort::ReleaseDate => "release_date",
+            RecordSort::Added => "added",
+        }
+    }
+
+    /// Column of the `records` table backing this sort
+    pub fn column(&self) -> &'static str {
+        match self {
+            RecordSort::Artist => "artist",
+            RecordSort::Title => "title",
+            RecordSort::ReleaseDate => "release_date",
+            RecordSort::Added => "created_at",
+        }
+    }
+
+    /// Alphabetical sorts go ascending, dates show the latest first
+    pub fn default_direction(&self) -> SortDirection {
+        match self {
+            RecordSort::Artist | RecordSort::Title => SortDirection::Asc,
+            RecordSort::ReleaseDate | RecordSort::Added => SortDirection::Desc,
+        }
+    }
+}
+
+#[derive(FromFormField, Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    /// Value of the `direction` query parameter
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        }
+    }
+
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        }
+    }
+
+    /// Comparison operator selecting the rows after a cursor
+    pub fn comparator(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => ">",
+            SortDirection::Desc => "<",
+        }
+    }
+}
+
+/// Keyset pagination parameters of a record listing
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct RecordPagination {
+    pub limit: Option<i64>,
+    /// Opaque cursor returned as `next_cursor` by the previous page
+    pub after: Option<String>,
+    pub sort: Option<RecordSort>,
+    pub direction: Option<SortDirection>,
+}
+
+impl RecordPagination {
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_LIMIT)
+            .clamp(1, MAX_PAGE_LIMIT)
+    }
+
+    pub fn sort(&self) -> RecordSort {
+        self.sort.unwrap_or_default()
+    }
+
+    pub fn direction(&self) -> SortDirection {
+        self.direction
+            .unwrap_or_else(|| self.sort().default_direction())
+    }
+
+    /// Decoded `after` cursor, `None` when absent or malformed
+    pub fn cursor(&self) -> Option<RecordCursor> {
+        self.after
+            .as_deref()
+            .and_then(|after| RecordCursor::decode(after, self.sort()))
+    }
+
+    /// Whether the `after` cursor, if any, can be decoded for the current sort
+    pub fn is_valid(&self) -> bool {
+        self.after.is_none() || self.cursor().is_some()
+    }
+}
+
+/// Input replacing a record with its current values, to update some of them
+impl From<Record> for RecordInput {
+    fn from(record: Record) -> Self {
+        Self {
+            title: record.title,
+            artist: record.artist,
+            release_date: record.release_date.format("%Y-%m-%d").to_string(),
+            cover_url: record.cover_url,
+            discogs_url: record.discogs_url,
+            spotify_url: record.spotify_url,
+            musicbrainz_id: record.musicbrainz_id,
+            owned: Some(record.owned),
+            wanted: Some(record.wanted),
+            created_at: Some(record.created_at.format(ADDED_AT_FORMAT).to_string()),
+            catalog_number: record.catalog_number,
+            format: record.format,
+            rating: record.rating,
+            collection_folder: record.collection_folder,
+            media_condition: record.media_condition,
+            sleeve_condition: record.sleeve_condition,
+            notes: record.notes,
+            tags: Some(record.tags.unwrap_or_default().iter().map(TagResponse::qualified_name).collect()),
+        }
+    }
+}
+
+/// Start of the covers shown for records without artwork
+pub const PLACEHOLDER_COVER_PREFIX: &str = "https://via.placeholder.com/";
+
+/// Cover shown for records without artwork
+pub fn placeholder_cover_url(artist: &str, title: &str) -> String {
+    format!(
+        "{}300x300?text={}",
+        PLACEHOLDER_COVER_PREFIX,
+        urlencoding::encode(&format!("{} - {}", artist, title))
+    )
+}
+
+/// Number of records fetched per query while exporting a collection
+pub const EXPORT_PAGE_SIZE: i64 = 200;
+
+/// File format of a collection export
+#[derive(FromFormField, Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// Same layout as a Discogs collection export, with an extra `Tags` column
+    Csv,
+    #[default]
+    Json,
+    /// One JSON record per line
+    Ndjson,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+/// How the `tag` filters of a listing are combined
+#[derive(FromFormField, Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatch {
+    /// Records having at least one of the tags
+    #[default]
+    Any,
+    /// Records having every tag
+    All,
+}
+
+/// Filters of a user's collection, shared by the listing, random pick and shared views
+#[derive(FromForm, Deserialize, Serialize, Debug, Clone, Default)]
+pub struct RecordFilter {
+    pub owned: Option<bool>,
+    pub wanted: Option<bool>,
+    /// Tag names or slugs, repeatable (`tag=jazz&tag=soul`).
+    /// A kind prefix only matches tags of that kind (`tag=genre:jazz`)
+    #[field(name = "tag")]
+    pub tags: Vec<String>,
+    pub tag_match: Option<TagMatch>,
+    /// Artist name, case insensitive
+    pub artist: Option<String>,
+    /// First release year, inclusive
+    pub released_from: Option<i32>,
+    /// Last release year, inclusive
+    pub released_to: Option<i32>,
+    /// Free text matched against titles and artists
+    pub q: Option<String>,
+}
+
+impl RecordFilter {
+    /// Distinct slugs of the requested tags
+    pub fn tag_slugs(&self) -> Vec<String> {
+        let mut slugs: Vec<String> = self
+            .tags
+            .iter()
+            .map(|tag| Tag::slug_of(tag))
+            .filter(|slug| !slug.is_empty())
+            .collect();
+        slugs.sort();
+        slugs.dedup();
+        slugs
+    }
+
+    /// Slugs of the requested tags matching any kind, and `kind:slug` pairs
+    /// of the ones restricted to a kind
+    pub fn tag_filters(&self) -> (Vec<String>, Vec<String>) {
+        let mut any_kind = Vec::new();
+        let mut of_kind = Vec::new();
+        for tag in &self.tags {
+            let (kind, name) = Tag::parse(tag);
+            let slug = Tag::slugify(name);
+            match kind {
+                _ if slug.is_empty() => {}
+                Some(kind) => of_kind.push(format!("{}:{}", kind.as_str(), slug)),
+                None => any_kind.push(slug),
+            }
+        }
+        for filters in [&mut any_kind, &mut of_kind] {
+            filters.sort();
+            filters.dedup();
+        }
+        (any_kind, of_kind)
+    }
+
+    pub fn released_from_date(&self) -> Option<NaiveDate> {
+        self.released_from
+            .and_then(|year| NaiveDate::from_ymd_opt(year, 1, 1))
+    }
+
+    pub fn released_to_date(&self) -> Option<NaiveDate> {
+        self.released_to
+            .and_then(|year| NaiveDate::from_ymd_opt(year, 12, 31))
+    }
+
+    /// `ILIKE` pattern of the free text query, `None` when blank
+    pub fn q_pattern(&self) -> Option<String> {
+        self.q
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .map(|q| format!("%{}%", escape_like(q)))
+    }
+}
+
+/// Escapes the `LIKE` wildcards of a user provided string
+fn escape_like(input: &str) -> String {
+    input
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Value of the sorted column of the last record of a page
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordCursorValue {
+    Text(String),
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+}
+
+/// Position of the last record of a page, encoded as `base64("<id>:<value>")`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordCursor {
+    pub id: i32,
+    pub value: RecordCursorValue,
+}
+
+const CURSOR_DATE_FORMAT: &str = "%Y-%m-%d";
+const CURSOR_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+impl RecordCursor {
+    pub fn from_record(record: &Record, sort: RecordSort) -> Self {
+        let value = match sort {
+            RecordSort::Artist => RecordCursorValue::Text(record.artist.clone()),
+            RecordSort::Title => RecordCursorValue::Text(record.title.clone()),
+            RecordSort::ReleaseDate => RecordCursorValue::Date(record.release_date),
+            RecordSort::Added => RecordCursorValue::Timestamp(record.created_at),
+        };
+        Self { id: record.id, value }
+    }
+
+    pub fn encode(&self) -> String {
+        let value = match &self.value {
+            RecordCursorValue::Text(text) => text.clone(),
+            RecordCursorValue::Date(date) => date.format(CURSOR_DATE_FORMAT).to_string(),
+            RecordCursorValue::Timestamp(timestamp) => {
+                timestamp.format(CURSOR_TIMESTAMP_FORMAT).to_string()
+            }
+        };
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(format!("{}:{}", self.id, value))
+    }
+
+    pub fn decode(cursor: &str, sort: RecordSort) -> Option<Self> {
+        let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()?;
+        let decoded = String::from_utf8(bytes).ok()?;
+        let (id, value) = decoded.split_once(':')?;
+
+        let value = match sort {
+            RecordSort::Artist | RecordSort::Title => RecordCursorValue::Text(value.to_string()),
+            RecordSort::ReleaseDate => RecordCursorValue::Date(
+                NaiveDate::parse_from_str(value, CURSOR_DATE_FORMAT).ok()?,
+            ),
+            RecordSort::Added => RecordCursorValue::Timestamp(
+                NaiveDateTime::parse_from_str(value, CURSOR_TIMESTAMP_FORMAT).ok()?,
+            ),
+        };
+
+        Some(Self {
+            id: id.parse().ok()?,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::fixture::record::{record_fixture, record_input_fixture};
+
+    #[test]
+    fn test_release_date_validation() {
+        let input = |release_date: &str| RecordInput {
+            release_date: release_date.to_string(),
+            ..record_input_fixture(1)
+        };
+        assert!(input("2025-01-01").validate().is_ok());
+        for release_date in ["2025/01/01", "2025-13-01", "01-01-2025"] {
+            let errors = input(release_date).validate().unwrap_err();
+            assert!(errors.field_errors().contains_key("release_date"));
+        }
+
+        let patch = RecordPatchInput {
+            release_date: Some("2025/01/01".to_string()),
+            ..Default::default()
+        };
+        assert!(patch.validate().is_err());
+        assert!(RecordPatchInput::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_patch_tells_null_from_missing() {
+        let patch: RecordPatchInput = serde_json::from_str(r#"{ "spotify_url": null, "rating": 4 }"#).unwrap();
+        assert_eq!(patch.spotify_url, Some(None));
+        assert_eq!(patch.rating, Some(Some(4)));
+        assert_eq!(patch.discogs_url, None);
+        assert!(patch.validate().is_ok());
+
+        let patch: RecordPatchInput = serde_json::from_str(r#"{ "spotify_url": "spotify", "rating": 9 }"#).unwrap();
+        let errors = patch.validate().unwrap_err();
+        assert!(errors.field_errors().contains_key("spotify_url"));
+        assert!(errors.field_errors().contains_key("rating"));
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let record = record_fixture(42);
+        for sort in [
+            RecordSort::Artist,
+            RecordSort::Title,
+            RecordSort::ReleaseDate,
+            RecordSort::Added,
+        ] {
+            let cursor = RecordCursor::from_record(&record, sort);
+            assert_eq!(RecordCursor::decode(&cursor.encode(), sort), Some(cursor));
+        }
+    }
+
+    #[test]
+    fn test_invalid_cursor() {
+        let pagination = RecordPagination {
+            after: Some("not a cursor".to_string()),
+            sort: Some(RecordSort::ReleaseDate),
+            ..Default::default()
+        };
+        assert!(!pagination.is_valid());
+        assert_eq!(pagination.limit(), DEFAULT_PAGE_LIMIT);
+    }
+
+    #[test]
+    fn test_filter_helpers() {
+        let filter = RecordFilter {
+            tags: vec!["Hip Hop".to_string(), "hip-hop".to_string(), "Jazz".to_string()],
+            released_from: Some(1990),
+            released_to: Some(1999),
+            q: Some(" 100%_pure ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(filter.tag_slugs(), vec!["hip-hop", "jazz"]);
+        assert_eq!(filter.tag_filters(), (vec!["hip-hop".to_string(), "jazz".to_string()], Vec::new()));
+        assert_eq!(filter.released_from_date(), NaiveDate::from_ymd_opt(1990, 1, 1));
+        assert_eq!(filter.released_to_date(), NaiveDate::from_ymd_opt(1999, 12, 31));
+        assert_eq!(filter.q_pattern().as_deref(), Some("%100\\%\\_pure%"));
+
+        let filter = RecordFilter {
+            tags: vec!["genre:Jazz".to_string(), "Label:Blue Note".to_string(), "jazz".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(filter.tag_slugs(), vec!["blue-note", "jazz"]);
+        assert_eq!(
+            filter.tag_filters(),
+            (vec!["jazz".to_string()], vec!["genre:jazz".to_string(), "label:blue-note".to_string()])
+        );
+    }
+}
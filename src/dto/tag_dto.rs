@@ -0,0 +1,42 @@
+use crate::models::tag_model::TagKind;
+use serde::{Deserialize, Deserializer, Serialize};
+use validator::Validate;
+
+/// Default number of tags suggested for a query
+pub const DEFAULT_SUGGEST_LIMIT: i64 = 10;
+/// Maximum number of tags a client can request as suggestions
+pub const MAX_SUGGEST_LIMIT: i64 = 50;
+
+/// Changes of a tag, missing fields are kept
+#[derive(Deserialize, Serialize, Debug, Clone, Default, Validate)]
+pub struct TagPatchInput {
+    #[validate(length(min = 1, message = "Name is required"))]
+    pub name: Option<String>,
+    pub kind: Option<TagKind>,
+    /// Slug of the tag to nest this one under, `null` makes it a root tag
+    #[serde(default, deserialize_with = "deserialize_parent")]
+    pub parent: Option<Option<String>>,
+}
+
+// Tells a `null` parent apart from a missing one
+fn deserialize_parent<'de, D>(deserializer: D) -> Result<Option<Option<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct TagMergeInput {
+    /// Slug of the tag receiving the records
+    #[validate(length(min = 1, message = "Target tag is required"))]
+    pub into: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct RecordTagsInput {
+    /// Names of the tags, created when the user has no tag with the same slug.
+    /// A kind prefix such as `mood:` gives their kind
+    #[validate(length(min = 1, message = "At least one tag is required"))]
+    pub tags: Vec<String>,
+}
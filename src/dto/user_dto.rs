@@ -7,7 +7,7 @@ use zxcvbn::{zxcvbn, Score};
 use regex::Regex;
 use std::sync::LazyLock;
 
-static VALID_USERNAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+pub static VALID_USERNAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^[a-zA-Z0-9_]+$").unwrap()
 });
 
@@ -24,6 +24,18 @@ pub enum AppError {
     #[error("Validation error")]
     ValidationError { errors: ValidationErrors },
 
+    /// An external service failed, or its circuit is open after repeated failures
+    #[error("{provider} is unavailable")]
+    UpstreamUnavailable { provider: &'static str },
+    #[error("{provider} did not answer in time")]
+    UpstreamTimeout { provider: &'static str },
+    /// The external service kept refusing requests over its rate limit
+    #[error("{provider} rate limit reached, try again later")]
+    UpstreamRateLimited { provider: &'static str },
+    /// The external service answered with something unexpected
+    #[error("{provider} sent an invalid response")]
+    UpstreamBadResponse { provider: &'static str },
+
     #[error("{message}")]
     CustomError { status_code: u16, message: String },
 }
@@ -52,6 +64,10 @@ impl AppError {
             AppError::NotFound => 404,
             AppError::InternalServerError => 500,
             AppError::ValidationError { .. } => 400,
+            AppError::UpstreamUnavailable { .. } => 503,
+            AppError::UpstreamTimeout { .. } => 504,
+            AppError::UpstreamRateLimited { .. } => 503,
+            AppError::UpstreamBadResponse { .. } => 502,
             AppError::CustomError { status_code, .. } => *status_code,
         }
     }
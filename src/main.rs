@@ -1,3 +1,6 @@
+// The hand-written OpenAPI spec is a single json! literal
+#![recursion_limit = "256"]
+
 #[macro_use]
 extern crate rocket;
 
@@ -16,19 +19,28 @@ mod error {
 mod controllers;
 mod use_cases;
 mod repositories;
+mod providers;
 mod models;
+mod workers;
 
 mod dto {
     pub mod record_dto;
+    pub mod import_dto;
+    pub mod backup_dto;
     pub mod user_dto;
     pub mod discogs_dto;
     pub mod spotify_dto;
+    pub mod musicbrainz_dto;
+    pub mod metadata_dto;
+    pub mod enrichment_dto;
+    pub mod tag_dto;
 }
 
 #[cfg(test)]
 mod test {
     pub mod app;
     pub mod db;
+    pub mod http;
     pub mod fixture {
         pub mod record;
         pub mod user;
@@ -45,8 +57,9 @@ mod test {
 
 use crate::app::create_app;
 use crate::config::Config;
-use crate::controllers::{record_controller, user_controller, auth_controller, collection_controller};
-use crate::db::Db;
+use crate::controllers::{record_controller, user_controller, auth_controller, collection_controller, tag_controller};
+use crate::db::{Cache, Db};
+use crate::workers::{enrichment_worker, import_worker};
 use dotenv::dotenv;
 use rocket::fairing::AdHoc;
 use rocket_db_pools::Database;
@@ -63,12 +76,16 @@ async fn rocket() -> _ {
 
     rocket::build()
         .attach(Db::init())
+        .attach(Cache::init())
         .attach(AdHoc::config::<Config>())
         .manage(create_app())
+        .attach(import_worker::fairing())
+        .attach(enrichment_worker::fairing())
         .mount("/users", user_controller::routes())
         .mount("/records", record_controller::routes())
         .mount("/auth", auth_controller::routes())
         .mount("/records/collection", collection_controller::routes())
+        .mount("/tags", tag_controller::routes())
         .mount("/docs", openapi::routes())
         .mount("/health-check", routes![health_check])
 }
@@ -0,0 +1,25 @@
+use crate::dto::enrichment_dto::EnrichmentError;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+
+/// Enrichment job model
+/// Tracks the metadata lookups of a user's records processed in the background,
+/// its status is one of `ImportJobStatus`
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
+pub struct EnrichmentJob {
+    pub id: i32,
+    pub user_id: i32,
+    pub status: String,
+    pub total_records: i32,
+    pub processed_records: i32,
+    pub enriched_count: i32,
+    pub unchanged_count: i32,
+    pub failed_count: i32,
+    /// Records that could not be looked up
+    pub errors: Json<Vec<EnrichmentError>>,
+    /// Why the job stopped, when it failed
+    pub failure: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
@@ -0,0 +1,49 @@
+use crate::dto::import_dto::ImportRowError;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+
+/// Lifecycle of a background job, import or enrichment
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportJobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl ImportJobStatus {
+    /// Value stored in the `status` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportJobStatus::Pending => "pending",
+            ImportJobStatus::Running => "running",
+            ImportJobStatus::Completed => "completed",
+            ImportJobStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Import job model
+/// Tracks the progress of a CSV import processed in the background,
+/// the parsed rows themselves are only read by the worker
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
+pub struct ImportJob {
+    pub id: i32,
+    pub user_id: i32,
+    pub status: String,
+    pub mode: String,
+    pub total_rows: i32,
+    pub processed_rows: i32,
+    pub created_count: i32,
+    pub updated_count: i32,
+    pub unchanged_count: i32,
+    pub skipped_count: i32,
+    /// Problems found on individual rows
+    pub errors: Json<Vec<ImportRowError>>,
+    /// Why the job stopped, when it failed
+    pub failure: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
@@ -2,4 +2,6 @@ pub mod jwt_model;
 pub mod record_model;
 pub mod user_model;
 pub mod tag_model;
-pub mod collection_model;
\ No newline at end of file
+pub mod collection_model;
+pub mod import_job_model;
+pub mod enrichment_job_model;
\ No newline at end of file
@@ -14,11 +14,24 @@ pub struct RecordDB {
 
     pub discogs_url: Option<String>,
     pub spotify_url: Option<String>,
+    /// MusicBrainz release group id
+    pub musicbrainz_id: Option<String>,
 
     pub owned: bool,
     pub wanted: bool,
 
     pub user_id: i32,
+
+    pub created_at: chrono::NaiveDateTime,
+
+    /// Collection details kept from a Discogs export
+    pub catalog_number: Option<String>,
+    pub format: Option<String>,
+    pub rating: Option<i32>,
+    pub collection_folder: Option<String>,
+    pub media_condition: Option<String>,
+    pub sleeve_condition: Option<String>,
+    pub notes: Option<String>,
 }
 
 /// Record is the complete model including tags
@@ -33,18 +46,36 @@ pub struct Record {
 
     pub discogs_url: Option<String>,
     pub spotify_url: Option<String>,
+    /// MusicBrainz release group id
+    pub musicbrainz_id: Option<String>,
 
     pub owned: bool,
     pub wanted: bool,
 
     pub user_id: i32,
 
+    pub created_at: chrono::NaiveDateTime,
+
+    /// Collection details kept from a Discogs export
+    pub catalog_number: Option<String>,
+    pub format: Option<String>,
+    pub rating: Option<i32>,
+    pub collection_folder: Option<String>,
+    pub media_condition: Option<String>,
+    pub sleeve_condition: Option<String>,
+    pub notes: Option<String>,
+
     /// Tags associated with this record
     /// This field is not stored in the database
     /// but is populated after retrieval
     /// from the database using a join query.
     /// Uses TagResponse to avoid exposing internal IDs
     pub tags: Option<Vec<TagResponse>>,
+
+    /// Confidence, between 0 and 1, that the Spotify album merged into a search result
+    /// is the same release. Not stored, only set on merged search results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_confidence: Option<f64>,
 }
 
 impl From<RecordDB> for Record {
@@ -57,10 +88,20 @@ impl From<RecordDB> for Record {
             cover_url: db.cover_url,
             discogs_url: db.discogs_url,
             spotify_url: db.spotify_url,
+            musicbrainz_id: db.musicbrainz_id,
             owned: db.owned,
             wanted: db.wanted,
             user_id: db.user_id,
+            created_at: db.created_at,
+            catalog_number: db.catalog_number,
+            format: db.format,
+            rating: db.rating,
+            collection_folder: db.collection_folder,
+            media_condition: db.media_condition,
+            sleeve_condition: db.sleeve_condition,
+            notes: db.notes,
             tags: None,
+            match_confidence: None,
         }
     }
 }
@@ -73,3 +114,20 @@ impl Record {
         self
     }
 }
+
+/// A page of records along with the cursor of the following page
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordPage {
+    /// Number of records matching the filters, across all pages
+    pub total: i64,
+    /// Cursor to pass as `after` to fetch the next page, if any
+    pub next_cursor: Option<String>,
+    pub items: Vec<Record>,
+}
+
+/// Results of an external search, `cache_hit` tells whether they came from the cache
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchResults {
+    pub records: Vec<Record>,
+    pub cache_hit: bool,
+}
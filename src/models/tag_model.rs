@@ -1,11 +1,58 @@
 use serde::{Deserialize, Serialize};
-use sqlx::{Decode, FromRow};
+use sqlx::FromRow;
+use std::collections::{HashMap, HashSet};
 
-#[derive(Debug, FromRow, Serialize, Deserialize, Clone, Decode)]
+/// What a tag describes, free-form tags added by the user are custom ones
+#[derive(FromFormField, Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagKind {
+    Genre,
+    Style,
+    Label,
+    Mood,
+    #[default]
+    Custom,
+}
+
+impl TagKind {
+    const ALL: [TagKind; 5] = [
+        TagKind::Genre,
+        TagKind::Style,
+        TagKind::Label,
+        TagKind::Mood,
+        TagKind::Custom,
+    ];
+
+    /// Value stored in the `kind` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TagKind::Genre => "genre",
+            TagKind::Style => "style",
+            TagKind::Label => "label",
+            TagKind::Mood => "mood",
+            TagKind::Custom => "custom",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str().eq_ignore_ascii_case(value.trim()))
+    }
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
 pub struct Tag {
     pub id: i32,
     pub name: String,
     pub slug: String,
+    pub user_id: i32,
+    pub kind: String,
+    pub parent_id: Option<i32>,
+}
+
+fn default_kind() -> String {
+    TagKind::Custom.as_str().to_string()
 }
 
 /// TagResponse is used for API responses where we don't want to expose the ID
@@ -13,6 +60,87 @@ pub struct Tag {
 pub struct TagResponse {
     pub name: String,
     pub slug: String,
+    /// Missing from backups made before tags had kinds
+    #[serde(default = "default_kind")]
+    pub kind: String,
+}
+
+impl TagResponse {
+    /// Name prefixed with its kind, such as `genre:Jazz`, custom tags keep their plain name
+    pub fn qualified_name(&self) -> String {
+        Tag::qualify(&self.kind, &self.name)
+    }
+}
+
+/// A tag of the user along with the number of records it is attached to
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
+pub struct TagUsage {
+    pub name: String,
+    pub slug: String,
+    pub kind: String,
+    pub records: i64,
+}
+
+/// A tag of the user with its parent and record counts, as loaded to build the tree
+#[derive(Debug, FromRow, Clone)]
+pub struct TagTreeRow {
+    pub id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+    pub slug: String,
+    pub kind: String,
+    pub records: i64,
+    pub total_records: i64,
+}
+
+/// A tag with its children. `records` counts the records having the tag itself,
+/// `total_records` the ones having it or one of its descendants
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagNode {
+    pub name: String,
+    pub slug: String,
+    pub kind: String,
+    pub records: i64,
+    pub total_records: i64,
+    pub children: Vec<TagNode>,
+}
+
+impl TagNode {
+    /// Nests the rows under their parent, tags without a known parent are roots.
+    /// Rows keep their order among siblings
+    pub fn build_tree(rows: Vec<TagTreeRow>) -> Vec<TagNode> {
+        let ids: HashSet<i32> = rows.iter().map(|row| row.id).collect();
+        let mut roots = Vec::new();
+        let mut children: HashMap<i32, Vec<TagTreeRow>> = HashMap::new();
+        for row in rows {
+            match row.parent_id {
+                Some(parent_id) if ids.contains(&parent_id) => {
+                    children.entry(parent_id).or_default().push(row)
+                }
+                _ => roots.push(row),
+            }
+        }
+
+        roots
+            .into_iter()
+            .map(|row| TagNode::from_row(row, &mut children))
+            .collect()
+    }
+
+    fn from_row(row: TagTreeRow, children: &mut HashMap<i32, Vec<TagTreeRow>>) -> TagNode {
+        let nested = children.remove(&row.id).unwrap_or_default();
+        TagNode {
+            name: row.name,
+            slug: row.slug,
+            kind: row.kind,
+            records: row.records,
+            total_records: row.total_records,
+            children: nested
+                .into_iter()
+                .map(|child| TagNode::from_row(child, children))
+                .collect(),
+        }
+    }
 }
 
 impl From<Tag> for TagResponse {
@@ -20,21 +148,52 @@ impl From<Tag> for TagResponse {
         Self {
             name: tag.name,
             slug: tag.slug,
+            kind: tag.kind,
         }
     }
 }
 
 impl Tag {
-    /// Creates a new tag with automatically generated slug from the name
-    pub fn new(name: String) -> Self {
-        let slug = Tag::slugify(&name);
+    /// Creates a new tag of the user with automatically generated slug from the name,
+    /// a `kind:` prefix gives its kind
+    pub fn new(user_id: i32, name: String) -> Self {
+        let (kind, name) = Tag::parse(&name);
+        let slug = Tag::slugify(name);
         Self { 
             id: 0, // Default value, will be set by the database
-            name, 
-            slug 
+            name: name.to_string(), 
+            slug,
+            user_id,
+            kind: kind.unwrap_or_default().as_str().to_string(),
+            parent_id: None,
+        }
+    }
+
+    /// Splits `genre:Jazz` into its kind and name. Names without a known kind prefix
+    /// are returned whole, without a kind
+    pub fn parse(input: &str) -> (Option<TagKind>, &str) {
+        match input.split_once(':') {
+            Some((kind, name)) if !name.trim().is_empty() => match TagKind::parse(kind) {
+                Some(kind) => (Some(kind), name.trim()),
+                None => (None, input.trim()),
+            },
+            _ => (None, input.trim()),
+        }
+    }
+
+    /// Reverse of `parse`, custom tags keep their plain name
+    pub fn qualify(kind: &str, name: &str) -> String {
+        match TagKind::parse(kind) {
+            Some(TagKind::Custom) | None => name.to_string(),
+            Some(kind) => format!("{}:{}", kind.as_str(), name),
         }
     }
 
+    /// Slug of a tag name, ignoring its kind prefix
+    pub fn slug_of(input: &str) -> String {
+        Tag::slugify(Tag::parse(input).1)
+    }
+
     /// Converts a string to a slug format:
     /// - Converts to lowercase
     /// - Removes special characters
@@ -70,4 +229,47 @@ mod tests {
         assert_eq!(Tag::slugify("R&B / Soul"), "rb-soul");
         assert_eq!(Tag::slugify("   Multiple   Spaces   "), "multiple-spaces");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Tag::parse("genre:Jazz"), (Some(TagKind::Genre), "Jazz"));
+        assert_eq!(Tag::parse("Label: Blue Note "), (Some(TagKind::Label), "Blue Note"));
+        assert_eq!(Tag::parse("Jazz"), (None, "Jazz"));
+        // Only known kinds are prefixes
+        assert_eq!(Tag::parse("Side A: Live"), (None, "Side A: Live"));
+        assert_eq!(Tag::parse("mood:"), (None, "mood:"));
+
+        let tag = Tag::new(1, "style:Hard Bop".to_string());
+        assert_eq!((tag.name.as_str(), tag.slug.as_str(), tag.kind.as_str()), ("Hard Bop", "hard-bop", "style"));
+        assert_eq!(TagResponse::from(tag).qualified_name(), "style:Hard Bop");
+        assert_eq!(Tag::qualify("custom", "Jazz"), "Jazz");
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let row = |id: i32, parent_id: Option<i32>, name: &str, records: i64, total_records: i64| TagTreeRow {
+            id,
+            parent_id,
+            name: name.to_string(),
+            slug: Tag::slugify(name),
+            kind: "genre".to_string(),
+            records,
+            total_records,
+        };
+        let tree = TagNode::build_tree(vec![
+            row(3, Some(2), "Deep House", 1, 1),
+            row(1, None, "Electronic", 1, 3),
+            row(2, Some(1), "House", 1, 2),
+            row(4, None, "Jazz", 2, 2),
+            // Its parent belongs to another user, it is shown as a root
+            row(5, Some(42), "Soul", 0, 0),
+        ]);
+
+        let slugs: Vec<&str> = tree.iter().map(|node| node.slug.as_str()).collect();
+        assert_eq!(slugs, ["electronic", "jazz", "soul"]);
+        let house = &tree[0].children[0];
+        assert_eq!((house.slug.as_str(), house.records, house.total_records), ("house", 1, 2));
+        assert_eq!(house.children[0].slug, "deep-house");
+        assert!(house.children[0].children.is_empty());
+    }
+}
@@ -21,6 +21,104 @@ pub async fn openapi_json() -> Json<Value> {
                     "description": "JWT authentication token"
                 }
             },
+            "parameters": {
+                "Tag": {
+                    "name": "tag",
+                    "in": "query",
+                    "description": "Filter by tag name or slug, can be repeated. A kind prefix only matches tags of that kind (genre:jazz). Records having a descendant of the tag match too",
+                    "required": false,
+                    "schema": {
+                        "type": "array",
+                        "items": { "type": "string" }
+                    },
+                    "style": "form",
+                    "explode": true
+                },
+                "TagMatch": {
+                    "name": "tag_match",
+                    "in": "query",
+                    "description": "Match records having any (default) or all of the tags",
+                    "required": false,
+                    "schema": {
+                        "type": "string",
+                        "enum": ["any", "all"]
+                    }
+                },
+                "Artist": {
+                    "name": "artist",
+                    "in": "query",
+                    "description": "Filter by artist name (case insensitive)",
+                    "required": false,
+                    "schema": {
+                        "type": "string"
+                    }
+                },
+                "ReleasedFrom": {
+                    "name": "released_from",
+                    "in": "query",
+                    "description": "First release year (inclusive)",
+                    "required": false,
+                    "schema": {
+                        "type": "integer"
+                    }
+                },
+                "ReleasedTo": {
+                    "name": "released_to",
+                    "in": "query",
+                    "description": "Last release year (inclusive)",
+                    "required": false,
+                    "schema": {
+                        "type": "integer"
+                    }
+                },
+                "Q": {
+                    "name": "q",
+                    "in": "query",
+                    "description": "Free text matched against titles and artists",
+                    "required": false,
+                    "schema": {
+                        "type": "string"
+                    }
+                },
+                "Limit": {
+                    "name": "limit",
+                    "in": "query",
+                    "description": "Number of records per page (default 50, max 500)",
+                    "required": false,
+                    "schema": {
+                        "type": "integer"
+                    }
+                },
+                "After": {
+                    "name": "after",
+                    "in": "query",
+                    "description": "Cursor returned as next_cursor by the previous page",
+                    "required": false,
+                    "schema": {
+                        "type": "string"
+                    }
+                },
+                "Sort": {
+                    "name": "sort",
+                    "in": "query",
+                    "description": "Field to sort records by (default added)",
+                    "required": false,
+                    "schema": {
+                        "type": "string",
+                        "enum": ["artist", "title", "release_date", "added"]
+                    }
+                },
+                "Direction": {
+                    "name": "direction",
+                    "in": "query",
+                    "description": "Sort direction (default asc for artist and title, desc for dates)",
+                    "required": false,
+                    "schema": {
+                        "type": "string",
+                        "enum": ["asc", "desc"]
+                    }
+                }
+            },
             "schemas": {
                 "User": {
                     "type": "object",
@@ -75,19 +173,224 @@ pub async fn openapi_json() -> Json<Value> {
                         "cover_url": { "type": "string", "format": "uri" },
                         "discogs_url": { "type": "string", "format": "uri" },
                         "spotify_url": { "type": "string", "format": "uri" },
+                        "musicbrainz_id": {
+                            "type": "string",
+                            "format": "uuid",
+                            "description": "MusicBrainz release group id"
+                        },
                         "owned": { "type": "boolean" },
                         "wanted": { "type": "boolean" },
                         "created_at": { "type": "string", "format": "date-time" },
                         "updated_at": { "type": "string", "format": "date-time" },
+                        "catalog_number": { "type": "string" },
+                        "format": { "type": "string" },
+                        "rating": { "type": "integer", "minimum": 0, "maximum": 5 },
+                        "collection_folder": { "type": "string" },
+                        "media_condition": { "type": "string" },
+                        "sleeve_condition": { "type": "string" },
+                        "notes": { "type": "string" },
+                        "tags": {
+                            "type": "array",
+                            "items": {
+                                "$ref": "#/components/schemas/Tag"
+                            }
+                        },
+                        "match_confidence": {
+                            "type": "number",
+                            "minimum": 0,
+                            "maximum": 1,
+                            "description": "Search results only, confidence that the Spotify album merged into the result is the same release"
+                        }
+                    }
+                },
+                "RecordPage": {
+                    "type": "object",
+                    "properties": {
+                        "total": { "type": "integer", "format": "int64" },
+                        "next_cursor": { "type": "string", "nullable": true },
+                        "items": {
+                            "type": "array",
+                            "items": {
+                                "$ref": "#/components/schemas/Record"
+                            }
+                        }
+                    }
+                },
+                "ImportReport": {
+                    "type": "object",
+                    "properties": {
+                        "created": { "type": "integer" },
+                        "updated": { "type": "integer" },
+                        "unchanged": { "type": "integer" },
+                        "skipped": { "type": "integer" },
+                        "rows": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "row": { "type": "integer" },
+                                    "status": {
+                                        "type": "string",
+                                        "enum": ["created", "updated", "unchanged", "skipped"]
+                                    },
+                                    "record_id": { "type": "integer", "nullable": true },
+                                    "message": { "type": "string", "nullable": true },
+                                    "errors": {
+                                        "type": "array",
+                                        "items": {
+                                            "$ref": "#/components/schemas/ImportRowError"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                "ImportJob": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "integer" },
+                        "user_id": { "type": "integer" },
+                        "status": {
+                            "type": "string",
+                            "enum": ["pending", "running", "completed", "failed"]
+                        },
+                        "mode": { "type": "string", "enum": ["create", "upsert"] },
+                        "total_rows": { "type": "integer" },
+                        "processed_rows": { "type": "integer" },
+                        "created_count": { "type": "integer" },
+                        "updated_count": { "type": "integer" },
+                        "unchanged_count": { "type": "integer" },
+                        "skipped_count": { "type": "integer" },
+                        "errors": {
+                            "type": "array",
+                            "items": {
+                                "$ref": "#/components/schemas/ImportRowError"
+                            }
+                        },
+                        "failure": { "type": "string", "nullable": true },
+                        "created_at": { "type": "string", "format": "date-time" },
+                        "updated_at": { "type": "string", "format": "date-time" }
+                    }
+                },
+                "EnrichmentJob": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "integer" },
+                        "user_id": { "type": "integer" },
+                        "status": {
+                            "type": "string",
+                            "enum": ["pending", "running", "completed", "failed"]
+                        },
+                        "total_records": { "type": "integer" },
+                        "processed_records": { "type": "integer" },
+                        "enriched_count": { "type": "integer" },
+                        "unchanged_count": { "type": "integer" },
+                        "failed_count": { "type": "integer" },
+                        "errors": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "record_id": { "type": "integer" },
+                                    "message": { "type": "string" }
+                                }
+                            }
+                        },
+                        "failure": { "type": "string", "nullable": true },
+                        "created_at": { "type": "string", "format": "date-time" },
+                        "updated_at": { "type": "string", "format": "date-time" }
+                    }
+                },
+                "AccountBackup": {
+                    "type": "object",
+                    "properties": {
+                        "version": { "type": "integer" },
+                        "exported_at": { "type": "string", "format": "date-time" },
+                        "profile": {
+                            "type": "object",
+                            "properties": {
+                                "email": { "type": "string" },
+                                "username": { "type": "string" },
+                                "password_hash": { "type": "string", "description": "Bcrypt hash" },
+                                "created_at": { "type": "string", "format": "date-time" }
+                            }
+                        },
                         "tags": {
+                            "type": "array",
+                            "description": "Tags of the user, unused ones included",
+                            "items": {
+                                "allOf": [
+                                    { "$ref": "#/components/schemas/Tag" },
+                                    {
+                                        "type": "object",
+                                        "properties": {
+                                            "parent": { "type": "string", "nullable": true, "description": "Slug of the parent tag" }
+                                        }
+                                    }
+                                ]
+                            }
+                        },
+                        "records": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "description": "A record without its id and user_id, tags are names"
+                            }
+                        },
+                        "collection_token": { "type": "string", "nullable": true }
+                    }
+                },
+                "RestoreReport": {
+                    "type": "object",
+                    "properties": {
+                        "user_id": { "type": "integer" },
+                        "records": { "type": "integer" },
+                        "tags": { "type": "integer" },
+                        "collection_token": { "type": "string", "nullable": true }
+                    }
+                },
+                "ImportRowError": {
+                    "type": "object",
+                    "properties": {
+                        "row": { "type": "integer" },
+                        "field": { "type": "string" },
+                        "message": { "type": "string" }
+                    }
+                },
+                "ImportPreview": {
+                    "type": "object",
+                    "properties": {
+                        "to_create": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "row": { "type": "integer" },
+                                    "title": { "type": "string" },
+                                    "artist": { "type": "string" },
+                                    "discogs_url": { "type": "string", "nullable": true }
+                                }
+                            }
+                        },
+                        "conflicts": {
                             "type": "array",
                             "items": {
                                 "type": "object",
                                 "properties": {
-                                    "id": { "type": "string", "format": "uuid" },
-                                    "name": { "type": "string" }
+                                    "row": { "type": "integer" },
+                                    "record_id": { "type": "integer" },
+                                    "title": { "type": "string" },
+                                    "artist": { "type": "string" },
+                                    "discogs_url": { "type": "string" }
                                 }
                             }
+                        },
+                        "errors": {
+                            "type": "array",
+                            "items": {
+                                "$ref": "#/components/schemas/ImportRowError"
+                            }
                         }
                     }
                 },
@@ -101,8 +404,59 @@ pub async fn openapi_json() -> Json<Value> {
                         "cover_url": { "type": "string", "format": "uri" },
                         "discogs_url": { "type": "string", "format": "uri" },
                         "spotify_url": { "type": "string", "format": "uri" },
+                        "musicbrainz_id": {
+                            "type": "string",
+                            "format": "uuid",
+                            "description": "MusicBrainz release group id"
+                        },
+                        "owned": { "type": "boolean" },
+                        "wanted": { "type": "boolean" },
+                        "created_at": {
+                            "type": "string",
+                            "description": "When the record was added, e.g. 2025-03-23 10:40:35"
+                        },
+                        "catalog_number": { "type": "string" },
+                        "format": { "type": "string" },
+                        "rating": { "type": "integer", "minimum": 0, "maximum": 5 },
+                        "collection_folder": { "type": "string" },
+                        "media_condition": { "type": "string" },
+                        "sleeve_condition": { "type": "string" },
+                        "notes": { "type": "string" },
+                        "tags": {
+                            "type": "array",
+                            "items": { "type": "string" }
+                        }
+                    }
+                },
+                "RecordPatchInput": {
+                    "type": "object",
+                    "description": "Only the provided fields are changed, nullable ones set to null are cleared",
+                    "properties": {
+                        "title": { "type": "string" },
+                        "artist": { "type": "string" },
+                        "release_date": { "type": "string", "format": "date" },
+                        "cover_url": { "type": "string", "format": "uri" },
+                        "discogs_url": { "type": "string", "format": "uri", "nullable": true },
+                        "spotify_url": { "type": "string", "format": "uri", "nullable": true },
+                        "musicbrainz_id": {
+                            "type": "string",
+                            "format": "uuid",
+                            "nullable": true,
+                            "description": "MusicBrainz release group id"
+                        },
                         "owned": { "type": "boolean" },
                         "wanted": { "type": "boolean" },
+                        "created_at": {
+                            "type": "string",
+                            "description": "When the record was added, e.g. 2025-03-23 10:40:35"
+                        },
+                        "catalog_number": { "type": "string", "nullable": true },
+                        "format": { "type": "string", "nullable": true },
+                        "rating": { "type": "integer", "minimum": 0, "maximum": 5, "nullable": true },
+                        "collection_folder": { "type": "string", "nullable": true },
+                        "media_condition": { "type": "string", "nullable": true },
+                        "sleeve_condition": { "type": "string", "nullable": true },
+                        "notes": { "type": "string", "nullable": true },
                         "tags": {
                             "type": "array",
                             "items": { "type": "string" }
@@ -117,6 +471,42 @@ pub async fn openapi_json() -> Json<Value> {
                         "user_id": { "type": "string", "format": "uuid" },
                         "created_at": { "type": "string", "format": "date-time" }
                     }
+                },
+                "TagKind": {
+                    "type": "string",
+                    "enum": ["genre", "style", "label", "mood", "custom"],
+                    "description": "Discogs genres, styles and labels get their kind on import, free-form tags are custom"
+                },
+                "Tag": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "slug": { "type": "string" },
+                        "kind": { "$ref": "#/components/schemas/TagKind" }
+                    }
+                },
+                "TagUsage": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "slug": { "type": "string" },
+                        "kind": { "$ref": "#/components/schemas/TagKind" },
+                        "records": { "type": "integer", "description": "Number of records using the tag" }
+                    }
+                },
+                "TagNode": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "slug": { "type": "string" },
+                        "kind": { "$ref": "#/components/schemas/TagKind" },
+                        "records": { "type": "integer", "description": "Number of records having the tag itself" },
+                        "total_records": { "type": "integer", "description": "Number of records having the tag or one of its descendants" },
+                        "children": {
+                            "type": "array",
+                            "items": { "$ref": "#/components/schemas/TagNode" }
+                        }
+                    }
                 }
             }
         },
@@ -189,6 +579,42 @@ pub async fn openapi_json() -> Json<Value> {
                     }
                 }
             },
+            "/auth/restore": {
+                "post": {
+                    "summary": "Restore a backup as a new account",
+                    "description": "Creates an account from the backup profile, it keeps the password and creation date of the original account. The profile is checked like a registration, its password hash must be a bcrypt one",
+                    "tags": ["Authentication"],
+                    "requestBody": {
+                        "required": true,
+                        "description": "A backup file, gzipped or not",
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "$ref": "#/components/schemas/AccountBackup"
+                                }
+                            },
+                            "application/gzip": {
+                                "schema": { "type": "string", "format": "binary" }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Restore report",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/RestoreReport"
+                                    }
+                                }
+                            }
+                        },
+                        "400": {
+                            "description": "Invalid or unsupported backup"
+                        }
+                    }
+                }
+            },
             "/auth/me": {
                 "get": {
                     "summary": "Get current user",
@@ -283,6 +709,84 @@ pub async fn openapi_json() -> Json<Value> {
                     }
                 }
             },
+            "/users/backup": {
+                "get": {
+                    "summary": "Back up the account",
+                    "description": "Downloads the profile, records, tags and collection token of the authenticated user as a versioned JSON document",
+                    "tags": ["Users"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "gzip",
+                            "in": "query",
+                            "description": "Compress the backup",
+                            "required": false,
+                            "schema": {
+                                "type": "boolean",
+                                "default": false
+                            }
+                        }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Account backup",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/AccountBackup"
+                                    }
+                                },
+                                "application/gzip": {
+                                    "schema": { "type": "string", "format": "binary" }
+                                }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        }
+                    }
+                }
+            },
+            "/users/restore": {
+                "post": {
+                    "summary": "Restore a backup into the account",
+                    "description": "Replaces the records and collection token of the authenticated user in a single transaction",
+                    "tags": ["Users"],
+                    "security": [{ "BearerAuth": [] }],
+                    "requestBody": {
+                        "required": true,
+                        "description": "A backup file, gzipped or not",
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "$ref": "#/components/schemas/AccountBackup"
+                                }
+                            },
+                            "application/gzip": {
+                                "schema": { "type": "string", "format": "binary" }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Restore report",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/RestoreReport"
+                                    }
+                                }
+                            }
+                        },
+                        "400": {
+                            "description": "Invalid or unsupported backup"
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        }
+                    }
+                }
+            },
             "/records": {
                 "get": {
                     "summary": "Get records",
@@ -307,22 +811,32 @@ pub async fn openapi_json() -> Json<Value> {
                             "schema": {
                                 "type": "boolean"
                             }
-                        }
+                        },
+                        { "$ref": "#/components/parameters/Tag" },
+                        { "$ref": "#/components/parameters/TagMatch" },
+                        { "$ref": "#/components/parameters/Artist" },
+                        { "$ref": "#/components/parameters/ReleasedFrom" },
+                        { "$ref": "#/components/parameters/ReleasedTo" },
+                        { "$ref": "#/components/parameters/Q" },
+                        { "$ref": "#/components/parameters/Limit" },
+                        { "$ref": "#/components/parameters/After" },
+                        { "$ref": "#/components/parameters/Sort" },
+                        { "$ref": "#/components/parameters/Direction" }
                     ],
                     "responses": {
                         "200": {
-                            "description": "List of records",
+                            "description": "Page of records",
                             "content": {
                                 "application/json": {
                                     "schema": {
-                                        "type": "array",
-                                        "items": {
-                                            "$ref": "#/components/schemas/Record"
-                                        }
+                                        "$ref": "#/components/schemas/RecordPage"
                                     }
                                 }
                             }
                         },
+                        "400": {
+                            "description": "Invalid pagination cursor"
+                        },
                         "401": {
                             "description": "Unauthorized"
                         }
@@ -369,33 +883,29 @@ pub async fn openapi_json() -> Json<Value> {
                     }
                 }
             },
-            "/records/search": {
+            "/records/{id}": {
                 "get": {
-                    "summary": "Search records",
-                    "description": "Searches for records matching a query",
+                    "summary": "Get record",
+                    "description": "Returns a record of the authenticated user's collection",
                     "tags": ["Records"],
                     "security": [{ "BearerAuth": [] }],
                     "parameters": [
                         {
-                            "name": "query",
-                            "in": "query",
-                            "description": "Search query",
+                            "name": "id",
+                            "in": "path",
                             "required": true,
                             "schema": {
-                                "type": "string"
+                                "type": "integer"
                             }
                         }
                     ],
                     "responses": {
                         "200": {
-                            "description": "Search results",
+                            "description": "Record",
                             "content": {
                                 "application/json": {
                                     "schema": {
-                                        "type": "array",
-                                        "items": {
-                                            "$ref": "#/components/schemas/Record"
-                                        }
+                                        "$ref": "#/components/schemas/Record"
                                     }
                                 }
                             }
@@ -404,10 +914,943 @@ pub async fn openapi_json() -> Json<Value> {
                             "description": "Unauthorized"
                         }
                     }
-                }
-            },
-            "/records/collection/tokens": {
-                "post": {
+                },
+                "put": {
+                    "summary": "Replace record",
+                    "description": "Replaces a record of the authenticated user's collection, tags are replaced when provided",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "id",
+                            "in": "path",
+                            "required": true,
+                            "schema": {
+                                "type": "integer"
+                            }
+                        }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "$ref": "#/components/schemas/RecordInput"
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Updated record",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/Record"
+                                    }
+                                }
+                            }
+                        },
+                        "400": {
+                            "description": "Invalid input"
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "404": {
+                            "description": "Record not found"
+                        }
+                    }
+                },
+                "patch": {
+                    "summary": "Update record",
+                    "description": "Updates the provided fields of a record of the authenticated user's collection",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "id",
+                            "in": "path",
+                            "required": true,
+                            "schema": {
+                                "type": "integer"
+                            }
+                        }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "$ref": "#/components/schemas/RecordPatchInput"
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Updated record",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/Record"
+                                    }
+                                }
+                            }
+                        },
+                        "400": {
+                            "description": "Invalid input"
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "404": {
+                            "description": "Record not found"
+                        }
+                    }
+                },
+                "delete": {
+                    "summary": "Delete record",
+                    "description": "Deletes a record of the authenticated user's collection",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "id",
+                            "in": "path",
+                            "required": true,
+                            "schema": {
+                                "type": "integer"
+                            }
+                        }
+                    ],
+                    "responses": {
+                        "204": {
+                            "description": "Record deleted"
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "404": {
+                            "description": "Record not found"
+                        }
+                    }
+                }
+            },
+            "/records/{id}/tags": {
+                "post": {
+                    "summary": "Add tags to a record",
+                    "description": "Adds tags to a record of the authenticated user's collection, keeping its current ones. Tags are matched by slug and created when missing, a kind prefix (mood:Chill) gives their kind",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "id",
+                            "in": "path",
+                            "required": true,
+                            "schema": {
+                                "type": "integer"
+                            }
+                        }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["tags"],
+                                    "properties": {
+                                        "tags": {
+                                            "type": "array",
+                                            "items": { "type": "string" },
+                                            "example": ["genre:Jazz", "label:Blue Note", "Late night"]
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Updated record",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/Record"
+                                    }
+                                }
+                            }
+                        },
+                        "400": {
+                            "description": "Invalid input"
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "404": {
+                            "description": "Record not found"
+                        }
+                    }
+                }
+            },
+            "/records/{id}/tags/{slug}": {
+                "delete": {
+                    "summary": "Remove a tag from a record",
+                    "description": "Removes a tag from a record of the authenticated user's collection, the tag itself is kept",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "id",
+                            "in": "path",
+                            "required": true,
+                            "schema": {
+                                "type": "integer"
+                            }
+                        },
+                        {
+                            "name": "slug",
+                            "in": "path",
+                            "required": true,
+                            "schema": {
+                                "type": "string"
+                            }
+                        }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Updated record",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/Record"
+                                    }
+                                }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "404": {
+                            "description": "Record or tag not found"
+                        }
+                    }
+                }
+            },
+            "/tags": {
+                "get": {
+                    "summary": "List tags",
+                    "description": "Lists the tags of the authenticated user with the number of records using them",
+                    "tags": ["Tags"],
+                    "security": [{ "BearerAuth": [] }],
+                    "responses": {
+                        "200": {
+                            "description": "Tags",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": {
+                                            "$ref": "#/components/schemas/TagUsage"
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        }
+                    }
+                }
+            },
+            "/tags/suggest": {
+                "get": {
+                    "summary": "Suggest tags",
+                    "description": "Suggests tags of the authenticated user for what is being typed. Tags whose slug starts with the query come first, then the most similar ones, then the most used ones. The query is compared by slug, so rnb finds R&B / Soul",
+                    "tags": ["Tags"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "q",
+                            "in": "query",
+                            "required": true,
+                            "description": "Text being typed, a kind prefix only suggests tags of that kind (style:hou). Empty suggests the most used tags",
+                            "schema": {
+                                "type": "string"
+                            }
+                        },
+                        {
+                            "name": "limit",
+                            "in": "query",
+                            "description": "Number of suggestions, 10 by default and 50 at most",
+                            "schema": {
+                                "type": "integer",
+                                "format": "int64"
+                            }
+                        }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Suggested tags, best first",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": {
+                                            "$ref": "#/components/schemas/TagUsage"
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        }
+                    }
+                }
+            },
+            "/tags/tree": {
+                "get": {
+                    "summary": "Tag tree",
+                    "description": "Lists the tags of the authenticated user nested under their parent, with the record counts of each subtree",
+                    "tags": ["Tags"],
+                    "security": [{ "BearerAuth": [] }],
+                    "responses": {
+                        "200": {
+                            "description": "Root tags with their descendants",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": {
+                                            "$ref": "#/components/schemas/TagNode"
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        }
+                    }
+                }
+            },
+            "/tags/{slug}": {
+                "patch": {
+                    "summary": "Update tag",
+                    "description": "Renames a tag, its slug follows the new name, changes its kind or nests it under another tag",
+                    "tags": ["Tags"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "slug",
+                            "in": "path",
+                            "required": true,
+                            "schema": {
+                                "type": "string"
+                            }
+                        }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "properties": {
+                                        "name": { "type": "string" },
+                                        "kind": { "$ref": "#/components/schemas/TagKind" },
+                                        "parent": {
+                                            "type": "string",
+                                            "nullable": true,
+                                            "description": "Slug of the parent tag, null makes it a root tag"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Updated tag",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/Tag"
+                                    }
+                                }
+                            }
+                        },
+                        "400": {
+                            "description": "Invalid input, or the parent is the tag itself or one of its descendants"
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "404": {
+                            "description": "Tag not found"
+                        },
+                        "409": {
+                            "description": "Another tag already has this name, merge them instead"
+                        }
+                    }
+                },
+                "delete": {
+                    "summary": "Delete tag",
+                    "description": "Deletes a tag and removes it from the records using it, its children move up to its parent",
+                    "tags": ["Tags"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "slug",
+                            "in": "path",
+                            "required": true,
+                            "schema": {
+                                "type": "string"
+                            }
+                        }
+                    ],
+                    "responses": {
+                        "204": {
+                            "description": "Tag deleted"
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "404": {
+                            "description": "Tag not found"
+                        }
+                    }
+                }
+            },
+            "/tags/{slug}/merge": {
+                "post": {
+                    "summary": "Merge tags",
+                    "description": "Moves the records of a tag to another one, then deletes it. Records having both tags keep a single one",
+                    "tags": ["Tags"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "slug",
+                            "in": "path",
+                            "required": true,
+                            "schema": {
+                                "type": "string"
+                            }
+                        }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["into"],
+                                    "properties": {
+                                        "into": { "type": "string", "description": "Slug of the tag receiving the records" }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Remaining tag",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/Tag"
+                                    }
+                                }
+                            }
+                        },
+                        "400": {
+                            "description": "Invalid input or a tag merged into itself"
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "404": {
+                            "description": "Tag not found"
+                        }
+                    }
+                }
+            },
+            "/records/random": {
+                "get": {
+                    "summary": "Get a random record",
+                    "description": "Returns a random record of the authenticated user's collection matching the filters",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "owned",
+                            "in": "query",
+                            "description": "Filter by owned records",
+                            "required": false,
+                            "schema": {
+                                "type": "boolean"
+                            }
+                        },
+                        {
+                            "name": "wanted",
+                            "in": "query",
+                            "description": "Filter by wanted records",
+                            "required": false,
+                            "schema": {
+                                "type": "boolean"
+                            }
+                        },
+                        { "$ref": "#/components/parameters/Tag" },
+                        { "$ref": "#/components/parameters/TagMatch" },
+                        { "$ref": "#/components/parameters/Artist" },
+                        { "$ref": "#/components/parameters/ReleasedFrom" },
+                        { "$ref": "#/components/parameters/ReleasedTo" },
+                        { "$ref": "#/components/parameters/Q" }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Random record, or null when none matches",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/Record"
+                                    }
+                                }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        }
+                    }
+                }
+            },
+            "/records/from-discogs/{id}": {
+                "post": {
+                    "summary": "Add a record from Discogs",
+                    "description": "Creates a record from the details of a Discogs release or master. Genres, styles and the label become tags",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "id",
+                            "in": "path",
+                            "description": "Discogs release or master id",
+                            "required": true,
+                            "schema": {
+                                "type": "integer"
+                            }
+                        },
+                        {
+                            "name": "kind",
+                            "in": "query",
+                            "description": "What the id points at",
+                            "required": false,
+                            "schema": {
+                                "type": "string",
+                                "enum": ["release", "master"],
+                                "default": "release"
+                            }
+                        },
+                        {
+                            "name": "wanted",
+                            "in": "query",
+                            "description": "Add the record to the wantlist instead of the collection",
+                            "required": false,
+                            "schema": {
+                                "type": "boolean",
+                                "default": false
+                            }
+                        }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Record created",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/Record"
+                                    }
+                                }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "404": {
+                            "description": "Unknown Discogs release"
+                        },
+                        "409": {
+                            "description": "The release is already in the user's records"
+                        },
+                        "502": {
+                            "description": "Discogs sent an invalid response"
+                        },
+                        "503": {
+                            "description": "Discogs is not configured, unavailable or rate limiting requests"
+                        },
+                        "504": {
+                            "description": "Discogs did not answer in time"
+                        }
+                    }
+                }
+            },
+            "/records/lookup": {
+                "get": {
+                    "summary": "Look up a record by barcode or catalog number",
+                    "description": "Finds the releases carrying a scanned barcode or a catalog number. The user's records with the catalog number are returned when there are some, Discogs is asked otherwise. Discogs releases already in the user's records come back with that record's id, owned and wanted flags, the others have an id of 0 and can be added with `POST /records/from-discogs/{id}`",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "barcode",
+                            "in": "query",
+                            "description": "EAN or UPC barcode, spaces and dashes are ignored",
+                            "required": false,
+                            "schema": {
+                                "type": "string"
+                            }
+                        },
+                        {
+                            "name": "catno",
+                            "in": "query",
+                            "description": "Catalog number, compared without case, spaces nor punctuation",
+                            "required": false,
+                            "schema": {
+                                "type": "string"
+                            }
+                        }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Matching records",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": {
+                                            "$ref": "#/components/schemas/Record"
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "400": {
+                            "description": "Neither or both of barcode and catno are given, or the code is empty"
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "503": {
+                            "description": "Discogs is not configured, unavailable or rate limiting requests"
+                        }
+                    }
+                }
+            },
+            "/records/search": {
+                "get": {
+                    "summary": "Search records",
+                    "description": "Searches for records on Discogs, MusicBrainz and Spotify, albums found on several of them are merged into one result. Results already in the user's records, by Discogs url, Spotify url or MusicBrainz id, come back with that record's id, owned and wanted flags, the others have an id of 0",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "query",
+                            "in": "query",
+                            "description": "Search query",
+                            "required": true,
+                            "schema": {
+                                "type": "string"
+                            }
+                        }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Search results",
+                            "headers": {
+                                "X-Cache": {
+                                    "description": "HIT when the results were served from the search cache, MISS otherwise",
+                                    "schema": {
+                                        "type": "string",
+                                        "enum": ["HIT", "MISS"]
+                                    }
+                                }
+                            },
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": {
+                                            "$ref": "#/components/schemas/Record"
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "502": {
+                            "description": "A provider sent an invalid response"
+                        },
+                        "503": {
+                            "description": "A provider is unavailable or rate limiting requests"
+                        },
+                        "504": {
+                            "description": "A provider did not answer in time"
+                        }
+                    }
+                }
+            },
+            "/records/import": {
+                "post": {
+                    "summary": "Import a Discogs collection",
+                    "description": "Imports a Discogs collection CSV export, rows are matched on their Discogs release",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "mode",
+                            "in": "query",
+                            "description": "create skips releases already in the collection, upsert updates them",
+                            "required": false,
+                            "schema": {
+                                "type": "string",
+                                "enum": ["create", "upsert"],
+                                "default": "create"
+                            }
+                        },
+                        {
+                            "name": "dry_run",
+                            "in": "query",
+                            "description": "Only return a preview of the import without writing anything",
+                            "required": false,
+                            "schema": {
+                                "type": "boolean",
+                                "default": false
+                            }
+                        },
+                        {
+                            "name": "background",
+                            "in": "query",
+                            "description": "Queue the import as a job processed in the background, files up to 64MB are accepted",
+                            "required": false,
+                            "schema": {
+                                "type": "boolean",
+                                "default": false
+                            }
+                        }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "text/csv": {
+                                "schema": { "type": "string" }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Import report with the outcome of each row, or a preview for a dry run",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "oneOf": [
+                                            { "$ref": "#/components/schemas/ImportReport" },
+                                            { "$ref": "#/components/schemas/ImportPreview" }
+                                        ]
+                                    }
+                                }
+                            }
+                        },
+                        "202": {
+                            "description": "Import queued as a background job",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/ImportJob"
+                                    }
+                                }
+                            }
+                        },
+                        "400": {
+                            "description": "No records found in the file"
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "413": {
+                            "description": "File larger than 5MB, or 64MB in the background"
+                        }
+                    }
+                }
+            },
+            "/records/export": {
+                "get": {
+                    "summary": "Export records",
+                    "description": "Streams the authenticated user's collection, the CSV layout can be imported back",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "format",
+                            "in": "query",
+                            "description": "csv follows the Discogs collection export with an extra Tags column",
+                            "required": false,
+                            "schema": {
+                                "type": "string",
+                                "enum": ["csv", "json", "ndjson"],
+                                "default": "json"
+                            }
+                        },
+                        {
+                            "name": "owned",
+                            "in": "query",
+                            "description": "Filter by owned records",
+                            "required": false,
+                            "schema": {
+                                "type": "boolean"
+                            }
+                        },
+                        {
+                            "name": "wanted",
+                            "in": "query",
+                            "description": "Filter by wanted records",
+                            "required": false,
+                            "schema": {
+                                "type": "boolean"
+                            }
+                        },
+                        { "$ref": "#/components/parameters/Tag" },
+                        { "$ref": "#/components/parameters/TagMatch" },
+                        { "$ref": "#/components/parameters/Artist" },
+                        { "$ref": "#/components/parameters/ReleasedFrom" },
+                        { "$ref": "#/components/parameters/ReleasedTo" },
+                        { "$ref": "#/components/parameters/Q" }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Collection export",
+                            "content": {
+                                "text/csv": {
+                                    "schema": { "type": "string" }
+                                },
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": {
+                                            "$ref": "#/components/schemas/Record"
+                                        }
+                                    }
+                                },
+                                "application/x-ndjson": {
+                                    "schema": { "type": "string" }
+                                }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        }
+                    }
+                }
+            },
+            "/records/import/jobs/{id}": {
+                "get": {
+                    "summary": "Get an import job",
+                    "description": "Returns the status and progress of a background import",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "id",
+                            "in": "path",
+                            "required": true,
+                            "schema": { "type": "integer" }
+                        }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Import job",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/ImportJob"
+                                    }
+                                }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "404": {
+                            "description": "Import job not found"
+                        }
+                    }
+                }
+            },
+            "/records/enrich": {
+                "post": {
+                    "summary": "Enrich records",
+                    "description": "Queues the lookup of the user's Discogs records with a placeholder cover, no Spotify link or no tags. Covers, exact release dates, genres and Spotify links are filled in by a background job",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "responses": {
+                        "202": {
+                            "description": "Enrichment job queued",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/EnrichmentJob"
+                                    }
+                                }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "503": {
+                            "description": "Discogs is not configured"
+                        }
+                    }
+                }
+            },
+            "/records/enrich/jobs/{id}": {
+                "get": {
+                    "summary": "Get an enrichment job",
+                    "description": "Returns the progress of a background enrichment and the records it could not look up",
+                    "tags": ["Records"],
+                    "security": [{ "BearerAuth": [] }],
+                    "parameters": [
+                        {
+                            "name": "id",
+                            "in": "path",
+                            "required": true,
+                            "schema": { "type": "integer" }
+                        }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Enrichment job",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$ref": "#/components/schemas/EnrichmentJob"
+                                    }
+                                }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized"
+                        },
+                        "404": {
+                            "description": "Enrichment job not found"
+                        }
+                    }
+                }
+            },
+            "/records/collection/tokens": {
+                "post": {
                     "summary": "Create collection token",
                     "description": "Creates a new token for sharing a user's collection",
                     "tags": ["Collections"],
@@ -523,18 +1966,25 @@ pub async fn openapi_json() -> Json<Value> {
                                 "type": "string",
                                 "enum": ["html", "json"]
                             }
-                        }
+                        },
+                        { "$ref": "#/components/parameters/Tag" },
+                        { "$ref": "#/components/parameters/TagMatch" },
+                        { "$ref": "#/components/parameters/Artist" },
+                        { "$ref": "#/components/parameters/ReleasedFrom" },
+                        { "$ref": "#/components/parameters/ReleasedTo" },
+                        { "$ref": "#/components/parameters/Q" },
+                        { "$ref": "#/components/parameters/Limit" },
+                        { "$ref": "#/components/parameters/After" },
+                        { "$ref": "#/components/parameters/Sort" },
+                        { "$ref": "#/components/parameters/Direction" }
                     ],
                     "responses": {
                         "200": {
-                            "description": "Collection",
+                            "description": "Page of the collection",
                             "content": {
                                 "application/json": {
                                     "schema": {
-                                        "type": "array",
-                                        "items": {
-                                            "$ref": "#/components/schemas/Record"
-                                        }
+                                        "$ref": "#/components/schemas/RecordPage"
                                     }
                                 },
                                 "text/html": {
@@ -0,0 +1,200 @@
+use crate::dto::metadata_dto::ProviderAlbum;
+use chrono::{Datelike, NaiveDate};
+
+/// Pairs scoring below this are not considered the same album
+pub const MATCH_THRESHOLD: f64 = 0.75;
+
+const TITLE_WEIGHT: f64 = 0.6;
+const ARTIST_WEIGHT: f64 = 0.3;
+const YEAR_WEIGHT: f64 = 0.1;
+
+/// Words telling an edition apart rather than the album itself
+const EDITION_WORDS: [&str; 8] = [
+    "remaster",
+    "remastered",
+    "deluxe",
+    "edition",
+    "expanded",
+    "anniversary",
+    "version",
+    "bonus",
+];
+
+/// Lowercases, drops bracketed parts such as "(Remastered 2011)" or the Discogs "(2)"
+/// disambiguation, punctuation, edition words and a leading "the"
+pub fn normalize(value: &str) -> String {
+    let mut depth = 0;
+    let mut cleaned = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = (depth - 1).max(0),
+            '&' if depth == 0 => cleaned.push_str(" and "),
+            c if depth == 0 && c.is_alphanumeric() => cleaned.extend(c.to_lowercase()),
+            _ if depth == 0 => cleaned.push(' '),
+            _ => {}
+        }
+    }
+
+    let words: Vec<&str> = cleaned
+        .split_whitespace()
+        .filter(|word| !EDITION_WORDS.contains(word))
+        .collect();
+    match words.split_first() {
+        Some((&"the", rest)) if !rest.is_empty() => rest.join(" "),
+        _ => words.join(" "),
+    }
+}
+
+/// Levenshtein distance over the length of the longest string, between 0 and 1
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        // Nothing to compare is not a match
+        return 0.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+/// Providers fall back to the default date when they don't know the release
+fn known_year(date: NaiveDate) -> Option<i32> {
+    (date != NaiveDate::default() && date.year() > 0).then_some(date.year())
+}
+
+/// Confidence, between 0 and 1, that both albums are the same release.
+/// The year only counts when both providers know it
+pub fn score(a: &ProviderAlbum, b: &ProviderAlbum) -> f64 {
+    let title = similarity(&normalize(&a.title), &normalize(&b.title));
+    let artist = similarity(&normalize(&a.artist), &normalize(&b.artist));
+
+    match (known_year(a.release_date), known_year(b.release_date)) {
+        (Some(year_a), Some(year_b)) => {
+            let year = match (year_a - year_b).abs() {
+                0 => 1.0,
+                1 => 0.5,
+                _ => 0.0,
+            };
+            TITLE_WEIGHT * title + ARTIST_WEIGHT * artist + YEAR_WEIGHT * year
+        }
+        _ => (TITLE_WEIGHT * title + ARTIST_WEIGHT * artist) / (TITLE_WEIGHT + ARTIST_WEIGHT),
+    }
+}
+
+/// Pairs each album with the candidate it matches best, a candidate being used at most once.
+/// Returns, for every album, the index of its candidate when the confidence reaches
+/// the threshold, along with the best confidence found (`None` without free candidates)
+pub fn match_albums(
+    albums: &[ProviderAlbum],
+    candidates: &[ProviderAlbum],
+) -> Vec<(Option<usize>, Option<f64>)> {
+    let mut pairs: Vec<(usize, usize, f64)> = albums
+        .iter()
+        .enumerate()
+        .flat_map(|(i, album)| {
+            candidates
+                .iter()
+                .enumerate()
+                .map(move |(j, candidate)| (i, j, score(album, candidate)))
+        })
+        .collect();
+    // Best pairs are taken first so that a candidate goes to the album it fits most
+    pairs.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let mut matches: Vec<(Option<usize>, Option<f64>)> = vec![(None, None); albums.len()];
+    let mut taken = vec![false; candidates.len()];
+    for (i, j, confidence) in pairs {
+        let (matched, best) = &mut matches[i];
+        // A close candidate paired with another album says nothing about this one
+        if matched.is_some() || (taken[j] && confidence >= MATCH_THRESHOLD) {
+            continue;
+        }
+        if best.is_none() {
+            *best = Some(confidence);
+        }
+        if confidence >= MATCH_THRESHOLD {
+            *matched = Some(j);
+            *best = Some(confidence);
+            taken[j] = true;
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn album(title: &str, artist: &str, year: i32) -> ProviderAlbum {
+        ProviderAlbum {
+            title: title.to_string(),
+            artist: artist.to_string(),
+            release_date: NaiveDate::from_ymd_opt(year, 1, 1).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Abbey Road (Remastered 2019)"), "abbey road");
+        assert_eq!(normalize("The Beatles"), "beatles");
+        assert_eq!(normalize("Simon & Garfunkel"), "simon and garfunkel");
+        assert_eq!(normalize("Nirvana (2)"), "nirvana");
+        assert_eq!(normalize("Random Access Memories [Deluxe Edition]"), "random access memories");
+        assert_eq!(normalize("The The"), "the");
+        assert_eq!(normalize("Björk"), "björk");
+        assert_eq!(normalize(""), "");
+    }
+
+    #[test]
+    fn test_score() {
+        let discogs = album("Discovery", "Daft Punk", 2001);
+        assert!(score(&discogs, &album("Discovery", "Daft Punk", 2001)) > 0.99);
+        assert!(score(&discogs, &album("Discovery (Remastered)", "Daft Punk", 2001)) > 0.99);
+        assert!(score(&discogs, &album("Homework", "Daft Punk", 1997)) < MATCH_THRESHOLD);
+        assert!(score(&discogs, &album("Discovery", "Electric Light Orchestra", 1979)) < MATCH_THRESHOLD);
+
+        // An unknown year neither helps nor hurts
+        let undated = ProviderAlbum {
+            release_date: NaiveDate::default(),
+            ..album("Discovery", "Daft Punk", 2001)
+        };
+        assert!(score(&discogs, &undated) > 0.99);
+        assert_eq!(score(&album("", "", 2001), &album("", "", 2001)), 0.1);
+    }
+
+    #[test]
+    fn test_match_albums() {
+        let discogs = vec![
+            album("Kind Of Blue", "Miles Davis", 1959),
+            album("Sketches Of Spain", "Miles Davis", 1960),
+            album("Unknown Album", "Nobody", 1990),
+        ];
+        let spotify = vec![
+            album("Sketches of Spain", "Miles Davis", 1960),
+            album("Kind of Blue (Legacy Edition)", "Miles Davis", 1959),
+        ];
+
+        let matches = match_albums(&discogs, &spotify);
+        assert_eq!(matches[0].0, Some(1));
+        assert_eq!(matches[1].0, Some(0));
+        assert_eq!(matches[2].0, None);
+        assert!(matches[2].1.unwrap() < MATCH_THRESHOLD);
+
+        // Nothing to match against
+        assert_eq!(match_albums(&discogs, &[]), vec![(None, None); 3]);
+        assert!(match_albums(&[], &spotify).is_empty());
+    }
+}
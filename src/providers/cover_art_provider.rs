@@ -0,0 +1,106 @@
+use crate::dto::musicbrainz_dto::CoverArtRoot;
+use crate::error::app_error::AppError;
+use crate::providers::metadata_provider::{env_setting, CoverProvider};
+use crate::providers::musicbrainz_provider::MUSICBRAINZ_USER_AGENT;
+use crate::providers::outbound_client::{OutboundClient, OutboundPolicy};
+use tracing::instrument;
+
+pub const DEFAULT_COVER_ART_ARCHIVE_URL: &str = "https://coverartarchive.org";
+
+/// The Cover Art Archive has no rate limit, covers of a whole search are looked up at once
+fn cover_art_policy() -> OutboundPolicy {
+    OutboundPolicy {
+        burst: 25,
+        requests_per_second: 10.0,
+        ..Default::default()
+    }
+}
+
+/// Covers of MusicBrainz release groups, no credentials needed
+pub struct CoverArtArchiveProvider {
+    client: OutboundClient,
+    base_url: String,
+}
+
+impl CoverArtArchiveProvider {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            client: OutboundClient::new("Cover Art Archive", cover_art_policy()),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Reads `COVER_ART_ARCHIVE_URL`
+    pub fn from_env() -> Self {
+        let base_url = env_setting("COVER_ART_ARCHIVE_URL").unwrap_or(DEFAULT_COVER_ART_ARCHIVE_URL.to_string());
+        Self::new(&base_url)
+    }
+}
+
+#[async_trait]
+impl CoverProvider for CoverArtArchiveProvider {
+    #[instrument(name = "cover_art_provider/front_cover", skip(self))]
+    async fn front_cover(&self, musicbrainz_id: &str) -> Result<Option<String>, AppError> {
+        // Redirects to the images of the release chosen for the group
+        let response = self
+            .client
+            .send(|client| {
+                client
+                    .get(format!("{}/release-group/{}", self.base_url, musicbrainz_id))
+                    .header("User-Agent", MUSICBRAINZ_USER_AGENT)
+                    .header("Accept", "application/json")
+            })
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            tracing::error!("Cover Art Archive returned {} for {}", response.status(), musicbrainz_id);
+            return Err(AppError::UpstreamBadResponse { provider: self.client.provider() });
+        }
+
+        // Full size images are too heavy for a listing, a thumbnail is used when there is one
+        let covers = self.client.json::<CoverArtRoot>(response).await?;
+        let cover = covers
+            .images
+            .into_iter()
+            .find(|image| image.front)
+            .map(|image| image.thumbnails.medium.or(image.thumbnails.large).unwrap_or(image.image));
+        Ok(cover)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::http::serve;
+
+    #[rocket::async_test]
+    async fn test_front_cover_against_stub_server() {
+        let server = serve(vec![(
+            200,
+            r#"{
+                "release": "https://musicbrainz.org/release/1",
+                "images": [
+                    { "front": false, "image": "https://coverartarchive.org/back.jpg", "thumbnails": {} },
+                    {
+                        "front": true, "image": "https://coverartarchive.org/front.jpg",
+                        "thumbnails": { "250": "https://coverartarchive.org/front-250.jpg", "500": "https://coverartarchive.org/front-500.jpg" }
+                    }
+                ]
+            }"#
+            .to_string(),
+        )])
+        .await;
+
+        let provider = CoverArtArchiveProvider::new(&server.url);
+        let cover = provider.front_cover("5b11f4ce-a62d-471e-81fc-a69a8278c7da").await.unwrap();
+        assert_eq!(cover.as_deref(), Some("https://coverartarchive.org/front-500.jpg"));
+
+        // Release groups without cover are not found
+        let server = serve(vec![(404, "{}".to_string())]).await;
+        let provider = CoverArtArchiveProvider::new(&server.url);
+        assert_eq!(provider.front_cover("5b11f4ce-a62d-471e-81fc-a69a8278c7da").await.unwrap(), None);
+    }
+}
@@ -0,0 +1,459 @@
+use crate::dto::discogs_dto::{DiscogsArtist, DiscogsRecord, DiscogsRelease, DiscogsRoot};
+use crate::dto::metadata_dto::{DiscogsKind, ProviderAlbum, ReleaseCode, ReleaseId};
+use crate::error::app_error::AppError;
+use crate::providers::metadata_provider::{env_setting, MetadataProvider};
+use crate::providers::outbound_client::{OutboundClient, OutboundPolicy};
+use chrono::NaiveDate;
+use tracing::instrument;
+
+pub const DEFAULT_DISCOGS_API_URL: &str = "https://api.discogs.com";
+
+/// Discogs allows 60 authenticated requests per minute, a full burst included
+fn discogs_policy() -> OutboundPolicy {
+    OutboundPolicy {
+        burst: 5,
+        requests_per_second: 0.9,
+        ..Default::default()
+    }
+}
+
+pub struct DiscogsProvider {
+    client: OutboundClient,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl DiscogsProvider {
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        Self {
+            client: OutboundClient::new("Discogs", discogs_policy()),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    /// Reads `DISCOGS_API_URL` and `DISCOGS_SECRET`, the provider is disabled without a secret
+    pub fn from_env() -> Self {
+        let base_url = env_setting("DISCOGS_API_URL").unwrap_or(DEFAULT_DISCOGS_API_URL.to_string());
+        Self::new(&base_url, env_setting("DISCOGS_SECRET"))
+    }
+
+    /// Fetches a release or a master, `None` when Discogs doesn't know it
+    #[instrument(name = "discogs_provider/fetch_release", skip(self, token))]
+    async fn fetch_release(
+        &self,
+        token: &str,
+        kind: DiscogsKind,
+        id: i64,
+    ) -> Result<Option<DiscogsRelease>, AppError> {
+        let response = self
+            .client
+            .send(|client| {
+                client
+                    .get(format!("{}/{}s/{}", self.base_url, kind.path(), id))
+                    .header("Authorization", format!("Discogs token={}", token))
+                    .header("User-Agent", "vinyl-api")
+            })
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            tracing::error!("Discogs API returned {} for {} {}", response.status(), kind.path(), id);
+            return Err(AppError::UpstreamBadResponse { provider: self.client.provider() });
+        }
+
+        let release = self.client.json::<DiscogsRelease>(response).await?;
+        Ok(Some(release))
+    }
+
+    /// Results of a database search, `filters` narrowing it
+    async fn search_database(&self, token: &str, filters: &[(&str, &str)]) -> Result<Vec<DiscogsRecord>, AppError> {
+        let response = self
+            .client
+            .send(|client| {
+                client
+                    .get(format!("{}/database/search", self.base_url))
+                    .query(filters)
+                    .header("Authorization", format!("Discogs token={}", token))
+                    .header("User-Agent", "vinyl-api")
+                    .header("Content-Type", "application/json")
+            })
+            .await?;
+        Ok(self.client.json::<DiscogsRoot>(response).await?.results)
+    }
+}
+
+/// Website url of a release or a master, the same form as imported records
+pub fn discogs_web_url(kind: DiscogsKind, id: i64) -> String {
+    format!("https://www.discogs.com/{}/{}", kind.path(), id)
+}
+
+/// Release referenced by a website or API url such as ".../release/249504-Title"
+/// or ".../masters/1000"
+pub fn parse_discogs_url(url: &str) -> Option<ReleaseId> {
+    let mut segments = url.split(['/', '?', '#']);
+    let kind = segments.find_map(|segment| match segment {
+        "release" | "releases" => Some(DiscogsKind::Release),
+        "master" | "masters" => Some(DiscogsKind::Master),
+        _ => None,
+    })?;
+    let id = segments.next()?.split('-').next()?.parse().ok()?;
+    Some(ReleaseId::Discogs { kind, id })
+}
+
+/// Discogs names its masters "Artist - Title"
+fn split_title(title: &str) -> (String, String) {
+    match title.split_once(" - ") {
+        Some((artist, title)) => (artist.trim().to_string(), title.trim().to_string()),
+        None => (String::new(), title.trim().to_string()),
+    }
+}
+
+/// Album of a search result, its url is left to the caller
+fn search_album(record: &DiscogsRecord) -> ProviderAlbum {
+    // Discogs only knows the release year
+    let year = record
+        .year
+        .as_deref()
+        .and_then(|year| year.parse().ok())
+        .unwrap_or(0);
+
+    let (artist, title) = split_title(&record.title);
+    ProviderAlbum {
+        title,
+        artist,
+        release_date: NaiveDate::from_ymd_opt(year, 1, 1).unwrap_or_default(),
+        cover_url: Some(record.cover_image.clone()),
+        genres: record.genre.clone(),
+        styles: record.style.clone(),
+        ..Default::default()
+    }
+}
+
+/// Drops the "(2)" Discogs adds to tell namesakes apart and the "*" marking a name variation
+fn clean_artist_name(name: &str) -> &str {
+    let name = name.trim().trim_end_matches('*');
+    match name.rsplit_once(" (") {
+        Some((base, suffix)) if suffix.ends_with(')') && suffix[..suffix.len() - 1].parse::<u32>().is_ok() => base,
+        _ => name,
+    }
+}
+
+/// Credited artists joined the way Discogs shows them, "Simon & Garfunkel"
+fn artist_names(artists: &[DiscogsArtist]) -> String {
+    let mut names = String::new();
+    for (index, artist) in artists.iter().enumerate() {
+        names.push_str(clean_artist_name(&artist.name));
+        if index + 1 < artists.len() {
+            match artist.join.trim() {
+                "" | "," => names.push_str(", "),
+                join => names.push_str(&format!(" {} ", join)),
+            }
+        }
+    }
+    names
+}
+
+/// Release dates are "2001-03-12", "2001-03-00" or "2001" when the day or month is unknown
+fn parse_released(released: Option<&str>, year: Option<i32>) -> NaiveDate {
+    let mut parts = released.unwrap_or_default().split('-').map(|part| part.parse::<u32>().ok());
+    let parsed_year = parts.next().flatten().map(|year| year as i32);
+    let month = parts.next().flatten().filter(|month| *month > 0).unwrap_or(1);
+    let day = parts.next().flatten().filter(|day| *day > 0).unwrap_or(1);
+
+    match parsed_year.filter(|year| *year > 0).or(year.filter(|year| *year > 0)) {
+        Some(year) => NaiveDate::from_ymd_opt(year, month, day)
+            .or(NaiveDate::from_ymd_opt(year, 1, 1))
+            .unwrap_or_default(),
+        None => NaiveDate::default(),
+    }
+}
+
+/// Turns a Discogs release into an album, `pressing` gives the label and format of a master
+fn release_album(kind: DiscogsKind, release: DiscogsRelease, pressing: Option<DiscogsRelease>) -> ProviderAlbum {
+    let pressing = pressing.as_ref().unwrap_or(&release);
+    let label = pressing.labels.first();
+    let format = pressing.formats.first().map(|format| {
+        std::iter::once(format.name.as_str())
+            .chain(format.descriptions.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(", ")
+    });
+    let cover_url = release
+        .images
+        .iter()
+        .find(|image| image.type_field == "primary")
+        .or(release.images.first())
+        .map(|image| image.uri.clone());
+
+    ProviderAlbum {
+        title: release.title.trim().to_string(),
+        artist: artist_names(&release.artists),
+        release_date: parse_released(release.released.as_deref(), release.year),
+        cover_url,
+        discogs_url: Some(discogs_web_url(kind, release.id)),
+        spotify_url: None,
+        musicbrainz_id: None,
+        genres: release.genres.clone(),
+        styles: release.styles.clone(),
+        label: label.map(|label| clean_artist_name(&label.name).to_string()),
+        // Catalog numbers belong to a single pressing
+        catalog_number: match kind {
+            DiscogsKind::Release => label.and_then(|label| label.catno.clone()),
+            DiscogsKind::Master => None,
+        },
+        format,
+    }
+}
+
+#[async_trait]
+impl MetadataProvider for DiscogsProvider {
+    fn name(&self) -> &'static str {
+        "discogs"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.token.is_some()
+    }
+
+    #[instrument(name = "discogs_provider/search", skip_all)]
+    async fn search(&self, query: &str) -> Result<Vec<ProviderAlbum>, AppError> {
+        let Some(token) = &self.token else {
+            return Ok(Vec::new());
+        };
+
+        let results = self
+            .search_database(token, &[("q", query), ("type", "master")])
+            .await?;
+
+        let albums = results
+            .into_iter()
+            .map(|record| ProviderAlbum {
+                // The API url of the master, in the website form of stored records
+                discogs_url: record
+                    .master_url
+                    .as_deref()
+                    .and_then(parse_discogs_url)
+                    .map(|ReleaseId::Discogs { kind, id }| discogs_web_url(kind, id)),
+                ..search_album(&record)
+            })
+            .collect();
+
+        Ok(albums)
+    }
+
+    #[instrument(name = "discogs_provider/lookup", skip_all)]
+    async fn lookup(&self, code: &ReleaseCode) -> Result<Vec<ProviderAlbum>, AppError> {
+        let Some(token) = &self.token else {
+            return Ok(Vec::new());
+        };
+
+        // Pressings carry the codes, not masters
+        let results = match code {
+            ReleaseCode::Barcode(_) => {
+                let barcode = code.normalized();
+                self.search_database(token, &[("barcode", barcode.as_str()), ("type", "release")])
+                    .await?
+            }
+            ReleaseCode::CatalogNumber(catalog_number) => {
+                self.search_database(token, &[("catno", catalog_number.trim()), ("type", "release")])
+                    .await?
+            }
+        };
+
+        let albums = results
+            .into_iter()
+            .map(|record| ProviderAlbum {
+                discogs_url: Some(discogs_web_url(DiscogsKind::Release, record.id)),
+                label: record.label.first().map(|label| clean_artist_name(label).to_string()),
+                catalog_number: Some(record.catno.trim().to_string()).filter(|catno| !catno.is_empty()),
+                format: Some(record.format.join(", ")).filter(|format| !format.is_empty()),
+                ..search_album(&record)
+            })
+            .collect();
+
+        Ok(albums)
+    }
+
+    #[instrument(name = "discogs_provider/release", skip_all)]
+    async fn release(&self, id: &ReleaseId) -> Result<Option<ProviderAlbum>, AppError> {
+        let Some(token) = &self.token else {
+            return Ok(None);
+        };
+        let ReleaseId::Discogs { kind, id } = *id;
+
+        let Some(release) = self.fetch_release(token, kind, id).await? else {
+            return Ok(None);
+        };
+        // Masters have no label, the one of their main pressing is used
+        let pressing = match (kind, release.main_release) {
+            (DiscogsKind::Master, Some(main_release)) => {
+                self.fetch_release(token, DiscogsKind::Release, main_release).await?
+            }
+            _ => None,
+        };
+
+        Ok(Some(release_album(kind, release, pressing)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::http::serve;
+
+    #[test]
+    fn test_split_title() {
+        let split = |artist: &str, title: &str| (artist.to_string(), title.to_string());
+        assert_eq!(split_title("Daft Punk - Discovery"), split("Daft Punk", "Discovery"));
+        assert_eq!(split_title("Jay-Z - The Blueprint - Live"), split("Jay-Z", "The Blueprint - Live"));
+        assert_eq!(split_title("Discovery"), split("", "Discovery"));
+    }
+
+    #[rocket::async_test]
+    async fn test_search_against_stub_server() {
+        let server = serve(vec![(
+            200,
+            r#"{
+                "pagination": { "page": 1, "pages": 1, "per_page": 50, "items": 1, "urls": {} },
+                "results": [{
+                    "country": "US", "year": "1959", "format": [], "label": [], "type": "master",
+                    "genre": [], "style": [], "id": 1, "barcode": [],
+                    "user_data": { "in_wantlist": false, "in_collection": false },
+                    "master_id": 1, "master_url": "https://api.discogs.com/masters/1",
+                    "uri": "/master/1", "catno": "", "title": "Miles Davis - Kind Of Blue",
+                    "thumb": "", "cover_image": "https://i.discogs.com/1.jpg",
+                    "resource_url": "", "community": { "want": 0, "have": 0 }
+                }]
+            }"#
+            .to_string(),
+        )])
+        .await;
+
+        let provider = DiscogsProvider::new(&server.url, Some("token".to_string()));
+        let albums = provider.search("kind of blue").await.unwrap();
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].artist, "Miles Davis");
+        assert_eq!(albums[0].title, "Kind Of Blue");
+        assert_eq!(albums[0].release_date, NaiveDate::from_ymd_opt(1959, 1, 1).unwrap());
+        assert_eq!(albums[0].discogs_url.as_deref(), Some("https://www.discogs.com/master/1"));
+
+        // Without a secret no request is sent
+        let provider = DiscogsProvider::new("http://127.0.0.1:1", None);
+        assert!(!provider.is_enabled());
+        assert!(provider.search("kind of blue").await.unwrap().is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn test_lookup_by_barcode_against_stub_server() {
+        let server = serve(vec![(
+            200,
+            r#"{
+                "pagination": { "page": 1, "pages": 1, "per_page": 50, "items": 1, "urls": {} },
+                "results": [{
+                    "country": "Europe", "year": "2001", "format": ["Vinyl", "LP", "Album"],
+                    "label": ["Virgin", "Daft Trax"], "type": "release",
+                    "genre": ["Electronic"], "style": ["House"], "id": 20, "barcode": ["724384960612"],
+                    "user_data": { "in_wantlist": false, "in_collection": false },
+                    "master_id": 10, "master_url": null,
+                    "uri": "/release/20", "catno": "7243 8 49606 1 2", "title": "Daft Punk - Discovery",
+                    "thumb": "", "cover_image": "https://i.discogs.com/20.jpg",
+                    "resource_url": "", "community": { "want": 0, "have": 0 }
+                }]
+            }"#
+            .to_string(),
+        )])
+        .await;
+
+        let provider = DiscogsProvider::new(&server.url, Some("token".to_string()));
+        let code = ReleaseCode::Barcode("7 24384 96061 2".to_string());
+        let albums = provider.lookup(&code).await.unwrap();
+        assert_eq!(albums.len(), 1);
+        assert_eq!((albums[0].artist.as_str(), albums[0].title.as_str()), ("Daft Punk", "Discovery"));
+        assert_eq!(albums[0].discogs_url.as_deref(), Some("https://www.discogs.com/release/20"));
+        assert_eq!(albums[0].catalog_number.as_deref(), Some("7243 8 49606 1 2"));
+        assert_eq!(albums[0].label.as_deref(), Some("Virgin"));
+        assert_eq!(albums[0].format.as_deref(), Some("Vinyl, LP, Album"));
+        assert_eq!(albums[0].genres, vec!["Electronic"]);
+        assert_eq!(albums[0].styles, vec!["House"]);
+    }
+
+    #[test]
+    fn test_parse_discogs_url() {
+        let release = |id| Some(ReleaseId::Discogs { kind: DiscogsKind::Release, id });
+        let master = |id| Some(ReleaseId::Discogs { kind: DiscogsKind::Master, id });
+        assert_eq!(parse_discogs_url("https://www.discogs.com/release/249504"), release(249504));
+        assert_eq!(parse_discogs_url("https://www.discogs.com/release/249504-Rick-Astley"), release(249504));
+        assert_eq!(parse_discogs_url("https://www.discogs.com/fr/master/1000?image=1"), master(1000));
+        assert_eq!(parse_discogs_url("https://api.discogs.com/masters/1000"), master(1000));
+        assert_eq!(parse_discogs_url("https://www.discogs.com/artist/1-Daft-Punk"), None);
+        assert_eq!(parse_discogs_url("https://www.discogs.com/release/"), None);
+    }
+
+    #[test]
+    fn test_artist_names() {
+        let artist = |name: &str, join: &str| DiscogsArtist {
+            name: name.to_string(),
+            join: join.to_string(),
+        };
+        assert_eq!(artist_names(&[artist("Nirvana (2)", "")]), "Nirvana");
+        assert_eq!(artist_names(&[artist("Simon", "&"), artist("Garfunkel*", "")]), "Simon & Garfunkel");
+        assert_eq!(artist_names(&[artist("A", ","), artist("B", "")]), "A, B");
+        assert_eq!(artist_names(&[artist("Area (Live)", "")]), "Area (Live)");
+        assert_eq!(artist_names(&[]), "");
+    }
+
+    #[test]
+    fn test_parse_released() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(parse_released(Some("2001-03-12"), None), date(2001, 3, 12));
+        assert_eq!(parse_released(Some("2001-03-00"), None), date(2001, 3, 1));
+        assert_eq!(parse_released(Some("2001"), Some(1999)), date(2001, 1, 1));
+        assert_eq!(parse_released(None, Some(1999)), date(1999, 1, 1));
+        assert_eq!(parse_released(Some("unknown"), Some(0)), NaiveDate::default());
+    }
+
+    #[rocket::async_test]
+    async fn test_release_of_a_master_takes_the_label_of_its_main_release() {
+        let master = r#"{
+            "id": 10, "title": "Discovery", "year": 2001, "main_release": 20,
+            "artists": [{ "name": "Daft Punk", "join": "" }],
+            "images": [
+                { "type": "secondary", "uri": "https://i.discogs.com/back.jpg" },
+                { "type": "primary", "uri": "https://i.discogs.com/front.jpg" }
+            ],
+            "genres": ["Electronic"], "styles": ["House", "Disco"]
+        }"#;
+        let release = r#"{
+            "id": 20, "title": "Discovery", "year": 2001, "released": "2001-03-12",
+            "artists": [{ "name": "Daft Punk", "join": "" }],
+            "labels": [{ "name": "Virgin", "catno": "7243 8 49606 1 2" }],
+            "formats": [{ "name": "Vinyl", "descriptions": ["LP", "Album"] }]
+        }"#;
+        let server = serve(vec![(200, master.to_string()), (200, release.to_string())]).await;
+
+        let provider = DiscogsProvider::new(&server.url, Some("token".to_string()));
+        let id = ReleaseId::Discogs { kind: DiscogsKind::Master, id: 10 };
+        let album = provider.release(&id).await.unwrap().unwrap();
+        assert_eq!(server.hits(), 2);
+        assert_eq!((album.artist.as_str(), album.title.as_str()), ("Daft Punk", "Discovery"));
+        assert_eq!(album.release_date, NaiveDate::from_ymd_opt(2001, 1, 1).unwrap());
+        assert_eq!(album.cover_url.as_deref(), Some("https://i.discogs.com/front.jpg"));
+        assert_eq!(album.discogs_url.as_deref(), Some("https://www.discogs.com/master/10"));
+        assert_eq!(album.genres, vec!["Electronic"]);
+        assert_eq!(album.styles, vec!["House", "Disco"]);
+        assert_eq!(album.label.as_deref(), Some("Virgin"));
+        assert_eq!(album.catalog_number, None);
+        assert_eq!(album.format.as_deref(), Some("Vinyl, LP, Album"));
+    }
+
+    #[rocket::async_test]
+    async fn test_unknown_release_is_not_found() {
+        let server = serve(vec![(404, r#"{"message": "Release not found."}"#.to_string())]).await;
+        let provider = DiscogsProvider::new(&server.url, Some("token".to_string()));
+        let id = ReleaseId::Discogs { kind: DiscogsKind::Release, id: 1 };
+        assert_eq!(provider.release(&id).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::dto::metadata_dto::{ProviderAlbum, ReleaseCode, ReleaseId};
+use crate::error::app_error::AppError;
+use mockall::automock;
+
+#[automock]
+#[async_trait]
+pub trait MetadataProvider: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Whether the provider has the credentials it needs,
+    /// a disabled provider is skipped instead of failing the request
+    fn is_enabled(&self) -> bool;
+
+    /// Albums matching a free text query
+    async fn search(&self, query: &str) -> Result<Vec<ProviderAlbum>, AppError>;
+
+    /// Details of a single release, `None` when it doesn't exist.
+    /// Ids of other providers are never found
+    async fn release(&self, id: &ReleaseId) -> Result<Option<ProviderAlbum>, AppError>;
+
+    /// Releases carrying a barcode or a catalog number,
+    /// empty when the provider can't look them up
+    async fn lookup(&self, code: &ReleaseCode) -> Result<Vec<ProviderAlbum>, AppError>;
+}
+
+#[automock]
+#[async_trait]
+pub trait CoverProvider: Send + Sync {
+    /// Url of the front cover of a MusicBrainz release group, `None` when it has none
+    async fn front_cover(&self, musicbrainz_id: &str) -> Result<Option<String>, AppError>;
+}
+
+/// Reads an optional setting, blank values count as missing
+pub fn env_setting(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
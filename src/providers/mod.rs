@@ -0,0 +1,9 @@
+pub mod metadata_provider;
+pub mod outbound_client;
+pub mod album_matcher;
+pub mod discogs_provider;
+pub mod spotify_provider;
+pub mod musicbrainz_provider;
+pub mod cover_art_provider;
+pub mod spotify_token;
+pub mod providers;
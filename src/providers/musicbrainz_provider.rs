@@ -0,0 +1,186 @@
+use crate::dto::metadata_dto::{ProviderAlbum, ReleaseCode, ReleaseId};
+use crate::dto::musicbrainz_dto::{MusicBrainzArtistCredit, MusicBrainzSearch};
+use crate::error::app_error::AppError;
+use crate::providers::metadata_provider::{env_setting, MetadataProvider};
+use crate::providers::outbound_client::{OutboundClient, OutboundPolicy};
+use chrono::NaiveDate;
+use tracing::instrument;
+
+pub const DEFAULT_MUSICBRAINZ_API_URL: &str = "https://musicbrainz.org";
+
+/// MusicBrainz refuses requests without a User-Agent naming the application
+pub const MUSICBRAINZ_USER_AGENT: &str = concat!("vinyl-api/", env!("CARGO_PKG_VERSION"));
+
+/// Release groups returned by a search
+const SEARCH_LIMIT: &str = "25";
+
+/// MusicBrainz allows a single request per second
+fn musicbrainz_policy() -> OutboundPolicy {
+    OutboundPolicy {
+        burst: 1,
+        requests_per_second: 1.0,
+        ..Default::default()
+    }
+}
+
+/// Searches MusicBrainz release groups, no credentials needed
+pub struct MusicBrainzProvider {
+    client: OutboundClient,
+    base_url: String,
+}
+
+impl MusicBrainzProvider {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            client: OutboundClient::new("MusicBrainz", musicbrainz_policy()),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Reads `MUSICBRAINZ_API_URL`
+    pub fn from_env() -> Self {
+        let base_url = env_setting("MUSICBRAINZ_API_URL").unwrap_or(DEFAULT_MUSICBRAINZ_API_URL.to_string());
+        Self::new(&base_url)
+    }
+}
+
+/// Escapes the characters the Lucene syntax of MusicBrainz searches gives a meaning to
+fn escape_query(query: &str) -> String {
+    let mut escaped = String::with_capacity(query.len());
+    for c in query.chars() {
+        if "+-&|!(){}[]^\"~*?:\\/".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Credited artists joined the way the release group shows them, such as "Simon & Garfunkel"
+fn artist_credit(credits: &[MusicBrainzArtistCredit]) -> String {
+    credits
+        .iter()
+        .map(|credit| format!("{}{}", credit.name, credit.joinphrase))
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// MusicBrainz dates are as precise as it knows them, "2001-03-12", "2001-03" or "2001"
+fn parse_first_release_date(date: &str) -> NaiveDate {
+    let date = match date.len() {
+        4 => format!("{}-01-01", date),
+        7 => format!("{}-01", date),
+        _ => date.to_string(),
+    };
+    NaiveDate::parse_from_str(&date, "%Y-%m-%d").unwrap_or_default()
+}
+
+#[async_trait]
+impl MetadataProvider for MusicBrainzProvider {
+    fn name(&self) -> &'static str {
+        "musicbrainz"
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    #[instrument(name = "musicbrainz_provider/search", skip_all)]
+    async fn search(&self, query: &str) -> Result<Vec<ProviderAlbum>, AppError> {
+        let query = escape_query(query);
+        let response = self
+            .client
+            .send(|client| {
+                client
+                    .get(format!("{}/ws/2/release-group", self.base_url))
+                    .query(&[("query", query.as_str()), ("limit", SEARCH_LIMIT), ("fmt", "json")])
+                    .header("User-Agent", MUSICBRAINZ_USER_AGENT)
+                    .header("Accept", "application/json")
+            })
+            .await?;
+
+        if !response.status().is_success() {
+            tracing::error!("MusicBrainz API returned {} for {}", response.status(), query);
+            return Err(AppError::UpstreamBadResponse { provider: self.client.provider() });
+        }
+        let search = self.client.json::<MusicBrainzSearch>(response).await?;
+
+        let albums = search
+            .release_groups
+            .into_iter()
+            .map(|group| ProviderAlbum {
+                artist: artist_credit(&group.artist_credit),
+                release_date: parse_first_release_date(&group.first_release_date),
+                cover_url: None,
+                discogs_url: None,
+                spotify_url: None,
+                musicbrainz_id: Some(group.id),
+                title: group.title,
+                ..Default::default()
+            })
+            .collect();
+
+        Ok(albums)
+    }
+
+    async fn release(&self, _id: &ReleaseId) -> Result<Option<ProviderAlbum>, AppError> {
+        Ok(None)
+    }
+
+    async fn lookup(&self, _code: &ReleaseCode) -> Result<Vec<ProviderAlbum>, AppError> {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::http::serve;
+
+    #[test]
+    fn test_escape_query() {
+        assert_eq!(escape_query("daft punk"), "daft punk");
+        assert_eq!(escape_query("AC/DC: Back in Black"), "AC\\/DC\\: Back in Black");
+        assert_eq!(escape_query("(What's the Story)"), "\\(What's the Story\\)");
+    }
+
+    #[test]
+    fn test_parse_first_release_date() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(parse_first_release_date("2001-03-12"), date(2001, 3, 12));
+        assert_eq!(parse_first_release_date("2001-03"), date(2001, 3, 1));
+        assert_eq!(parse_first_release_date("2001"), date(2001, 1, 1));
+        assert_eq!(parse_first_release_date(""), NaiveDate::default());
+    }
+
+    #[rocket::async_test]
+    async fn test_search_against_stub_server() {
+        let server = serve(vec![(
+            200,
+            r#"{
+                "created": "2025-04-25T09:00:00.000Z", "count": 1, "offset": 0,
+                "release-groups": [{
+                    "id": "5b11f4ce-a62d-471e-81fc-a69a8278c7da", "score": 100,
+                    "primary-type": "Album", "title": "Bookends",
+                    "first-release-date": "1968-04-03",
+                    "artist-credit": [
+                        { "name": "Simon", "joinphrase": " & ", "artist": { "id": "1", "name": "Paul Simon" } },
+                        { "name": "Garfunkel", "artist": { "id": "2", "name": "Art Garfunkel" } }
+                    ]
+                }]
+            }"#
+            .to_string(),
+        )])
+        .await;
+
+        let provider = MusicBrainzProvider::new(&server.url);
+        let albums = provider.search("bookends").await.unwrap();
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].artist, "Simon & Garfunkel");
+        assert_eq!(albums[0].title, "Bookends");
+        assert_eq!(albums[0].release_date, NaiveDate::from_ymd_opt(1968, 4, 3).unwrap());
+        assert_eq!(albums[0].musicbrainz_id.as_deref(), Some("5b11f4ce-a62d-471e-81fc-a69a8278c7da"));
+        assert_eq!(albums[0].cover_url, None);
+    }
+}
@@ -0,0 +1,327 @@
+use crate::error::app_error::AppError;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::sync::{Arc, Mutex as SyncMutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Limits and retries applied to the requests sent to an external service
+#[derive(Debug, Clone)]
+pub struct OutboundPolicy {
+    /// Requests sent at once before the rate limit applies
+    pub burst: u32,
+    /// Requests allowed per second once the burst is spent
+    pub requests_per_second: f64,
+    pub timeout: Duration,
+    /// Retries of a request failing with a timeout, a 429 or a 5xx
+    pub max_retries: u32,
+    /// First backoff delay, doubled on every retry
+    pub backoff: Duration,
+    /// Longest delay waited before a retry, `Retry-After` included
+    pub max_backoff: Duration,
+    /// Consecutive failures opening the circuit
+    pub failure_threshold: u32,
+    /// How long an open circuit fails requests without sending them
+    pub open_duration: Duration,
+}
+
+impl Default for OutboundPolicy {
+    fn default() -> Self {
+        Self {
+            burst: 5,
+            requests_per_second: 5.0,
+            timeout: Duration::from_secs(10),
+            max_retries: 3,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Token bucket refilled continuously at the policy rate
+struct RateLimiter {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Counts consecutive failures, requests are refused while the circuit is open
+struct CircuitBreaker {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+struct Inner {
+    provider: &'static str,
+    policy: OutboundPolicy,
+    client: reqwest::Client,
+    /// Held while waiting for a token so requests go out in order
+    limiter: Mutex<RateLimiter>,
+    breaker: SyncMutex<CircuitBreaker>,
+}
+
+/// HTTP client shared by the requests to one external service,
+/// cloning it shares its rate limit and circuit breaker
+#[derive(Clone)]
+pub struct OutboundClient {
+    inner: Arc<Inner>,
+}
+
+/// Why an attempt did not get a usable response
+enum Failure {
+    Timeout,
+    RateLimited(Option<Duration>),
+    Unavailable,
+}
+
+impl OutboundClient {
+    pub fn new(provider: &'static str, policy: OutboundPolicy) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(policy.timeout)
+            .build()
+            .unwrap_or_default();
+        Self {
+            inner: Arc::new(Inner {
+                provider,
+                limiter: Mutex::new(RateLimiter {
+                    tokens: policy.burst as f64,
+                    refilled_at: Instant::now(),
+                }),
+                breaker: SyncMutex::new(CircuitBreaker {
+                    failures: 0,
+                    open_until: None,
+                }),
+                policy,
+                client,
+            }),
+        }
+    }
+
+    /// Name of the service, used in errors
+    pub fn provider(&self) -> &'static str {
+        self.inner.provider
+    }
+
+    /// Sends the request built by `build`, built again for every retry. Timeouts, 429 and 5xx
+    /// responses are retried with an exponential backoff or after `Retry-After`,
+    /// any other response is returned as is
+    pub async fn send<F>(&self, build: F) -> Result<Response, AppError>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let policy = &self.inner.policy;
+        let mut attempt = 0;
+        loop {
+            self.check_circuit()?;
+            self.acquire().await;
+
+            let failure = match build(&self.inner.client).send().await {
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    Failure::RateLimited(retry_after(&response))
+                }
+                Ok(response) if response.status().is_server_error() => Failure::Unavailable,
+                Ok(response) => {
+                    self.record_success();
+                    return Ok(response);
+                }
+                Err(e) if e.is_timeout() => Failure::Timeout,
+                Err(e) => {
+                    tracing::warn!("Request to {} failed: {}", self.provider(), e);
+                    Failure::Unavailable
+                }
+            };
+
+            // Being rate limited says nothing about the health of the service
+            if !matches!(failure, Failure::RateLimited(_)) {
+                self.record_failure();
+            }
+
+            let delay = match failure {
+                Failure::RateLimited(Some(retry_after)) => retry_after,
+                _ => policy.backoff.saturating_mul(2u32.saturating_pow(attempt)),
+            };
+            if attempt >= policy.max_retries || delay > policy.max_backoff {
+                return Err(match failure {
+                    Failure::Timeout => AppError::UpstreamTimeout { provider: self.provider() },
+                    Failure::RateLimited(_) => AppError::UpstreamRateLimited { provider: self.provider() },
+                    Failure::Unavailable => AppError::UpstreamUnavailable { provider: self.provider() },
+                });
+            }
+
+            tracing::warn!("Retrying a request to {} in {:?}", self.provider(), delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Reads a JSON body, a body of another shape is a bad response
+    pub async fn json<T: serde::de::DeserializeOwned>(&self, response: Response) -> Result<T, AppError> {
+        response.json::<T>().await.map_err(|e| {
+            tracing::error!("Invalid response from {}: {}", self.provider(), e);
+            AppError::UpstreamBadResponse { provider: self.provider() }
+        })
+    }
+
+    /// Waits for a token of the bucket
+    async fn acquire(&self) {
+        let policy = &self.inner.policy;
+        let mut limiter = self.inner.limiter.lock().await;
+        loop {
+            let now = Instant::now();
+            let elapsed = now.duration_since(limiter.refilled_at).as_secs_f64();
+            limiter.tokens = (limiter.tokens + elapsed * policy.requests_per_second).min(policy.burst as f64);
+            limiter.refilled_at = now;
+
+            if limiter.tokens >= 1.0 {
+                limiter.tokens -= 1.0;
+                return;
+            }
+            let missing = (1.0 - limiter.tokens) / policy.requests_per_second;
+            tokio::time::sleep(Duration::from_secs_f64(missing)).await;
+        }
+    }
+
+    /// Refuses the request while the circuit is open, lets one through once it's due to close
+    fn check_circuit(&self) -> Result<(), AppError> {
+        let mut breaker = self.inner.breaker.lock().unwrap_or_else(|e| e.into_inner());
+        match breaker.open_until {
+            Some(open_until) if Instant::now() < open_until => {
+                Err(AppError::UpstreamUnavailable { provider: self.provider() })
+            }
+            Some(_) => {
+                // Half open, the next failure opens the circuit again
+                breaker.open_until = None;
+                breaker.failures = self.inner.policy.failure_threshold.saturating_sub(1);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn record_success(&self) {
+        let mut breaker = self.inner.breaker.lock().unwrap_or_else(|e| e.into_inner());
+        breaker.failures = 0;
+    }
+
+    fn record_failure(&self) {
+        let policy = &self.inner.policy;
+        let mut breaker = self.inner.breaker.lock().unwrap_or_else(|e| e.into_inner());
+        breaker.failures += 1;
+        if breaker.failures >= policy.failure_threshold && breaker.open_until.is_none() {
+            tracing::error!("Too many failures, requests to {} are paused for {:?}", self.provider(), policy.open_duration);
+            breaker.open_until = Some(Instant::now() + policy.open_duration);
+        }
+    }
+}
+
+/// Delay asked by a 429 response, in seconds
+fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim().parse::<u64>().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::http::{serve, serve_with_headers};
+    use tokio::net::TcpListener;
+
+    fn policy() -> OutboundPolicy {
+        OutboundPolicy {
+            burst: 10,
+            requests_per_second: 100.0,
+            timeout: Duration::from_millis(200),
+            max_retries: 2,
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(2),
+            failure_threshold: 3,
+            open_duration: Duration::from_secs(60),
+        }
+    }
+
+    #[rocket::async_test]
+    async fn test_server_errors_are_retried() {
+        let server = serve(vec![(503, "{}".to_string()), (500, "{}".to_string()), (200, "{}".to_string())]).await;
+        let client = OutboundClient::new("Stub", policy());
+        let response = client.send(|client| client.get(&server.url)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(server.hits(), 3);
+
+        // Client errors are the caller's business
+        let server = serve(vec![(404, "{}".to_string())]).await;
+        let response = client.send(|client| client.get(&server.url)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(server.hits(), 1);
+    }
+
+    #[rocket::async_test]
+    async fn test_retry_after_is_honored() {
+        let server = serve_with_headers(vec![
+            (429, vec![("Retry-After", "1".to_string())], "{}".to_string()),
+            (200, Vec::new(), "{}".to_string()),
+        ])
+        .await;
+        let client = OutboundClient::new("Stub", policy());
+        let started = Instant::now();
+        client.send(|client| client.get(&server.url)).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(server.hits(), 2);
+
+        // Waiting longer than the policy allows gives up at once
+        let server = serve_with_headers(vec![(429, vec![("Retry-After", "60".to_string())], "{}".to_string())]).await;
+        let result = client.send(|client| client.get(&server.url)).await;
+        assert!(matches!(result, Err(AppError::UpstreamRateLimited { provider: "Stub" })));
+        assert_eq!(server.hits(), 1);
+    }
+
+    #[rocket::async_test]
+    async fn test_circuit_opens_after_repeated_failures() {
+        let server = serve(vec![(502, "{}".to_string())]).await;
+        let client = OutboundClient::new("Stub", policy());
+
+        let result = client.send(|client| client.get(&server.url)).await;
+        assert!(matches!(result, Err(AppError::UpstreamUnavailable { .. })));
+        assert_eq!(server.hits(), 3);
+
+        // The circuit is open, nothing is sent
+        let result = client.send(|client| client.get(&server.url)).await;
+        assert!(matches!(result, Err(AppError::UpstreamUnavailable { .. })));
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[rocket::async_test]
+    async fn test_slow_service_times_out() {
+        // Accepts connections but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+
+        let client = OutboundClient::new("Stub", OutboundPolicy { max_retries: 0, ..policy() });
+        let result = client.send(|client| client.get(&url)).await;
+        assert!(matches!(result, Err(AppError::UpstreamTimeout { provider: "Stub" })));
+        assert_eq!(result.unwrap_err().status_code(), 504);
+    }
+
+    #[rocket::async_test]
+    async fn test_requests_are_rate_limited() {
+        let server = serve(vec![(200, "{}".to_string())]).await;
+        let client = OutboundClient::new(
+            "Stub",
+            OutboundPolicy { burst: 2, requests_per_second: 10.0, ..policy() },
+        );
+
+        // The burst goes out at once, the next two wait 100ms each
+        let started = Instant::now();
+        for _ in 0..4 {
+            client.send(|client| client.get(&server.url)).await.unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(190));
+        assert_eq!(server.hits(), 4);
+    }
+}
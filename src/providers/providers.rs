@@ -0,0 +1,33 @@
+use crate::providers::cover_art_provider::CoverArtArchiveProvider;
+use crate::providers::discogs_provider::DiscogsProvider;
+use crate::providers::metadata_provider::{CoverProvider, MetadataProvider};
+use crate::providers::musicbrainz_provider::MusicBrainzProvider;
+use crate::providers::spotify_provider::SpotifyProvider;
+
+/// External services records are looked up on
+pub struct Providers {
+    pub discogs: Box<dyn MetadataProvider>,
+    pub spotify: Box<dyn MetadataProvider>,
+    pub musicbrainz: Box<dyn MetadataProvider>,
+    /// Covers of the MusicBrainz results
+    pub cover_art: Box<dyn CoverProvider>,
+}
+
+impl Providers {
+    pub fn new() -> Self {
+        let providers = Self {
+            discogs: Box::new(DiscogsProvider::from_env()),
+            spotify: Box::new(SpotifyProvider::from_env()),
+            musicbrainz: Box::new(MusicBrainzProvider::from_env()),
+            cover_art: Box::new(CoverArtArchiveProvider::from_env()),
+        };
+
+        for provider in [&providers.discogs, &providers.spotify, &providers.musicbrainz] {
+            if !provider.is_enabled() {
+                tracing::warn!("Metadata provider {} is disabled, its credentials are missing", provider.name());
+            }
+        }
+
+        providers
+    }
+}
@@ -0,0 +1,217 @@
+use crate::dto::metadata_dto::{ProviderAlbum, ReleaseCode, ReleaseId};
+use crate::dto::spotify_dto::SpotifyRoot;
+use crate::error::app_error::AppError;
+use crate::providers::metadata_provider::{env_setting, MetadataProvider};
+use crate::providers::outbound_client::{OutboundClient, OutboundPolicy};
+use crate::providers::spotify_token::{SpotifyCredentials, SpotifyTokenManager};
+use chrono::NaiveDate;
+use tracing::instrument;
+
+pub const DEFAULT_SPOTIFY_API_URL: &str = "https://api.spotify.com";
+pub const DEFAULT_SPOTIFY_ACCOUNTS_URL: &str = "https://accounts.spotify.com";
+
+/// Spotify doesn't publish its limit, it answers 429 with a `Retry-After` once reached
+fn spotify_policy() -> OutboundPolicy {
+    OutboundPolicy {
+        burst: 10,
+        requests_per_second: 5.0,
+        ..Default::default()
+    }
+}
+
+pub struct SpotifyProvider {
+    client: OutboundClient,
+    api_url: String,
+    /// Missing when the credentials are not configured
+    tokens: Option<SpotifyTokenManager>,
+}
+
+impl SpotifyProvider {
+    pub fn new(api_url: &str, accounts_url: &str, credentials: Option<SpotifyCredentials>) -> Self {
+        // The accounts service shares the limits of the API
+        let client = OutboundClient::new("Spotify", spotify_policy());
+        let tokens = credentials
+            .map(|credentials| SpotifyTokenManager::new(client.clone(), accounts_url, credentials));
+        Self {
+            client,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            tokens,
+        }
+    }
+
+    /// Reads `SPOTIFY_API_URL`, `SPOTIFY_ACCOUNTS_URL` and the `SPOTIFY_CLIENT_ID`,
+    /// `SPOTIFY_CLIENT_SECRET` and `SPOTIFY_REFRESH_TOKEN` credentials,
+    /// the provider is disabled when one of them is missing
+    pub fn from_env() -> Self {
+        let api_url = env_setting("SPOTIFY_API_URL").unwrap_or(DEFAULT_SPOTIFY_API_URL.to_string());
+        let accounts_url =
+            env_setting("SPOTIFY_ACCOUNTS_URL").unwrap_or(DEFAULT_SPOTIFY_ACCOUNTS_URL.to_string());
+        let credentials = match (
+            env_setting("SPOTIFY_CLIENT_ID"),
+            env_setting("SPOTIFY_CLIENT_SECRET"),
+            env_setting("SPOTIFY_REFRESH_TOKEN"),
+        ) {
+            (Some(client_id), Some(client_secret), Some(refresh_token)) => Some(SpotifyCredentials {
+                client_id,
+                client_secret,
+                refresh_token,
+            }),
+            _ => None,
+        };
+        Self::new(&api_url, &accounts_url, credentials)
+    }
+}
+
+/// Spotify dates are as precise as `precision` says (day, month or year)
+fn parse_release_date(release_date: &str, precision: &str) -> NaiveDate {
+    let release_date = match precision {
+        // For format like "2013-05-20"
+        "day" => release_date.to_string(),
+        // For format like "2013-05"
+        "month" => format!("{}-01", release_date),
+        // For format like "2013"
+        _ => format!("{}-01-01", release_date),
+    };
+    NaiveDate::parse_from_str(&release_date, "%Y-%m-%d").unwrap_or_default()
+}
+
+#[async_trait]
+impl MetadataProvider for SpotifyProvider {
+    fn name(&self) -> &'static str {
+        "spotify"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.tokens.is_some()
+    }
+
+    #[instrument(name = "spotify_provider/search", skip_all)]
+    async fn search(&self, query: &str) -> Result<Vec<ProviderAlbum>, AppError> {
+        let Some(tokens) = &self.tokens else {
+            return Ok(Vec::new());
+        };
+
+        // A token revoked before its expiry is exchanged again, once
+        let mut retried = false;
+        let response = loop {
+            let access_token = tokens.access_token().await?;
+            let response = self
+                .client
+                .send(|client| {
+                    client
+                        .get(format!("{}/v1/search", self.api_url))
+                        .query(&[("q", query)])
+                        .query(&[("type", "album")])
+                        .header("Authorization", format!("Bearer {}", access_token))
+                        .header("Content-Type", "application/json")
+                })
+                .await?;
+
+            if response.status() == reqwest::StatusCode::UNAUTHORIZED && !retried {
+                tracing::warn!("Spotify refused the access token, refreshing it");
+                tokens.invalidate(&access_token).await;
+                retried = true;
+                continue;
+            }
+            break response;
+        };
+
+        let spotify_json = self.client.json::<SpotifyRoot>(response).await?;
+
+        let albums = spotify_json
+            .albums
+            .items
+            .into_iter()
+            .map(|item| ProviderAlbum {
+                artist: item
+                    .artists
+                    .first()
+                    .map(|artist| artist.name.clone())
+                    .unwrap_or_default(),
+                release_date: parse_release_date(&item.release_date, &item.release_date_precision),
+                cover_url: item.images.first().map(|image| image.url.clone()),
+                discogs_url: None,
+                spotify_url: Some(item.external_urls.spotify),
+                title: item.name,
+                ..Default::default()
+            })
+            .collect();
+
+        Ok(albums)
+    }
+
+    async fn release(&self, _id: &ReleaseId) -> Result<Option<ProviderAlbum>, AppError> {
+        Ok(None)
+    }
+
+    async fn lookup(&self, _code: &ReleaseCode) -> Result<Vec<ProviderAlbum>, AppError> {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::http::serve;
+
+    fn credentials() -> SpotifyCredentials {
+        SpotifyCredentials {
+            client_id: "id".to_string(),
+            client_secret: "secret".to_string(),
+            refresh_token: "refresh".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_release_date() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(parse_release_date("2013-05-20", "day"), date(2013, 5, 20));
+        assert_eq!(parse_release_date("2013-05", "month"), date(2013, 5, 1));
+        assert_eq!(parse_release_date("2013", "year"), date(2013, 1, 1));
+        assert_eq!(parse_release_date("unknown", "day"), NaiveDate::default());
+    }
+
+    #[rocket::async_test]
+    async fn test_search_retries_once_with_a_new_token() {
+        let token = |token: &str| {
+            let body = format!(
+                r#"{{"access_token":"{}","token_type":"Bearer","expires_in":3600,"scope":""}}"#,
+                token
+            );
+            (200, body)
+        };
+        let accounts = serve(vec![token("revoked"), token("fresh")]).await;
+        let album = r#"{
+            "album_type": "album", "total_tracks": 10,
+            "external_urls": { "spotify": "https://open.spotify.com/album/1" },
+            "href": "", "id": "1", "name": "Discovery",
+            "images": [{ "url": "https://i.scdn.co/1.jpg", "height": 640, "width": 640 }],
+            "release_date": "2001-03", "release_date_precision": "month",
+            "type": "album", "uri": "",
+            "artists": [{ "external_urls": { "spotify": "" }, "href": "", "id": "1", "name": "Daft Punk", "type": "artist", "uri": "" }]
+        }"#;
+        let search = format!(
+            r#"{{"albums": {{"href": "", "limit": 20, "next": "", "offset": 0, "previous": null, "total": 1, "items": [{}]}}}}"#,
+            album
+        );
+        let api = serve(vec![(401, "{}".to_string()), (200, search)]).await;
+
+        let provider = SpotifyProvider::new(&api.url, &accounts.url, Some(credentials()));
+        let albums = provider.search("discovery").await.unwrap();
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].artist, "Daft Punk");
+        assert_eq!(albums[0].release_date, NaiveDate::from_ymd_opt(2001, 3, 1).unwrap());
+        assert_eq!((accounts.hits(), api.hits()), (2, 2));
+
+        // The fresh token is reused by the next search
+        provider.search("discovery").await.unwrap();
+        assert_eq!((accounts.hits(), api.hits()), (2, 3));
+    }
+
+    #[rocket::async_test]
+    async fn test_missing_credentials_disable_the_provider() {
+        let provider = SpotifyProvider::new("http://127.0.0.1:1", "http://127.0.0.1:1", None);
+        assert!(!provider.is_enabled());
+        assert!(provider.search("kind of blue").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::dto::spotify_dto::SpotifyAccessTokenRoot;
+use crate::error::app_error::AppError;
+use crate::providers::outbound_client::OutboundClient;
+use base64::Engine;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::instrument;
+
+/// A token is refreshed this long before Spotify expires it
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Credentials of the Spotify application
+#[derive(Debug, Clone)]
+pub struct SpotifyCredentials {
+    pub client_id: String,
+    pub client_secret: String,
+    pub refresh_token: String,
+}
+
+struct CachedToken {
+    value: String,
+    expires_at: Instant,
+}
+
+/// Exchanges the refresh token for access tokens and reuses them until they expire
+pub struct SpotifyTokenManager {
+    client: OutboundClient,
+    accounts_url: String,
+    credentials: SpotifyCredentials,
+    /// Held while refreshing so racing requests wait for a single exchange
+    cached: Mutex<Option<CachedToken>>,
+}
+
+impl SpotifyTokenManager {
+    pub fn new(client: OutboundClient, accounts_url: &str, credentials: SpotifyCredentials) -> Self {
+        Self {
+            client,
+            accounts_url: accounts_url.trim_end_matches('/').to_string(),
+            credentials,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// A valid access token, refreshed when the cached one is about to expire
+    pub async fn access_token(&self) -> Result<String, AppError> {
+        let mut cached = self.cached.lock().await;
+        if let Some(token) = cached.as_ref().filter(|token| token.expires_at > Instant::now()) {
+            return Ok(token.value.clone());
+        }
+
+        let token_data = self.exchange().await?;
+        let lifetime = Duration::from_secs(token_data.expires_in.max(0) as u64);
+        *cached = Some(CachedToken {
+            value: token_data.access_token.clone(),
+            expires_at: Instant::now() + lifetime.saturating_sub(EXPIRY_MARGIN),
+        });
+        Ok(token_data.access_token)
+    }
+
+    /// Drops a token Spotify refused, unless another request already replaced it
+    pub async fn invalidate(&self, token: &str) {
+        let mut cached = self.cached.lock().await;
+        if cached.as_ref().is_some_and(|cached| cached.value == token) {
+            *cached = None;
+        }
+    }
+
+    #[instrument(name = "spotify_token/exchange", skip_all)]
+    async fn exchange(&self) -> Result<SpotifyAccessTokenRoot, AppError> {
+        let auth_string = format!("{}:{}", self.credentials.client_id, self.credentials.client_secret);
+        let auth_encoded = base64::engine::general_purpose::STANDARD.encode(auth_string);
+
+        tracing::info!("Authenticating with Spotify");
+        let response = self
+            .client
+            .send(|client| {
+                client
+                    .post(format!("{}/api/token", self.accounts_url))
+                    .header("Authorization", format!("Basic {}", auth_encoded))
+                    .header("Content-Type", "application/x-www-form-urlencoded")
+                    .form(&[
+                        ("grant_type", "refresh_token"),
+                        ("refresh_token", &self.credentials.refresh_token),
+                    ])
+            })
+            .await?;
+
+        let status = response.status();
+        let body = response.text().await.map_err(|e| {
+            tracing::error!("[SpotifyAccessToken.text] Error: {}", e);
+            AppError::UpstreamBadResponse { provider: self.client.provider() }
+        })?;
+
+        serde_json::from_str::<SpotifyAccessTokenRoot>(&body).map_err(|e| {
+            tracing::error!("Spotify API returned {}: {} ({})", status, body, e);
+            AppError::UpstreamBadResponse { provider: self.client.provider() }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::http::serve;
+    use std::sync::Arc;
+
+    fn token_body(token: &str, expires_in: i64) -> (u16, String) {
+        let body = format!(
+            r#"{{"access_token":"{}","token_type":"Bearer","expires_in":{},"scope":""}}"#,
+            token, expires_in
+        );
+        (200, body)
+    }
+
+    fn client() -> OutboundClient {
+        OutboundClient::new("Spotify", Default::default())
+    }
+
+    fn credentials() -> SpotifyCredentials {
+        SpotifyCredentials {
+            client_id: "id".to_string(),
+            client_secret: "secret".to_string(),
+            refresh_token: "refresh".to_string(),
+        }
+    }
+
+    #[rocket::async_test]
+    async fn test_token_is_cached_across_racing_requests() {
+        let server = serve(vec![token_body("first", 3600), token_body("second", 3600)]).await;
+        let manager = Arc::new(SpotifyTokenManager::new(client(), &server.url, credentials()));
+
+        let requests: Vec<_> = (0..5)
+            .map(|_| {
+                let manager = manager.clone();
+                tokio::spawn(async move { manager.access_token().await.unwrap() })
+            })
+            .collect();
+        for request in requests {
+            assert_eq!(request.await.unwrap(), "first");
+        }
+        assert_eq!(server.hits(), 1);
+
+        // A refused token is exchanged again, an already replaced one is kept
+        manager.invalidate("first").await;
+        assert_eq!(manager.access_token().await.unwrap(), "second");
+        manager.invalidate("first").await;
+        assert_eq!(manager.access_token().await.unwrap(), "second");
+        assert_eq!(server.hits(), 2);
+    }
+
+    #[rocket::async_test]
+    async fn test_expired_token_is_refreshed() {
+        // Tokens living less than the margin are never reused
+        let server = serve(vec![token_body("first", 30), token_body("second", 30)]).await;
+        let manager = SpotifyTokenManager::new(client(), &server.url, credentials());
+
+        assert_eq!(manager.access_token().await.unwrap(), "first");
+        assert_eq!(manager.access_token().await.unwrap(), "second");
+        assert_eq!(server.hits(), 2);
+    }
+}
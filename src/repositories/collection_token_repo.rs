@@ -24,6 +24,14 @@ pub trait CollectionTokenRepo: Send + Sync {
         user_id: i32,
     ) -> Result<CollectionToken, DbRepoError>;
 
+    /// Create a collection token for a user with a known token string
+    async fn create_with_token(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        token: &str,
+    ) -> Result<CollectionToken, DbRepoError>;
+
     /// Find a collection token by its token string
     async fn find_by_token(
         &self,
@@ -79,6 +87,27 @@ impl CollectionTokenRepo for CollectionTokenRepoImpl {
         Ok(saved_token)
     }
 
+    #[instrument(name = "collection_token_repo/create_with_token", skip_all)]
+    async fn create_with_token(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        token: &str,
+    ) -> Result<CollectionToken, DbRepoError> {
+        let saved_token = query_as!(
+            CollectionToken,
+            "INSERT INTO collection_tokens (token, user_id, created_at) VALUES ($1, $2, $3) RETURNING *",
+            token,
+            user_id,
+            chrono::Utc::now().naive_utc()
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+
+        Ok(saved_token)
+    }
+
     #[instrument(name = "collection_token_repo/find_by_token", skip_all)]
     async fn find_by_token(
         &self,
@@ -177,6 +206,22 @@ mod tests {
         tx.rollback().await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_create_with_token() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+
+        let repo = CollectionTokenRepoImpl::new();
+        repo.delete_all_by_user_id(&mut tx, 1).await.unwrap();
+        let token = repo.create_with_token(&mut tx, 1, "restored-token").await.unwrap();
+        assert_eq!(token.token, "restored-token");
+
+        let found = repo.find_by_user_id(&mut tx, 1).await.unwrap();
+        assert_eq!(found.token, "restored-token");
+
+        tx.rollback().await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_delete_token() {
         let mut db_con = create_db_con_for_test().await.unwrap();
@@ -0,0 +1,249 @@
+use crate::dto::enrichment_dto::{EnrichmentError, EnrichmentReport};
+use crate::models::enrichment_job_model::EnrichmentJob;
+use crate::models::import_job_model::ImportJobStatus;
+use crate::repositories::error::DbRepoError;
+use crate::log_into;
+use mockall::automock;
+use sqlx::types::Json;
+use sqlx::{query, query_as, PgConnection};
+use tracing::instrument;
+
+pub struct EnrichmentJobRepoImpl {}
+
+impl EnrichmentJobRepoImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait EnrichmentJobRepo: Send + Sync {
+    /// Queue the lookups of the given records
+    async fn create(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        record_ids: &[i32],
+    ) -> Result<EnrichmentJob, DbRepoError>;
+
+    async fn find_by_id(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+    ) -> Result<Option<EnrichmentJob>, DbRepoError>;
+
+    /// Mark the oldest pending job as running and return it
+    async fn claim_next(&self, con: &mut PgConnection) -> Result<Option<EnrichmentJob>, DbRepoError>;
+
+    /// Records of a job, in processing order
+    async fn find_record_ids(&self, con: &mut PgConnection, id: i32) -> Result<Vec<i32>, DbRepoError>;
+
+    /// Add the outcome of a processed chunk of records to the job
+    async fn record_progress(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        processed_records: i32,
+        report: &EnrichmentReport,
+    ) -> Result<EnrichmentJob, DbRepoError>;
+
+    async fn finish(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        status: ImportJobStatus,
+        failure: Option<String>,
+    ) -> Result<EnrichmentJob, DbRepoError>;
+
+    /// Put back in the queue the jobs left running by a previous process
+    async fn requeue_running(&self, con: &mut PgConnection) -> Result<u64, DbRepoError>;
+}
+
+#[async_trait]
+impl EnrichmentJobRepo for EnrichmentJobRepoImpl {
+    #[instrument(name = "enrichment_job_repo/create", skip_all, fields(user_id = %user_id))]
+    async fn create(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        record_ids: &[i32],
+    ) -> Result<EnrichmentJob, DbRepoError> {
+        query_as!(
+            EnrichmentJob,
+            r#"INSERT INTO enrichment_jobs (user_id, status, record_ids, total_records) VALUES ($1, $2, $3, $4)
+            RETURNING id, user_id, status, total_records, processed_records, enriched_count, unchanged_count, failed_count,
+                errors as "errors: Json<Vec<EnrichmentError>>", failure, created_at, updated_at"#,
+            user_id,
+            ImportJobStatus::Pending.as_str(),
+            record_ids,
+            record_ids.len() as i32
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "enrichment_job_repo/find_by_id", skip_all, fields(id = %id))]
+    async fn find_by_id(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+    ) -> Result<Option<EnrichmentJob>, DbRepoError> {
+        query_as!(
+            EnrichmentJob,
+            r#"SELECT id, user_id, status, total_records, processed_records, enriched_count, unchanged_count, failed_count,
+                errors as "errors: Json<Vec<EnrichmentError>>", failure, created_at, updated_at
+            FROM enrichment_jobs WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "enrichment_job_repo/claim_next", skip_all)]
+    async fn claim_next(&self, con: &mut PgConnection) -> Result<Option<EnrichmentJob>, DbRepoError> {
+        // SKIP LOCKED lets several workers share the queue
+        query_as!(
+            EnrichmentJob,
+            r#"UPDATE enrichment_jobs SET status = $1, updated_at = CURRENT_TIMESTAMP
+            WHERE id = (SELECT id FROM enrichment_jobs WHERE status = $2 ORDER BY id FOR UPDATE SKIP LOCKED LIMIT 1)
+            RETURNING id, user_id, status, total_records, processed_records, enriched_count, unchanged_count, failed_count,
+                errors as "errors: Json<Vec<EnrichmentError>>", failure, created_at, updated_at"#,
+            ImportJobStatus::Running.as_str(),
+            ImportJobStatus::Pending.as_str()
+        )
+        .fetch_optional(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "enrichment_job_repo/find_record_ids", skip_all, fields(id = %id))]
+    async fn find_record_ids(&self, con: &mut PgConnection, id: i32) -> Result<Vec<i32>, DbRepoError> {
+        let job = query!("SELECT record_ids FROM enrichment_jobs WHERE id = $1", id)
+            .fetch_one(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+
+        Ok(job.record_ids)
+    }
+
+    #[instrument(name = "enrichment_job_repo/record_progress", skip_all, fields(id = %id))]
+    async fn record_progress(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        processed_records: i32,
+        report: &EnrichmentReport,
+    ) -> Result<EnrichmentJob, DbRepoError> {
+        let errors = serde_json::to_value(&report.errors).map_err(|e| log_into!(e, DbRepoError))?;
+
+        query_as!(
+            EnrichmentJob,
+            r#"UPDATE enrichment_jobs SET processed_records = processed_records + $2, enriched_count = enriched_count + $3,
+                unchanged_count = unchanged_count + $4, failed_count = failed_count + $5,
+                errors = errors || $6, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING id, user_id, status, total_records, processed_records, enriched_count, unchanged_count, failed_count,
+                errors as "errors: Json<Vec<EnrichmentError>>", failure, created_at, updated_at"#,
+            id,
+            processed_records,
+            report.enriched as i32,
+            report.unchanged as i32,
+            report.failed as i32,
+            errors
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "enrichment_job_repo/finish", skip_all, fields(id = %id))]
+    async fn finish(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        status: ImportJobStatus,
+        failure: Option<String>,
+    ) -> Result<EnrichmentJob, DbRepoError> {
+        query_as!(
+            EnrichmentJob,
+            r#"UPDATE enrichment_jobs SET status = $2, failure = $3, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING id, user_id, status, total_records, processed_records, enriched_count, unchanged_count, failed_count,
+                errors as "errors: Json<Vec<EnrichmentError>>", failure, created_at, updated_at"#,
+            id,
+            status.as_str(),
+            failure
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "enrichment_job_repo/requeue_running", skip_all)]
+    async fn requeue_running(&self, con: &mut PgConnection) -> Result<u64, DbRepoError> {
+        let result = query!(
+            "UPDATE enrichment_jobs SET status = $1, updated_at = CURRENT_TIMESTAMP WHERE status = $2",
+            ImportJobStatus::Pending.as_str(),
+            ImportJobStatus::Running.as_str()
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dto::enrichment_dto::{EnrichmentError, EnrichmentReport};
+    use crate::models::import_job_model::ImportJobStatus;
+    use crate::repositories::enrichment_job_repo::{EnrichmentJobRepo, EnrichmentJobRepoImpl};
+    use crate::test::db::create_db_con_for_test;
+    use crate::test::repositories::prepare::user::create_user;
+    use sqlx::Connection;
+
+    #[tokio::test]
+    async fn test_enrichment_job_lifecycle() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = EnrichmentJobRepoImpl::new();
+        let job = repo.create(&mut tx, user.id, &[3, 1, 2]).await.unwrap();
+        assert_eq!(job.status, "pending");
+        assert_eq!(job.total_records, 3);
+
+        // Older pending jobs from other tests may be claimed first
+        let mut claimed = repo.claim_next(&mut tx).await.unwrap().unwrap();
+        while claimed.id != job.id {
+            claimed = repo.claim_next(&mut tx).await.unwrap().unwrap();
+        }
+        assert_eq!(claimed.status, "running");
+        assert_eq!(repo.find_record_ids(&mut tx, job.id).await.unwrap(), vec![3, 1, 2]);
+
+        let report = EnrichmentReport {
+            enriched: 1,
+            unchanged: 0,
+            failed: 1,
+            errors: vec![EnrichmentError::new(1, "Not found on Discogs")],
+        };
+        let job = repo.record_progress(&mut tx, job.id, 2, &report).await.unwrap();
+        assert_eq!((job.processed_records, job.enriched_count, job.failed_count), (2, 1, 1));
+        assert_eq!(job.errors.0, report.errors);
+
+        // An interrupted job goes back to the queue
+        assert!(repo.requeue_running(&mut tx).await.unwrap() >= 1);
+        let job = repo.find_by_id(&mut tx, job.id).await.unwrap().unwrap();
+        assert_eq!(job.status, "pending");
+
+        let job = repo
+            .finish(&mut tx, job.id, ImportJobStatus::Completed, None)
+            .await
+            .unwrap();
+        assert_eq!(job.status, "completed");
+        tx.rollback().await.unwrap();
+    }
+}
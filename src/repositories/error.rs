@@ -6,6 +6,8 @@ pub enum DbRepoError {
     SerdeError(#[from] serde_json::Error),
     #[error("[DbRepoError::SqlxError] {0}")]
     SqlxError(#[from] sqlx::Error),
+    #[error("[DbRepoError::RedisError] {0}")]
+    RedisError(#[from] rocket_db_pools::deadpool_redis::redis::RedisError),
 
     #[error("[DbRepoError::NotFound] Record not found")]
     NotFound,
@@ -0,0 +1,271 @@
+use crate::dto::import_dto::{ImportMode, ImportReport, ImportRow, ImportRowError};
+use crate::models::import_job_model::{ImportJob, ImportJobStatus};
+use crate::repositories::error::DbRepoError;
+use crate::log_into;
+use mockall::automock;
+use sqlx::types::Json;
+use sqlx::{query, query_as, PgConnection};
+use tracing::instrument;
+
+pub struct ImportJobRepoImpl {}
+
+impl ImportJobRepoImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait ImportJobRepo: Send + Sync {
+    /// Queue the parsed rows of an import
+    async fn create(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        mode: ImportMode,
+        rows: &[ImportRow],
+    ) -> Result<ImportJob, DbRepoError>;
+
+    async fn find_by_id(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+    ) -> Result<Option<ImportJob>, DbRepoError>;
+
+    /// Mark the oldest pending job as running and return it
+    async fn claim_next(&self, con: &mut PgConnection) -> Result<Option<ImportJob>, DbRepoError>;
+
+    /// Parsed rows of a job, in CSV order
+    async fn find_rows(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+    ) -> Result<Vec<ImportRow>, DbRepoError>;
+
+    /// Add the outcome of a processed chunk of rows to the job
+    async fn record_progress(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        processed_rows: i32,
+        report: &ImportReport,
+    ) -> Result<ImportJob, DbRepoError>;
+
+    async fn finish(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        status: ImportJobStatus,
+        failure: Option<String>,
+    ) -> Result<ImportJob, DbRepoError>;
+
+    /// Put back in the queue the jobs left running by a previous process
+    async fn requeue_running(&self, con: &mut PgConnection) -> Result<u64, DbRepoError>;
+}
+
+#[async_trait]
+impl ImportJobRepo for ImportJobRepoImpl {
+    #[instrument(name = "import_job_repo/create", skip_all, fields(user_id = %user_id))]
+    async fn create(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        mode: ImportMode,
+        rows: &[ImportRow],
+    ) -> Result<ImportJob, DbRepoError> {
+        let payload = serde_json::to_value(rows).map_err(|e| log_into!(e, DbRepoError))?;
+
+        query_as!(
+            ImportJob,
+            r#"INSERT INTO import_jobs (user_id, status, mode, payload, total_rows) VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, user_id, status, mode, total_rows, processed_rows, created_count, updated_count, unchanged_count, skipped_count,
+                errors as "errors: Json<Vec<ImportRowError>>", failure, created_at, updated_at"#,
+            user_id,
+            ImportJobStatus::Pending.as_str(),
+            mode.as_str(),
+            payload,
+            rows.len() as i32
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "import_job_repo/find_by_id", skip_all, fields(id = %id))]
+    async fn find_by_id(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+    ) -> Result<Option<ImportJob>, DbRepoError> {
+        query_as!(
+            ImportJob,
+            r#"SELECT id, user_id, status, mode, total_rows, processed_rows, created_count, updated_count, unchanged_count, skipped_count,
+                errors as "errors: Json<Vec<ImportRowError>>", failure, created_at, updated_at
+            FROM import_jobs WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "import_job_repo/claim_next", skip_all)]
+    async fn claim_next(&self, con: &mut PgConnection) -> Result<Option<ImportJob>, DbRepoError> {
+        // SKIP LOCKED lets several workers share the queue
+        query_as!(
+            ImportJob,
+            r#"UPDATE import_jobs SET status = $1, updated_at = CURRENT_TIMESTAMP
+            WHERE id = (SELECT id FROM import_jobs WHERE status = $2 ORDER BY id FOR UPDATE SKIP LOCKED LIMIT 1)
+            RETURNING id, user_id, status, mode, total_rows, processed_rows, created_count, updated_count, unchanged_count, skipped_count,
+                errors as "errors: Json<Vec<ImportRowError>>", failure, created_at, updated_at"#,
+            ImportJobStatus::Running.as_str(),
+            ImportJobStatus::Pending.as_str()
+        )
+        .fetch_optional(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "import_job_repo/find_rows", skip_all, fields(id = %id))]
+    async fn find_rows(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+    ) -> Result<Vec<ImportRow>, DbRepoError> {
+        let job = query!(
+            r#"SELECT payload as "payload: Json<Vec<ImportRow>>" FROM import_jobs WHERE id = $1"#,
+            id
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+
+        Ok(job.payload.0)
+    }
+
+    #[instrument(name = "import_job_repo/record_progress", skip_all, fields(id = %id))]
+    async fn record_progress(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        processed_rows: i32,
+        report: &ImportReport,
+    ) -> Result<ImportJob, DbRepoError> {
+        let errors = serde_json::to_value(report.row_errors()).map_err(|e| log_into!(e, DbRepoError))?;
+
+        query_as!(
+            ImportJob,
+            r#"UPDATE import_jobs SET processed_rows = processed_rows + $2, created_count = created_count + $3,
+                updated_count = updated_count + $4, unchanged_count = unchanged_count + $5, skipped_count = skipped_count + $6,
+                errors = errors || $7, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING id, user_id, status, mode, total_rows, processed_rows, created_count, updated_count, unchanged_count, skipped_count,
+                errors as "errors: Json<Vec<ImportRowError>>", failure, created_at, updated_at"#,
+            id,
+            processed_rows,
+            report.created as i32,
+            report.updated as i32,
+            report.unchanged as i32,
+            report.skipped as i32,
+            errors
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "import_job_repo/finish", skip_all, fields(id = %id))]
+    async fn finish(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        status: ImportJobStatus,
+        failure: Option<String>,
+    ) -> Result<ImportJob, DbRepoError> {
+        query_as!(
+            ImportJob,
+            r#"UPDATE import_jobs SET status = $2, failure = $3, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING id, user_id, status, mode, total_rows, processed_rows, created_count, updated_count, unchanged_count, skipped_count,
+                errors as "errors: Json<Vec<ImportRowError>>", failure, created_at, updated_at"#,
+            id,
+            status.as_str(),
+            failure
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "import_job_repo/requeue_running", skip_all)]
+    async fn requeue_running(&self, con: &mut PgConnection) -> Result<u64, DbRepoError> {
+        let result = query!(
+            "UPDATE import_jobs SET status = $1, updated_at = CURRENT_TIMESTAMP WHERE status = $2",
+            ImportJobStatus::Pending.as_str(),
+            ImportJobStatus::Running.as_str()
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dto::import_dto::{ImportMode, ImportReport, ImportRow, ImportRowError, ImportRowStatus};
+    use crate::models::import_job_model::ImportJobStatus;
+    use crate::repositories::import_job_repo::{ImportJobRepo, ImportJobRepoImpl};
+    use crate::test::db::create_db_con_for_test;
+    use crate::test::fixture::record::record_input_fixture;
+    use crate::test::repositories::prepare::user::create_user;
+    use sqlx::Connection;
+
+    #[tokio::test]
+    async fn test_import_job_lifecycle() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = ImportJobRepoImpl::new();
+        let rows = vec![
+            ImportRow { row: 1, record: Ok(record_input_fixture(1)) },
+            ImportRow {
+                row: 2,
+                record: Err(vec![ImportRowError::new(2, "title", "Title is required")]),
+            },
+        ];
+        let job = repo.create(&mut tx, user.id, ImportMode::Upsert, &rows).await.unwrap();
+        assert_eq!(job.status, "pending");
+        assert_eq!(job.total_rows, 2);
+
+        // Older pending jobs from other tests may be claimed first
+        let mut claimed = repo.claim_next(&mut tx).await.unwrap().unwrap();
+        while claimed.id != job.id {
+            claimed = repo.claim_next(&mut tx).await.unwrap().unwrap();
+        }
+        assert_eq!(claimed.status, "running");
+
+        let stored_rows = repo.find_rows(&mut tx, job.id).await.unwrap();
+        assert_eq!(stored_rows.len(), 2);
+        assert_eq!(stored_rows[0].record.as_ref().unwrap().title, "title1");
+        assert!(stored_rows[1].record.is_err());
+
+        let mut report = ImportReport::default();
+        report.push(1, ImportRowStatus::Created, Some(1), None);
+        report.push_invalid(2, vec![ImportRowError::new(2, "title", "Title is required")]);
+        let progress = repo.record_progress(&mut tx, job.id, 2, &report).await.unwrap();
+        assert_eq!(progress.processed_rows, 2);
+        assert_eq!((progress.created_count, progress.skipped_count), (1, 1));
+        assert_eq!(progress.errors.0[0].field, "title");
+
+        let finished = repo
+            .finish(&mut tx, job.id, ImportJobStatus::Completed, None)
+            .await
+            .unwrap();
+        assert_eq!(finished.status, "completed");
+        assert!(repo.find_by_id(&mut tx, job.id).await.unwrap().is_some());
+        tx.rollback().await.unwrap();
+    }
+}
@@ -3,4 +3,7 @@ pub mod record_repo;
 pub mod tag_repo;
 pub mod user_repo;
 pub mod collection_token_repo;
+pub mod import_job_repo;
+pub mod enrichment_job_repo;
+pub mod search_cache_repo;
 pub mod repositories;
\ No newline at end of file
@@ -1,12 +1,18 @@
-use crate::models::record_model::{Record, RecordDB};
+use crate::dto::record_dto::{
+    PLACEHOLDER_COVER_PREFIX, RecordCursor, RecordCursorValue, RecordFilter, RecordPagination,
+};
+use crate::models::record_model::{Record, RecordDB, RecordPage};
 use crate::models::tag_model::Tag;
 use crate::repositories::error::DbRepoError;
 use crate::{dto::record_dto::RecordInput, log_into};
 use mockall::automock;
-use sqlx::{query, query_as, Connection, PgConnection, Row};
+use sqlx::{query, query_as, Connection, PgConnection, Postgres, QueryBuilder, Row};
 use tracing::instrument;
 use std::sync::OnceLock;
-use crate::repositories::tag_repo::{TagRepo, TagRepoImpl};
+use crate::repositories::tag_repo::{push_tag_filter, TagRepo, TagRepoImpl};
+
+/// Records inserted by a single statement of `create_multiple`
+const INSERT_CHUNK_SIZE: usize = 500;
 
 // Global singleton instance of TagRepoImpl
 static TAG_REPO: OnceLock<TagRepoImpl> = OnceLock::new();
@@ -16,6 +22,49 @@ fn get_tag_repo() -> &'static dyn TagRepo {
     TAG_REPO.get_or_init(TagRepoImpl::new)
 }
 
+// Attaches their tags to a batch of records with a single query
+async fn hydrate_tags(con: &mut PgConnection, records: Vec<Record>) -> Result<Vec<Record>, DbRepoError> {
+    let record_ids: Vec<i32> = records.iter().map(|record| record.id).collect();
+    let mut tags_by_record = get_tag_repo().find_all_by_record_ids(con, &record_ids).await?;
+
+    Ok(records
+        .into_iter()
+        .map(|record| {
+            let tags = tags_by_record.remove(&record.id).unwrap_or_default();
+            record.with_tags(tags)
+        })
+        .collect())
+}
+
+// Appends the collection filters to a query already scoped by user_id
+fn push_filters(query: &mut QueryBuilder<'_, Postgres>, user_id: i32, filter: &RecordFilter) {
+    if let Some(owned) = filter.owned {
+        query.push(" AND owned = ").push_bind(owned);
+    }
+    if let Some(wanted) = filter.wanted {
+        query.push(" AND wanted = ").push_bind(wanted);
+    }
+    if let Some(artist) = &filter.artist {
+        query.push(" AND LOWER(artist) = LOWER(").push_bind(artist.clone()).push(")");
+    }
+    if let Some(released_from) = filter.released_from_date() {
+        query.push(" AND release_date >= ").push_bind(released_from);
+    }
+    if let Some(released_to) = filter.released_to_date() {
+        query.push(" AND release_date <= ").push_bind(released_to);
+    }
+    if let Some(pattern) = filter.q_pattern() {
+        query
+            .push(" AND (title ILIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR artist ILIKE ")
+            .push_bind(pattern)
+            .push(")");
+    }
+
+    push_tag_filter(query, user_id, filter);
+}
+
 pub struct RecordRepoImpl {}
 impl RecordRepoImpl {
     pub fn new() -> Self {
@@ -49,18 +98,59 @@ pub trait RecordRepo: Send + Sync {
         &self,
         con: &mut PgConnection,
         user_id: i32,
-        owned: Option<bool>,
-        wanted: Option<bool>,
-    ) -> Result<Vec<Record>, DbRepoError>;
+        filter: &RecordFilter,
+        pagination: &RecordPagination,
+    ) -> Result<RecordPage, DbRepoError>;
     async fn get_random_by_user_id(
         &self,
         con: &mut PgConnection,
         user_id: i32,
-        owned: Option<bool>,
-        wanted: Option<bool>,
+        filter: &RecordFilter,
     ) -> Result<Option<Record>, DbRepoError>;
+    async fn find_all_by_discogs_urls(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        discogs_urls: &[String],
+    ) -> Result<Vec<Record>, DbRepoError>;
+    /// Records of a user sharing a Discogs url, a Spotify url or a MusicBrainz id with the given ones
+    async fn find_all_by_external_urls(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        discogs_urls: &[String],
+        spotify_urls: &[String],
+        musicbrainz_ids: &[String],
+    ) -> Result<Vec<Record>, DbRepoError>;
+
+    /// Records of a user with the given catalog number, `normalized` like `ReleaseCode::normalized`
+    async fn find_all_by_catalog_number(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        normalized: &str,
+    ) -> Result<Vec<Record>, DbRepoError>;
+
+    /// Ids of the user's Discogs records with a placeholder cover, no Spotify link or no tags
+    async fn find_ids_to_enrich(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<i32>, DbRepoError>;
 
+    async fn update(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        record_input: RecordInput,
+    ) -> Result<Record, DbRepoError>;
     async fn delete(&self, con: &mut PgConnection, id: i32) -> Result<(), DbRepoError>;
+    /// Delete every record of a user, returns how many were deleted
+    async fn delete_all_by_user_id(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<u64, DbRepoError>;
 }
 
 #[async_trait]
@@ -77,7 +167,7 @@ impl RecordRepo for RecordRepoImpl {
 
         let record_db = query_as!(
             RecordDB,
-            "INSERT INTO records (title, artist, release_date, cover_url, discogs_url, spotify_url, owned, wanted, user_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING *",
+            "INSERT INTO records (title, artist, release_date, cover_url, discogs_url, spotify_url, owned, wanted, user_id, created_at, catalog_number, format, rating, collection_folder, media_condition, sleeve_condition, notes, musicbrainz_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, COALESCE($10, LOCALTIMESTAMP), $11, $12, $13, $14, $15, $16, $17, $18) RETURNING *",
             record_input.title,
             record_input.artist,
             chrono::NaiveDate::parse_from_str(&record_input.release_date, "%Y-%m-%d").unwrap(),
@@ -86,7 +176,16 @@ impl RecordRepo for RecordRepoImpl {
             record_input.spotify_url,
             record_input.owned,
             record_input.wanted,
-            user_id
+            user_id,
+            record_input.added_at(),
+            record_input.catalog_number,
+            record_input.format,
+            record_input.rating,
+            record_input.collection_folder,
+            record_input.media_condition,
+            record_input.sleeve_condition,
+            record_input.notes,
+            record_input.musicbrainz_id
         )
         .fetch_one(&mut *tx)
         .await
@@ -104,7 +203,7 @@ impl RecordRepo for RecordRepoImpl {
                 // Create or find each tag and collect their IDs
                 let mut tag_ids = Vec::new();
                 for tag_name in tag_names {
-                    let tag = tag_repo.find_or_create(&mut tx, &tag_name).await?;
+                    let tag = tag_repo.find_or_create(&mut tx, record.user_id, &tag_name).await?;
                     tags.push(tag.clone());
                     tag_ids.push(tag.id);
                 }
@@ -137,78 +236,100 @@ impl RecordRepo for RecordRepoImpl {
         // Start a transaction to handle both records and tags
         let mut tx = con.begin().await.map_err(|e| log_into!(e, DbRepoError))?;
         
-        // Build the SQL string with the proper number of placeholders
-        let mut sql = String::from(
-            "INSERT INTO records (title, artist, release_date, cover_url, discogs_url, spotify_url, owned, wanted, user_id) VALUES ",
-        );
-        let mut placeholders = Vec::with_capacity(records_inputs.len());
-        for i in 0..records_inputs.len() {
-            let base = i * 9;
-            placeholders.push(format!(
-                "(${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${})",
-                base + 1,
-                base + 2,
-                base + 3,
-                base + 4,
-                base + 5,
-                base + 6,
-                base + 7,
-                base + 8,
-                base + 9
-            ));
-        }
-        sql.push_str(&placeholders.join(", "));
-        sql.push_str(" RETURNING *");
-
-        // Build the query and bind all parameters in order
-        let mut query = sqlx::query(&sql);
-        
         // Store tag names for each record for later association
         let mut record_tags: Vec<Option<Vec<String>>> = Vec::with_capacity(records_inputs.len());
-        
-        for record_input in records_inputs {
-            let release_date =
-                chrono::NaiveDate::parse_from_str(&record_input.release_date, "%Y-%m-%d").unwrap();
-            query = query
-                .bind(record_input.title)
-                .bind(record_input.artist)
-                .bind(release_date)
-                .bind(record_input.cover_url)
-                .bind(record_input.discogs_url)
-                .bind(record_input.spotify_url)
-                .bind(record_input.owned)
-                .bind(record_input.wanted)
-                .bind(user_id);
-                
-            // Store tags for later processing
-            record_tags.push(record_input.tags);
-        }
+        let mut records: Vec<Record> = Vec::with_capacity(records_inputs.len());
 
-        // Execute the query to insert all records
-        let rows = query
-            .fetch_all(&mut *tx)
-            .await
-            .map_err(|e| log_into!(e, DbRepoError))?;
+        // Postgres takes at most 65,535 bind parameters per statement, 18 per record
+        let mut inputs = records_inputs.into_iter().peekable();
+        while inputs.peek().is_some() {
+            let chunk: Vec<RecordInput> = inputs.by_ref().take(INSERT_CHUNK_SIZE).collect();
 
-        // Convert rows to RecordDB structs
-        let records_db: Vec<RecordDB> = rows
-            .iter()
-            .map(|row| RecordDB {
-                id: row.get("id"),
-                title: row.get("title"),
-                artist: row.get("artist"),
-                release_date: row.get("release_date"),
-                cover_url: row.get("cover_url"),
-                discogs_url: row.get("discogs_url"),
-                spotify_url: row.get("spotify_url"),
-                owned: row.get("owned"),
-                wanted: row.get("wanted"),
-                user_id: row.get("user_id"),
-            })
-            .collect();
-            
-        // Convert RecordDB to Record
-        let records: Vec<Record> = records_db.into_iter().map(Record::from).collect();
+            // Build the SQL string with the proper number of placeholders
+            let mut sql = String::from(
+                "INSERT INTO records (title, artist, release_date, cover_url, discogs_url, spotify_url, owned, wanted, user_id, created_at, catalog_number, format, rating, collection_folder, media_condition, sleeve_condition, notes, musicbrainz_id) VALUES ",
+            );
+            let mut placeholders = Vec::with_capacity(chunk.len());
+            for i in 0..chunk.len() {
+                let base = i * 18;
+                let params: Vec<String> = (1..=18)
+                    .map(|n| match n {
+                        // Records without an added date are added now
+                        10 => format!("COALESCE(${}, LOCALTIMESTAMP)", base + n),
+                        _ => format!("${}", base + n),
+                    })
+                    .collect();
+                placeholders.push(format!("({})", params.join(", ")));
+            }
+            sql.push_str(&placeholders.join(", "));
+            sql.push_str(" RETURNING *");
+
+            // Build the query and bind all parameters in order
+            let mut query = sqlx::query(&sql);
+
+            for record_input in chunk {
+                let release_date =
+                    chrono::NaiveDate::parse_from_str(&record_input.release_date, "%Y-%m-%d").unwrap();
+                let added_at = record_input.added_at();
+                query = query
+                    .bind(record_input.title)
+                    .bind(record_input.artist)
+                    .bind(release_date)
+                    .bind(record_input.cover_url)
+                    .bind(record_input.discogs_url)
+                    .bind(record_input.spotify_url)
+                    .bind(record_input.owned)
+                    .bind(record_input.wanted)
+                    .bind(user_id)
+                    .bind(added_at)
+                    .bind(record_input.catalog_number)
+                    .bind(record_input.format)
+                    .bind(record_input.rating)
+                    .bind(record_input.collection_folder)
+                    .bind(record_input.media_condition)
+                    .bind(record_input.sleeve_condition)
+                    .bind(record_input.notes)
+                    .bind(record_input.musicbrainz_id);
+
+                // Store tags for later processing
+                record_tags.push(record_input.tags);
+            }
+
+            // Execute the query to insert the records of the chunk
+            let rows = query
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| log_into!(e, DbRepoError))?;
+
+            // Convert rows to RecordDB structs
+            let records_db: Vec<RecordDB> = rows
+                .iter()
+                .map(|row| RecordDB {
+                    id: row.get("id"),
+                    title: row.get("title"),
+                    artist: row.get("artist"),
+                    release_date: row.get("release_date"),
+                    cover_url: row.get("cover_url"),
+                    discogs_url: row.get("discogs_url"),
+                    spotify_url: row.get("spotify_url"),
+                    musicbrainz_id: row.get("musicbrainz_id"),
+                    owned: row.get("owned"),
+                    wanted: row.get("wanted"),
+                    user_id: row.get("user_id"),
+                    created_at: row.get("created_at"),
+                    catalog_number: row.get("catalog_number"),
+                    format: row.get("format"),
+                    rating: row.get("rating"),
+                    collection_folder: row.get("collection_folder"),
+                    media_condition: row.get("media_condition"),
+                    sleeve_condition: row.get("sleeve_condition"),
+                    notes: row.get("notes"),
+                })
+                .collect();
+
+            // Convert RecordDB to Record
+            records.extend(records_db.into_iter().map(Record::from));
+        }
             
         // Process tags if they exist
         if record_tags.is_empty() {
@@ -230,7 +351,7 @@ impl RecordRepo for RecordRepoImpl {
                     // Create or find each tag and collect their IDs
                     let mut tag_ids = Vec::new();
                     for tag_name in tag_names {
-                        let tag = tag_repo.find_or_create(&mut tx, &tag_name).await?;
+                        let tag = tag_repo.find_or_create(&mut tx, record.user_id, &tag_name).await?;
                         tags.push(tag.clone());
                         tag_ids.push(tag.id);
                     }
@@ -254,9 +375,6 @@ impl RecordRepo for RecordRepoImpl {
 
     #[instrument(name = "record_repo/find_all", skip_all)]
     async fn find_all(&self, con: &mut PgConnection) -> Result<Vec<Record>, DbRepoError> {
-        // Use the singleton tag repository
-        let tag_repo = get_tag_repo();
-    
         // Get all records first
         let records_db = query_as!(RecordDB, "SELECT * FROM records")
             .fetch_all(&mut *con)
@@ -266,14 +384,8 @@ impl RecordRepo for RecordRepoImpl {
         // Convert RecordDB to Record
         let records: Vec<Record> = records_db.into_iter().map(Record::from).collect();
             
-        // For each record, fetch its tags
-        let mut records_with_tags = Vec::with_capacity(records.len());
-        for record in records {
-            let tags = tag_repo.find_all_by_record_id(con, record.id).await?;
-            records_with_tags.push(record.with_tags(tags));
-        }
-        
-        Ok(records_with_tags)
+        // Fetch the tags of every record at once
+        hydrate_tags(con, records).await
     }
 
     #[instrument(name = "record_repo/find_all_by_user_id", skip_all)]
@@ -281,39 +393,75 @@ impl RecordRepo for RecordRepoImpl {
         &self,
         con: &mut PgConnection,
         user_id: i32,
-        owned: Option<bool>,
-        wanted: Option<bool>,
-    ) -> Result<Vec<Record>, DbRepoError> {
-        // Use the singleton tag repository
-        let tag_repo = get_tag_repo();
-    
+        filter: &RecordFilter,
+        pagination: &RecordPagination,
+    ) -> Result<RecordPage, DbRepoError> {
+        let sort = pagination.sort();
+        let direction = pagination.direction();
+        let limit = pagination.limit();
+
+        // Count every matching record, regardless of the page
+        let mut count_query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM records WHERE user_id = ");
+        count_query.push_bind(user_id);
+        push_filters(&mut count_query, user_id, filter);
+
+        let total: i64 = count_query
+            .build_query_scalar()
+            .fetch_one(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+
         // Build query with filter conditions
-        let mut query_str = String::from("SELECT * FROM records WHERE user_id = $1");
-        if let Some(owned) = owned {
-            query_str.push_str(format!(" AND owned = {}", owned).as_str());
-        }
-        if let Some(wanted) = wanted {
-            query_str.push_str(format!(" AND wanted = {}", wanted).as_str());
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM records WHERE user_id = ");
+        query.push_bind(user_id);
+        push_filters(&mut query, user_id, filter);
+
+        // Keyset condition: only the rows sorted after the cursor
+        if let Some(cursor) = pagination.cursor() {
+            query.push(format!(" AND ({}, id) {} (", sort.column(), direction.comparator()));
+            match cursor.value {
+                RecordCursorValue::Text(value) => query.push_bind(value),
+                RecordCursorValue::Date(value) => query.push_bind(value),
+                RecordCursorValue::Timestamp(value) => query.push_bind(value),
+            };
+            query.push(", ").push_bind(cursor.id).push(")");
         }
 
+        // Fetch one extra row to know whether there is a next page
+        query.push(format!(
+            " ORDER BY {} {}, id {} LIMIT ",
+            sort.column(),
+            direction.keyword(),
+            direction.keyword()
+        ));
+        query.push_bind(limit + 1);
+
         // Get records first
-        let records_db = sqlx::query_as::<_, RecordDB>(&query_str)
-            .bind(user_id)
+        let mut records_db = query
+            .build_query_as::<RecordDB>()
             .fetch_all(&mut *con)
             .await
             .map_err(|e| log_into!(e, DbRepoError))?;
-            
+
+        let has_next_page = records_db.len() as i64 > limit;
+        records_db.truncate(limit as usize);
+
         // Convert RecordDB to Record
         let records: Vec<Record> = records_db.into_iter().map(Record::from).collect();
-            
-        // For each record, fetch its tags
-        let mut records_with_tags = Vec::with_capacity(records.len());
-        for record in records {
-            let tags = tag_repo.find_all_by_record_id(con, record.id).await?;
-            records_with_tags.push(record.with_tags(tags));
-        }
-        
-        Ok(records_with_tags)
+
+        let next_cursor = match records.last() {
+            Some(last) if has_next_page => Some(RecordCursor::from_record(last, sort).encode()),
+            _ => None,
+        };
+
+        // Fetch the tags of the whole page at once
+        let items = hydrate_tags(con, records).await?;
+
+        Ok(RecordPage {
+            total,
+            next_cursor,
+            items,
+        })
     }
 
     #[instrument(name = "record_repo/find_by_id", skip_all, fields(id = %id))]
@@ -346,37 +494,190 @@ impl RecordRepo for RecordRepoImpl {
         &self,
         con: &mut PgConnection,
         user_id: i32,
-        owned: Option<bool>,
-        wanted: Option<bool>,
+        filter: &RecordFilter,
     ) -> Result<Option<Record>, DbRepoError> {
-        // Use the singleton tag repository
-        let tag_repo = get_tag_repo();
-    
         // Build query with filter conditions
-        let mut query_str = String::from("SELECT * FROM records WHERE user_id = $1");
-        if let Some(owned) = owned {
-            query_str.push_str(format!(" AND owned = {}", owned).as_str());
-        }
-        if let Some(wanted) = wanted {
-            query_str.push_str(format!(" AND wanted = {}", wanted).as_str());
-        }
-        query_str.push_str(" ORDER BY RANDOM() LIMIT 1");
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM records WHERE user_id = ");
+        query.push_bind(user_id);
+        push_filters(&mut query, user_id, filter);
+        query.push(" ORDER BY RANDOM() LIMIT 1");
 
         // Try to find a random record
-        let record_db_opt = sqlx::query_as::<_, RecordDB>(&query_str)
-            .bind(user_id)
+        let record_db_opt = query
+            .build_query_as::<RecordDB>()
             .fetch_optional(&mut *con)
             .await
             .map_err(|e| log_into!(e, DbRepoError))?;
             
         // If record found, get its tags
-        if let Some(record_db) = record_db_opt {
-            let record = Record::from(record_db);
-            let tags = tag_repo.find_all_by_record_id(con, record.id).await?;
-            Ok(Some(record.with_tags(tags)))
-        } else {
-            Ok(None)
+        let records: Vec<Record> = record_db_opt.into_iter().map(Record::from).collect();
+        Ok(hydrate_tags(con, records).await?.pop())
+    }
+
+    #[instrument(name = "record_repo/find_all_by_discogs_urls", skip_all, fields(user_id = %user_id))]
+    async fn find_all_by_discogs_urls(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        discogs_urls: &[String],
+    ) -> Result<Vec<Record>, DbRepoError> {
+        if discogs_urls.is_empty() {
+            return Ok(Vec::new());
         }
+
+        let records_db = query_as!(
+            RecordDB,
+            "SELECT * FROM records WHERE user_id = $1 AND discogs_url = ANY($2)",
+            user_id,
+            discogs_urls
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+
+        let records: Vec<Record> = records_db.into_iter().map(Record::from).collect();
+        hydrate_tags(con, records).await
+    }
+
+    #[instrument(name = "record_repo/find_all_by_external_urls", skip_all, fields(user_id = %user_id))]
+    async fn find_all_by_external_urls(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        discogs_urls: &[String],
+        spotify_urls: &[String],
+        musicbrainz_ids: &[String],
+    ) -> Result<Vec<Record>, DbRepoError> {
+        if discogs_urls.is_empty() && spotify_urls.is_empty() && musicbrainz_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let records_db = query_as!(
+            RecordDB,
+            "SELECT * FROM records WHERE user_id = $1 AND (discogs_url = ANY($2) OR spotify_url = ANY($3) OR musicbrainz_id = ANY($4))",
+            user_id,
+            discogs_urls,
+            spotify_urls,
+            musicbrainz_ids
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+
+        let records: Vec<Record> = records_db.into_iter().map(Record::from).collect();
+        hydrate_tags(con, records).await
+    }
+
+    #[instrument(name = "record_repo/find_all_by_catalog_number", skip_all, fields(user_id = %user_id))]
+    async fn find_all_by_catalog_number(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        normalized: &str,
+    ) -> Result<Vec<Record>, DbRepoError> {
+        if normalized.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let records_db = query_as!(
+            RecordDB,
+            "SELECT * FROM records WHERE user_id = $1 AND regexp_replace(LOWER(catalog_number), '[^[:alnum:]]', '', 'g') = $2 ORDER BY id",
+            user_id,
+            normalized
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+
+        let records: Vec<Record> = records_db.into_iter().map(Record::from).collect();
+        hydrate_tags(con, records).await
+    }
+
+    #[instrument(name = "record_repo/find_ids_to_enrich", skip_all, fields(user_id = %user_id))]
+    async fn find_ids_to_enrich(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<i32>, DbRepoError> {
+        let rows = query!(
+            r#"SELECT r.id FROM records r
+            WHERE r.user_id = $1 AND r.discogs_url IS NOT NULL
+                AND (r.cover_url LIKE $2 || '%' OR r.spotify_url IS NULL
+                    OR NOT EXISTS (SELECT 1 FROM records_tags rt WHERE rt.record_id = r.id))
+            ORDER BY r.id"#,
+            user_id,
+            PLACEHOLDER_COVER_PREFIX
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    #[instrument(name = "record_repo/update", skip_all, fields(id = %id))]
+    async fn update(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        record_input: RecordInput,
+    ) -> Result<Record, DbRepoError> {
+        // Start a transaction to handle both record update and tag replacement
+        let mut tx = con.begin().await.map_err(|e| log_into!(e, DbRepoError))?;
+
+        let record_db = query_as!(
+            RecordDB,
+            "UPDATE records SET title = $1, artist = $2, release_date = $3, cover_url = $4, discogs_url = $5, spotify_url = $6, owned = $7, wanted = $8, created_at = COALESCE($9, created_at), catalog_number = $10, format = $11, rating = $12, collection_folder = $13, media_condition = $14, sleeve_condition = $15, notes = $16, musicbrainz_id = $17 WHERE id = $18 RETURNING *",
+            record_input.title,
+            record_input.artist,
+            chrono::NaiveDate::parse_from_str(&record_input.release_date, "%Y-%m-%d").unwrap(),
+            record_input.cover_url,
+            record_input.discogs_url,
+            record_input.spotify_url,
+            record_input.owned.unwrap_or(false),
+            record_input.wanted.unwrap_or(false),
+            record_input.added_at(),
+            record_input.catalog_number,
+            record_input.format,
+            record_input.rating,
+            record_input.collection_folder,
+            record_input.media_condition,
+            record_input.sleeve_condition,
+            record_input.notes,
+            record_input.musicbrainz_id,
+            id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+
+        let record = Record::from(record_db);
+
+        // Use the singleton tag repository
+        let tag_repo = get_tag_repo();
+
+        // Replace the tags only when provided, otherwise keep the current ones
+        let tags = match record_input.tags {
+            Some(tag_names) => {
+                let mut tags = Vec::<Tag>::new();
+                for tag_name in tag_names {
+                    let tag = tag_repo.find_or_create(&mut tx, record.user_id, &tag_name).await?;
+                    if !tags.iter().any(|t| t.id == tag.id) {
+                        tags.push(tag);
+                    }
+                }
+
+                let tag_ids: Vec<i32> = tags.iter().map(|tag| tag.id).collect();
+                tag_repo.associate_tags_with_record(&mut tx, record.id, &tag_ids).await?;
+                tags
+            }
+            None => tag_repo.find_all_by_record_id(&mut tx, record.id).await?,
+        };
+
+        // Commit the transaction
+        tx.commit().await.map_err(|e| log_into!(e, DbRepoError))?;
+
+        Ok(record.with_tags(tags))
     }
 
     #[instrument(name = "record_repo/delete", skip_all, fields(id = %id))]
@@ -387,13 +688,34 @@ impl RecordRepo for RecordRepoImpl {
             .map_err(|e| log_into!(e, DbRepoError))?;
         Ok(())
     }
+
+    #[instrument(name = "record_repo/delete_all_by_user_id", skip_all, fields(user_id = %user_id))]
+    async fn delete_all_by_user_id(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<u64, DbRepoError> {
+        let result = query!("DELETE FROM records WHERE user_id = $1", user_id)
+            .execute(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(result.rows_affected())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::dto::record_dto::{
+        placeholder_cover_url, RecordFilter, RecordInput, RecordPagination, RecordSort, TagMatch,
+    };
+    use crate::models::tag_model::TagResponse;
     use crate::repositories::record_repo::{RecordRepo, RecordRepoImpl};
+    use crate::repositories::tag_repo::{TagRepo, TagRepoImpl};
+    use crate::repositories::user_repo::{UserRepo, UserRepoImpl};
     use crate::test::db::create_db_con_for_test;
+    use crate::test::fixture::record::record_input_fixture;
     use crate::test::repositories::prepare::record::create_record;
+    use crate::test::repositories::prepare::user::create_user;
     use sqlx::Connection;
 
     #[tokio::test]
@@ -416,6 +738,333 @@ mod tests {
         tx.rollback().await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_find_all_by_user_id_paginated() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = RecordRepoImpl::new();
+        let inputs = (1..=3).rev().map(record_input_fixture).collect();
+        repo.create_multiple(&mut tx, user.id, inputs).await.unwrap();
+
+        let mut pagination = RecordPagination {
+            limit: Some(2),
+            sort: Some(RecordSort::Artist),
+            ..Default::default()
+        };
+        let page = repo
+            .find_all_by_user_id(&mut tx, user.id, &RecordFilter::default(), &pagination)
+            .await
+            .unwrap();
+        let artists: Vec<String> = page.items.into_iter().map(|record| record.artist).collect();
+        assert_eq!(page.total, 3);
+        assert_eq!(artists, vec!["artist1", "artist2"]);
+        assert!(page.next_cursor.is_some());
+
+        pagination.after = page.next_cursor;
+        let page = repo
+            .find_all_by_user_id(&mut tx, user.id, &RecordFilter::default(), &pagination)
+            .await
+            .unwrap();
+        let artists: Vec<String> = page.items.into_iter().map(|record| record.artist).collect();
+        assert_eq!(page.total, 3);
+        assert_eq!(artists, vec!["artist3"]);
+        assert!(page.next_cursor.is_none());
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_find_all_by_user_id_filtered() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = RecordRepoImpl::new();
+        let tags = [vec!["genre:Jazz", "Soul"], vec!["jazz"], vec!["soul"]];
+        let inputs = (1..=3)
+            .map(|id| RecordInput {
+                release_date: format!("{}-06-01", 1985 + id * 10),
+                tags: Some(tags[id - 1].iter().map(|tag| tag.to_string()).collect()),
+                ..record_input_fixture(id)
+            })
+            .collect();
+        repo.create_multiple(&mut tx, user.id, inputs).await.unwrap();
+
+        let filters = [
+            (RecordFilter { tags: vec!["jazz".to_string()], ..Default::default() }, vec!["title1", "title2"]),
+            (
+                RecordFilter {
+                    tags: vec!["jazz".to_string(), "soul".to_string()],
+                    tag_match: Some(TagMatch::All),
+                    ..Default::default()
+                },
+                vec!["title1"],
+            ),
+            (
+                RecordFilter { released_from: Some(2000), released_to: Some(2010), ..Default::default() },
+                vec!["title2"],
+            ),
+            (RecordFilter { tags: vec!["genre:jazz".to_string()], ..Default::default() }, vec!["title1", "title2"]),
+            (RecordFilter { tags: vec!["style:jazz".to_string()], ..Default::default() }, Vec::new()),
+            (RecordFilter { q: Some("TITLE3".to_string()), ..Default::default() }, vec!["title3"]),
+            (RecordFilter { artist: Some("Artist2".to_string()), ..Default::default() }, vec!["title2"]),
+        ];
+        let pagination = RecordPagination {
+            sort: Some(RecordSort::Title),
+            ..Default::default()
+        };
+        for (filter, expected) in filters {
+            let page = repo
+                .find_all_by_user_id(&mut tx, user.id, &filter, &pagination)
+                .await
+                .unwrap();
+            let titles: Vec<String> = page.items.into_iter().map(|record| record.title).collect();
+            assert_eq!(page.total, expected.len() as i64);
+            assert_eq!(titles, expected);
+        }
+
+        let filter = RecordFilter {
+            tags: vec!["jazz".to_string(), "soul".to_string()],
+            tag_match: Some(TagMatch::All),
+            ..Default::default()
+        };
+        let random = repo.get_random_by_user_id(&mut tx, user.id, &filter).await.unwrap();
+        assert_eq!(random.unwrap().title, "title1");
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_batched_tags_match_per_record_tags() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = RecordRepoImpl::new();
+        let tag_repo = TagRepoImpl::new();
+        let mut inputs: Vec<RecordInput> = (1..=4).map(record_input_fixture).collect();
+        inputs[1].tags = Some(vec!["shared".to_string(), "tag1-1".to_string()]);
+        inputs[3].tags = None;
+        repo.create_multiple(&mut tx, user.id, inputs).await.unwrap();
+
+        let page = repo
+            .find_all_by_user_id(&mut tx, user.id, &RecordFilter::default(), &RecordPagination::default())
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 4);
+
+        for record in page.items {
+            let mut expected: Vec<TagResponse> = tag_repo
+                .find_all_by_record_id(&mut tx, record.id)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(TagResponse::from)
+                .collect();
+            let mut batched = record.tags.unwrap();
+            batched.sort_by(|a, b| a.slug.cmp(&b.slug));
+            expected.sort_by(|a, b| a.slug.cmp(&b.slug));
+            assert_eq!(
+                serde_json::to_value(&batched).unwrap(),
+                serde_json::to_value(&expected).unwrap()
+            );
+        }
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_multiple_keeps_collection_fields() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = RecordRepoImpl::new();
+        let input = RecordInput {
+            created_at: Some("2025-03-23 10:40:35".to_string()),
+            catalog_number: Some("0190296617164".to_string()),
+            format: Some("2xLP, Album, RE, Gat".to_string()),
+            rating: Some(5),
+            media_condition: Some("Mint (M)".to_string()),
+            notes: Some("Signed".to_string()),
+            ..record_input_fixture(1)
+        };
+        let created = repo
+            .create_multiple(&mut tx, user.id, vec![input, record_input_fixture(2)])
+            .await
+            .unwrap();
+
+        assert_eq!(created[0].created_at.to_string(), "2025-03-23 10:40:35");
+        assert_eq!(created[0].catalog_number.as_deref(), Some("0190296617164"));
+        assert_eq!(created[0].format.as_deref(), Some("2xLP, Album, RE, Gat"));
+        assert_eq!(created[0].rating, Some(5));
+        assert_eq!(created[0].media_condition.as_deref(), Some("Mint (M)"));
+        assert!(created[0].sleeve_condition.is_none());
+        assert_eq!(created[0].notes.as_deref(), Some("Signed"));
+        // Records without an added date are added now
+        assert!(created[1].created_at > created[0].created_at);
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_multiple_beyond_the_bind_parameter_limit() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = RecordRepoImpl::new();
+
+        // 18 parameters per record, more than 65,535 in a single statement
+        let inputs = (1..=4000)
+            .map(|id| RecordInput { tags: None, ..record_input_fixture(id) })
+            .collect();
+        let created = repo.create_multiple(&mut tx, user.id, inputs).await.unwrap();
+
+        assert_eq!(created.len(), 4000);
+        assert_eq!(created[0].title, "title1");
+        assert_eq!(created[3999].title, "title4000");
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_find_all_by_discogs_urls() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = RecordRepoImpl::new();
+        let inputs = vec![record_input_fixture(1), record_input_fixture(2)];
+        let created = repo.create_multiple(&mut tx, user.id, inputs).await.unwrap();
+
+        let urls = vec![
+            created[0].discogs_url.clone().unwrap(),
+            "https://www.discogs.com/release/0".to_string(),
+        ];
+        let found = repo.find_all_by_discogs_urls(&mut tx, user.id, &urls).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, created[0].id);
+        assert_eq!(found[0].tags.as_ref().unwrap().len(), 2);
+
+        let other_user = repo.find_all_by_discogs_urls(&mut tx, 1, &urls).await.unwrap();
+        assert!(other_user.is_empty());
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_find_all_by_external_urls() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = RecordRepoImpl::new();
+        let mut spotify_only = record_input_fixture(2);
+        spotify_only.discogs_url = None;
+        spotify_only.spotify_url = Some("https://open.spotify.com/album/2".to_string());
+        let mut musicbrainz_only = record_input_fixture(4);
+        musicbrainz_only.discogs_url = None;
+        musicbrainz_only.musicbrainz_id = Some("5b11f4ce-a62d-471e-81fc-a69a8278c7da".to_string());
+        let inputs = vec![record_input_fixture(1), spotify_only, record_input_fixture(3), musicbrainz_only];
+        let created = repo.create_multiple(&mut tx, user.id, inputs).await.unwrap();
+
+        let discogs_urls = vec![created[0].discogs_url.clone().unwrap()];
+        let spotify_urls = vec![created[1].spotify_url.clone().unwrap()];
+        let musicbrainz_ids = vec![created[3].musicbrainz_id.clone().unwrap()];
+        let mut found = repo
+            .find_all_by_external_urls(&mut tx, user.id, &discogs_urls, &spotify_urls, &musicbrainz_ids)
+            .await
+            .unwrap();
+        found.sort_by_key(|record| record.id);
+        assert_eq!(found.len(), 3);
+        assert_eq!(
+            (found[0].id, found[1].id, found[2].id),
+            (created[0].id, created[1].id, created[3].id)
+        );
+
+        let other_user = repo
+            .find_all_by_external_urls(&mut tx, 1, &discogs_urls, &spotify_urls, &musicbrainz_ids)
+            .await
+            .unwrap();
+        assert!(other_user.is_empty());
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_find_all_by_catalog_number() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = RecordRepoImpl::new();
+        let inputs = vec![
+            RecordInput { catalog_number: Some("7243 8 49606 1 2".to_string()), ..record_input_fixture(1) },
+            RecordInput { catalog_number: Some("V2940".to_string()), ..record_input_fixture(2) },
+            record_input_fixture(3),
+        ];
+        let created = repo.create_multiple(&mut tx, user.id, inputs).await.unwrap();
+
+        let found = repo.find_all_by_catalog_number(&mut tx, user.id, "724384960612").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, created[0].id);
+        assert!(repo.find_all_by_catalog_number(&mut tx, user.id, "").await.unwrap().is_empty());
+        assert!(repo.find_all_by_catalog_number(&mut tx, 1, "v2940").await.unwrap().is_empty());
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_find_ids_to_enrich() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = RecordRepoImpl::new();
+
+        let mut complete = record_input_fixture(1);
+        complete.spotify_url = Some("https://open.spotify.com/album/1".to_string());
+        let mut placeholder = record_input_fixture(2);
+        placeholder.spotify_url = complete.spotify_url.clone();
+        placeholder.cover_url = placeholder_cover_url("artist2", "title2");
+        let mut untagged = record_input_fixture(3);
+        untagged.spotify_url = complete.spotify_url.clone();
+        untagged.tags = None;
+        let no_spotify = record_input_fixture(4);
+        let mut not_on_discogs = record_input_fixture(5);
+        not_on_discogs.discogs_url = None;
+        let inputs = vec![complete, placeholder, untagged, no_spotify, not_on_discogs];
+        let created = repo.create_multiple(&mut tx, user.id, inputs).await.unwrap();
+
+        let ids = repo.find_ids_to_enrich(&mut tx, user.id).await.unwrap();
+        assert_eq!(ids, vec![created[1].id, created[2].id, created[3].id]);
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_record() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let record = create_record(&mut tx).await.unwrap();
+        let repo = RecordRepoImpl::new();
+        let input = RecordInput {
+            title: "new title".to_string(),
+            artist: record.artist.clone(),
+            release_date: "2022-02-02".to_string(),
+            cover_url: record.cover_url.clone(),
+            discogs_url: record.discogs_url.clone(),
+            spotify_url: record.spotify_url.clone(),
+            musicbrainz_id: None,
+            owned: Some(false),
+            wanted: Some(true),
+            created_at: None,
+            catalog_number: None,
+            format: None,
+            rating: None,
+            collection_folder: None,
+            media_condition: None,
+            sleeve_condition: None,
+            notes: None,
+            tags: Some(vec!["tag2".to_string(), "tag3".to_string()]),
+        };
+        let result = repo.update(&mut tx, record.id, input).await.unwrap();
+        assert_eq!(result.title, "new title");
+        assert!(result.wanted);
+
+        let found = repo.find_by_id(&mut tx, record.id).await.unwrap().unwrap();
+        let slugs: Vec<String> = found.tags.unwrap().into_iter().map(|tag| tag.slug).collect();
+        assert_eq!(slugs.len(), 2);
+        assert!(slugs.contains(&"tag2".to_string()));
+        assert!(slugs.contains(&"tag3".to_string()));
+        tx.rollback().await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_delete_record() {
         let mut db_con = create_db_con_for_test().await.unwrap();
@@ -426,4 +1075,25 @@ mod tests {
         assert!(result.is_ok());
         tx.rollback().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_delete_all_by_user_id() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let other_user = UserRepoImpl::new()
+            .create(&mut tx, &"other@test.com".to_string(), &"other_user".to_string(), &"password".to_string())
+            .await
+            .unwrap();
+        let repo = RecordRepoImpl::new();
+        repo.create_multiple(&mut tx, user.id, vec![record_input_fixture(1), record_input_fixture(2)])
+            .await
+            .unwrap();
+        let kept = repo.create(&mut tx, other_user.id, record_input_fixture(3)).await.unwrap();
+
+        let deleted = repo.delete_all_by_user_id(&mut tx, user.id).await.unwrap();
+        assert_eq!(deleted, 2);
+        assert!(repo.find_by_id(&mut tx, kept.id).await.unwrap().is_some());
+        tx.rollback().await.unwrap();
+    }
 }
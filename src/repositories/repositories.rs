@@ -2,12 +2,18 @@ use crate::repositories::record_repo::{RecordRepo, RecordRepoImpl};
 use crate::repositories::tag_repo::{TagRepo, TagRepoImpl};
 use crate::repositories::user_repo::{UserRepo, UserRepoImpl};
 use crate::repositories::collection_token_repo::{CollectionTokenRepo, CollectionTokenRepoImpl};
+use crate::repositories::import_job_repo::{ImportJobRepo, ImportJobRepoImpl};
+use crate::repositories::enrichment_job_repo::{EnrichmentJobRepo, EnrichmentJobRepoImpl};
+use crate::repositories::search_cache_repo::{SearchCacheRepo, SearchCacheRepoImpl};
 
 pub struct Repositories {
     pub record: Box<dyn RecordRepo>,
     pub user: Box<dyn UserRepo>,
     pub tag: Box<dyn TagRepo>,
     pub collection_token: Box<dyn CollectionTokenRepo>,
+    pub import_job: Box<dyn ImportJobRepo>,
+    pub enrichment_job: Box<dyn EnrichmentJobRepo>,
+    pub search_cache: Box<dyn SearchCacheRepo>,
 }
 
 impl Repositories {
@@ -17,6 +23,9 @@ impl Repositories {
             user: Box::new(UserRepoImpl::new()),
             tag: Box::new(TagRepoImpl::new()),
             collection_token: Box::new(CollectionTokenRepoImpl::new()),
+            import_job: Box::new(ImportJobRepoImpl::new()),
+            enrichment_job: Box::new(EnrichmentJobRepoImpl::new()),
+            search_cache: Box::new(SearchCacheRepoImpl::new()),
         }
     }
 }
@@ -0,0 +1,86 @@
+use crate::db::CacheCon;
+use crate::log_into;
+use crate::models::record_model::Record;
+use crate::repositories::error::DbRepoError;
+use mockall::automock;
+use rocket_db_pools::deadpool_redis::redis::AsyncCommands;
+use tracing::instrument;
+
+/// Prefix of the search keys, bumped when the cached results change shape
+const KEY_PREFIX: &str = "search:v2:";
+
+pub struct SearchCacheRepoImpl {}
+
+impl SearchCacheRepoImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Key of a search, queries differing only by case or spacing share it
+pub fn search_key(query: &str) -> String {
+    let normalized = query
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase();
+    format!("{}{}", KEY_PREFIX, normalized)
+}
+
+#[automock]
+#[async_trait]
+pub trait SearchCacheRepo: Send + Sync {
+    /// Cached results of a search, `None` when missing or expired
+    async fn get(&self, con: &mut CacheCon, query: &str) -> Result<Option<Vec<Record>>, DbRepoError>;
+
+    /// Cache the results of a search for `ttl` seconds
+    async fn set(
+        &self,
+        con: &mut CacheCon,
+        query: &str,
+        records: &[Record],
+        ttl: u64,
+    ) -> Result<(), DbRepoError>;
+}
+
+#[async_trait]
+impl SearchCacheRepo for SearchCacheRepoImpl {
+    #[instrument(name = "search_cache_repo/get", skip_all)]
+    async fn get(&self, con: &mut CacheCon, query: &str) -> Result<Option<Vec<Record>>, DbRepoError> {
+        let cached: Option<String> = con
+            .get(search_key(query))
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+
+        match cached {
+            Some(json) => Ok(Some(serde_json::from_str(&json).map_err(|e| log_into!(e, DbRepoError))?)),
+            None => Ok(None),
+        }
+    }
+
+    #[instrument(name = "search_cache_repo/set", skip_all)]
+    async fn set(
+        &self,
+        con: &mut CacheCon,
+        query: &str,
+        records: &[Record],
+        ttl: u64,
+    ) -> Result<(), DbRepoError> {
+        let json = serde_json::to_string(records).map_err(|e| log_into!(e, DbRepoError))?;
+        con.set_ex::<_, _, ()>(search_key(query), json, ttl)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::search_key;
+
+    #[test]
+    fn test_search_key_is_normalized() {
+        assert_eq!(search_key("  Daft   PUNK "), "search:v2:daft punk");
+        assert_eq!(search_key("daft punk"), search_key("Daft\tPunk"));
+    }
+}
@@ -1,10 +1,61 @@
+use crate::dto::record_dto::{RecordFilter, TagMatch};
 use crate::log_into;
-use crate::models::tag_model::Tag;
+use crate::models::tag_model::{Tag, TagKind, TagTreeRow, TagUsage};
 use crate::repositories::error::DbRepoError;
 use mockall::automock;
-use sqlx::{query, query_as, PgConnection};
+use sqlx::{query, query_as, query_scalar, FromRow, PgConnection, Postgres, QueryBuilder};
+use std::collections::HashMap;
 use tracing::instrument;
 
+/// A tag along with the record it is attached to
+#[derive(Debug, FromRow)]
+struct RecordTagRow {
+    record_id: i32,
+    id: i32,
+    name: String,
+    slug: String,
+    user_id: i32,
+    kind: String,
+    parent_id: Option<i32>,
+}
+
+/// Minimum `word_similarity` between the query and a slug, or a name with its "&" read as "n",
+/// for a fuzzy suggestion. pg_trgm's default: the `<%` filters only narrow the rows through the
+/// trigram indexes, this one holds whatever threshold the session has set
+const SUGGEST_SIMILARITY_THRESHOLD: f32 = 0.6;
+
+/// Appends the tag filter to a records query already scoped by user_id. A requested tag
+/// matches the records having it or one of its descendants, walked with a recursive CTE
+pub fn push_tag_filter(query: &mut QueryBuilder<'_, Postgres>, user_id: i32, filter: &RecordFilter) {
+    let slugs = filter.tag_slugs();
+    if slugs.is_empty() {
+        return;
+    }
+
+    let (any_kind, of_kind) = filter.tag_filters();
+    query
+        .push(" AND id IN (WITH RECURSIVE tagged (root_id, id) AS (SELECT t.id, t.id FROM tags t WHERE t.user_id = ")
+        .push_bind(user_id)
+        .push(" AND (t.slug = ANY(")
+        .push_bind(any_kind)
+        .push(") OR t.kind || ':' || t.slug = ANY(")
+        .push_bind(of_kind)
+        .push(")) UNION SELECT tagged.root_id, c.id FROM tags c JOIN tagged ON c.parent_id = tagged.id)")
+        .push(" SELECT rt.record_id FROM records_tags rt JOIN tagged ON tagged.id = rt.tag_id");
+    match filter.tag_match.unwrap_or_default() {
+        TagMatch::Any => {
+            query.push(")");
+        }
+        TagMatch::All => {
+            // Every requested tag is matched by the record, directly or through a descendant
+            query
+                .push(" GROUP BY rt.record_id HAVING COUNT(DISTINCT tagged.root_id) = ")
+                .push_bind(slugs.len() as i64)
+                .push(")");
+        }
+    }
+}
+
 pub struct TagRepoImpl {}
 
 impl TagRepoImpl {
@@ -19,12 +70,16 @@ pub trait TagRepo: Send + Sync {
     async fn create(
         &self,
         con: &mut PgConnection,
+        user_id: i32,
         name: &str,
     ) -> Result<Tag, DbRepoError>;
     
+    /// Tags are namespaced per user, two users may own a tag with the same slug.
+    /// A `kind:` prefix sets the kind of a new tag, or of an existing custom one
     async fn find_or_create(
         &self,
         con: &mut PgConnection,
+        user_id: i32,
         name: &str,
     ) -> Result<Tag, DbRepoError>;
 
@@ -37,19 +92,60 @@ pub trait TagRepo: Send + Sync {
     async fn find_by_slug(
         &self,
         con: &mut PgConnection,
+        user_id: i32,
         slug: &str,
     ) -> Result<Option<Tag>, DbRepoError>;
     
-    async fn find_all(
+    async fn find_all_by_user_id(
         &self,
         con: &mut PgConnection,
+        user_id: i32,
     ) -> Result<Vec<Tag>, DbRepoError>;
     
+    /// Tags of the user with the number of records using them, unused tags included
+    async fn find_all_usages_by_user_id(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<TagUsage>, DbRepoError>;
+
+    /// Tags of the user with their parent, counting the records of each tag and of its subtree
+    async fn find_tree_by_user_id(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<TagTreeRow>, DbRepoError>;
+
+    /// Tags of the user whose slug starts with the given one first, then the most similar ones,
+    /// then the most used ones
+    async fn suggest(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        slug: &str,
+        kind: Option<TagKind>,
+        limit: i64,
+    ) -> Result<Vec<TagUsage>, DbRepoError>;
+
+    /// Ids of a tag and of all its descendants
+    async fn find_subtree_ids(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+    ) -> Result<Vec<i32>, DbRepoError>;
+    
     async fn find_all_by_record_id(
         &self,
         con: &mut PgConnection,
         record_id: i32,
     ) -> Result<Vec<Tag>, DbRepoError>;
+
+    /// Tags of several records in a single query, keyed by record id
+    async fn find_all_by_record_ids(
+        &self,
+        con: &mut PgConnection,
+        record_ids: &[i32],
+    ) -> Result<HashMap<i32, Vec<Tag>>, DbRepoError>;
     
     async fn associate_tags_with_record(
         &self,
@@ -57,6 +153,55 @@ pub trait TagRepo: Send + Sync {
         record_id: i32,
         tag_ids: &[i32],
     ) -> Result<(), DbRepoError>;
+
+    /// Adds tags to a record, keeping the ones it already has
+    async fn attach_tags_to_record(
+        &self,
+        con: &mut PgConnection,
+        record_id: i32,
+        tag_ids: &[i32],
+    ) -> Result<(), DbRepoError>;
+
+    async fn detach_tag_from_record(
+        &self,
+        con: &mut PgConnection,
+        record_id: i32,
+        tag_id: i32,
+    ) -> Result<(), DbRepoError>;
+
+    async fn update_kind(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        kind: TagKind,
+    ) -> Result<Tag, DbRepoError>;
+
+    /// Nests a tag under another one, or makes it a root tag
+    async fn set_parent(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        parent_id: Option<i32>,
+    ) -> Result<Tag, DbRepoError>;
+
+    /// Renames a tag, its slug follows the new name
+    async fn rename(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        name: &str,
+    ) -> Result<Tag, DbRepoError>;
+
+    /// Moves the records of the source tag to the target one, then deletes the source
+    async fn merge(
+        &self,
+        con: &mut PgConnection,
+        source_id: i32,
+        target_id: i32,
+    ) -> Result<(), DbRepoError>;
+
+    /// Deletes a tag, detaching it from its records. Its children move up to its parent
+    async fn delete(&self, con: &mut PgConnection, id: i32) -> Result<(), DbRepoError>;
 }
 
 #[async_trait]
@@ -65,15 +210,18 @@ impl TagRepo for TagRepoImpl {
     async fn create(
         &self,
         con: &mut PgConnection,
+        user_id: i32,
         name: &str,
     ) -> Result<Tag, DbRepoError> {
-        let tag = Tag::new(name.to_string());
+        let tag = Tag::new(user_id, name.to_string());
         
         query_as!(
             Tag,
-            "INSERT INTO tags (name, slug) VALUES ($1, $2) RETURNING *",
+            "INSERT INTO tags (name, slug, user_id, kind) VALUES ($1, $2, $3, $4) RETURNING *",
             tag.name,
-            tag.slug
+            tag.slug,
+            tag.user_id,
+            tag.kind
         )
         .fetch_one(&mut *con)
         .await
@@ -84,18 +232,26 @@ impl TagRepo for TagRepoImpl {
     async fn find_or_create(
         &self,
         con: &mut PgConnection,
+        user_id: i32,
         name: &str,
     ) -> Result<Tag, DbRepoError> {
-        let slug = Tag::slugify(name);
+        let (kind, tag_name) = Tag::parse(name);
+        let slug = Tag::slugify(tag_name);
         
-        // Try to find existing tag by slug
-        let existing_tag = self.find_by_slug(con, &slug).await?;
+        // Try to find existing tag of the user by slug
+        let existing_tag = self.find_by_slug(con, user_id, &slug).await?;
         
         if let Some(tag) = existing_tag {
-            Ok(tag)
+            // Tags imported before they had kinds are custom ones, they take the kind they're given
+            match kind {
+                Some(kind) if kind != TagKind::Custom && tag.kind == TagKind::Custom.as_str() => {
+                    self.update_kind(con, tag.id, kind).await
+                }
+                _ => Ok(tag),
+            }
         } else {
             // Create new tag if not found
-            self.create(con, name).await
+            self.create(con, user_id, name).await
         }
     }
 
@@ -111,27 +267,134 @@ impl TagRepo for TagRepoImpl {
             .map_err(|e| log_into!(e, DbRepoError))
     }
     
-    #[instrument(name = "tag_repo/find_by_slug", skip_all, fields(slug = %slug))]
+    #[instrument(name = "tag_repo/find_by_slug", skip_all, fields(user_id = %user_id, slug = %slug))]
     async fn find_by_slug(
         &self,
         con: &mut PgConnection,
+        user_id: i32,
         slug: &str,
     ) -> Result<Option<Tag>, DbRepoError> {
-        query_as!(Tag, "SELECT * FROM tags WHERE slug = $1", slug)
+        query_as!(Tag, "SELECT * FROM tags WHERE user_id = $1 AND slug = $2", user_id, slug)
             .fetch_optional(&mut *con)
             .await
             .map_err(|e| log_into!(e, DbRepoError))
     }
     
-    #[instrument(name = "tag_repo/find_all", skip_all)]
-    async fn find_all(&self, con: &mut PgConnection) -> Result<Vec<Tag>, DbRepoError> {
-        let tags = query_as!(Tag, "SELECT * FROM tags")
+    #[instrument(name = "tag_repo/find_all_by_user_id", skip_all, fields(user_id = %user_id))]
+    async fn find_all_by_user_id(&self, con: &mut PgConnection, user_id: i32) -> Result<Vec<Tag>, DbRepoError> {
+        let tags = query_as!(Tag, "SELECT * FROM tags WHERE user_id = $1 ORDER BY name", user_id)
             .fetch_all(&mut *con)
             .await
             .map_err(|e| log_into!(e, DbRepoError))?;
         Ok(tags)
     }
     
+    #[instrument(name = "tag_repo/find_all_usages_by_user_id", skip_all, fields(user_id = %user_id))]
+    async fn find_all_usages_by_user_id(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<TagUsage>, DbRepoError> {
+        query_as!(
+            TagUsage,
+            r#"SELECT t.name, t.slug, t.kind, COUNT(rt.record_id) AS "records!" FROM tags t
+             LEFT JOIN records_tags rt ON rt.tag_id = t.id
+             WHERE t.user_id = $1
+             GROUP BY t.id
+             ORDER BY t.name"#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+    
+    #[instrument(name = "tag_repo/find_tree_by_user_id", skip_all, fields(user_id = %user_id))]
+    async fn find_tree_by_user_id(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<TagTreeRow>, DbRepoError> {
+        // Pairs every tag with itself and each of its descendants, a record is counted
+        // once per subtree even when it has several tags of it
+        query_as!(
+            TagTreeRow,
+            r#"WITH RECURSIVE subtree (root_id, id) AS (
+                 SELECT id, id FROM tags WHERE user_id = $1
+                 UNION
+                 SELECT subtree.root_id, c.id FROM tags c JOIN subtree ON c.parent_id = subtree.id
+             )
+             SELECT t.id, t.parent_id, t.name, t.slug, t.kind,
+                 COUNT(DISTINCT rt.record_id) FILTER (WHERE rt.tag_id = t.id) AS "records!",
+                 COUNT(DISTINCT rt.record_id) AS "total_records!"
+             FROM tags t
+             JOIN subtree ON subtree.root_id = t.id
+             LEFT JOIN records_tags rt ON rt.tag_id = subtree.id
+             GROUP BY t.id
+             ORDER BY t.name"#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "tag_repo/suggest", skip_all, fields(user_id = %user_id, slug = %slug))]
+    async fn suggest(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        slug: &str,
+        kind: Option<TagKind>,
+        limit: i64,
+    ) -> Result<Vec<TagUsage>, DbRepoError> {
+        // Slugs only hold letters, digits and hyphens, they need no LIKE escaping
+        query_as!(
+            TagUsage,
+            r#"SELECT t.name, t.slug, t.kind, COUNT(rt.record_id) AS "records!" FROM tags t
+             LEFT JOIN records_tags rt ON rt.tag_id = t.id
+             WHERE t.user_id = $1
+               AND (t.slug LIKE $2::text || '%'
+                 OR ($2::text <% t.slug AND word_similarity($2::text, t.slug) >= $5)
+                 OR ($2::text <% replace(lower(t.name), '&', 'n')
+                   AND word_similarity($2::text, replace(lower(t.name), '&', 'n')) >= $5))
+               AND ($3::varchar IS NULL OR t.kind = $3)
+             GROUP BY t.id
+             ORDER BY t.slug LIKE $2::text || '%' DESC,
+               GREATEST(word_similarity($2::text, t.slug), word_similarity($2::text, replace(lower(t.name), '&', 'n'))) DESC,
+               COUNT(rt.record_id) DESC, t.name
+             LIMIT $4"#,
+            user_id,
+            slug,
+            kind.map(|kind| kind.as_str()),
+            limit,
+            SUGGEST_SIMILARITY_THRESHOLD
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "tag_repo/find_subtree_ids", skip_all, fields(id = %id))]
+    async fn find_subtree_ids(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+    ) -> Result<Vec<i32>, DbRepoError> {
+        query_scalar!(
+            r#"WITH RECURSIVE subtree (id) AS (
+                 SELECT $1::int
+                 UNION
+                 SELECT c.id FROM tags c JOIN subtree ON c.parent_id = subtree.id
+             )
+             SELECT id AS "id!" FROM subtree"#,
+            id
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
     #[instrument(name = "tag_repo/find_all_by_record_id", skip_all, fields(record_id = %record_id))]
     async fn find_all_by_record_id(
         &self,
@@ -151,6 +414,41 @@ impl TagRepo for TagRepoImpl {
         Ok(tags)
     }
     
+    #[instrument(name = "tag_repo/find_all_by_record_ids", skip_all)]
+    async fn find_all_by_record_ids(
+        &self,
+        con: &mut PgConnection,
+        record_ids: &[i32],
+    ) -> Result<HashMap<i32, Vec<Tag>>, DbRepoError> {
+        if record_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let rows = query_as!(
+            RecordTagRow,
+            "SELECT rt.record_id, t.id, t.name, t.slug, t.user_id, t.kind, t.parent_id FROM tags t
+             JOIN records_tags rt ON rt.tag_id = t.id
+             WHERE rt.record_id = ANY($1)",
+            record_ids
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+
+        let mut tags_by_record: HashMap<i32, Vec<Tag>> = HashMap::new();
+        for row in rows {
+            tags_by_record.entry(row.record_id).or_default().push(Tag {
+                id: row.id,
+                name: row.name,
+                slug: row.slug,
+                user_id: row.user_id,
+                kind: row.kind,
+                parent_id: row.parent_id,
+            });
+        }
+        Ok(tags_by_record)
+    }
+    
     #[instrument(name = "tag_repo/associate_tags_with_record", skip_all, fields(record_id = %record_id))]
     async fn associate_tags_with_record(
         &self,
@@ -180,6 +478,8 @@ impl TagRepo for TagRepoImpl {
             query_string.push_str(&format!("($1, ${})", i + 2));
             values.push(*tag_id);
         }
+        // The same tag may be given twice under different spellings
+        query_string.push_str(" ON CONFLICT DO NOTHING");
         
         // Build the query with parameters
         let mut query_builder = sqlx::query(&query_string).bind(record_id);
@@ -194,12 +494,150 @@ impl TagRepo for TagRepoImpl {
             
         Ok(())
     }
+
+    #[instrument(name = "tag_repo/attach_tags_to_record", skip_all, fields(record_id = %record_id))]
+    async fn attach_tags_to_record(
+        &self,
+        con: &mut PgConnection,
+        record_id: i32,
+        tag_ids: &[i32],
+    ) -> Result<(), DbRepoError> {
+        query!(
+            "INSERT INTO records_tags (record_id, tag_id)
+             SELECT $1, tag_id FROM UNNEST($2::int[]) AS tag_id
+             ON CONFLICT DO NOTHING",
+            record_id,
+            tag_ids
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
+
+    #[instrument(name = "tag_repo/detach_tag_from_record", skip_all, fields(record_id = %record_id, tag_id = %tag_id))]
+    async fn detach_tag_from_record(
+        &self,
+        con: &mut PgConnection,
+        record_id: i32,
+        tag_id: i32,
+    ) -> Result<(), DbRepoError> {
+        query!(
+            "DELETE FROM records_tags WHERE record_id = $1 AND tag_id = $2",
+            record_id,
+            tag_id
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
+
+    #[instrument(name = "tag_repo/update_kind", skip_all, fields(id = %id))]
+    async fn update_kind(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        kind: TagKind,
+    ) -> Result<Tag, DbRepoError> {
+        query_as!(
+            Tag,
+            "UPDATE tags SET kind = $2 WHERE id = $1 RETURNING *",
+            id,
+            kind.as_str()
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "tag_repo/set_parent", skip_all, fields(id = %id))]
+    async fn set_parent(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        parent_id: Option<i32>,
+    ) -> Result<Tag, DbRepoError> {
+        query_as!(
+            Tag,
+            "UPDATE tags SET parent_id = $2 WHERE id = $1 RETURNING *",
+            id,
+            parent_id
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "tag_repo/rename", skip_all, fields(id = %id))]
+    async fn rename(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        name: &str,
+    ) -> Result<Tag, DbRepoError> {
+        query_as!(
+            Tag,
+            "UPDATE tags SET name = $2, slug = $3 WHERE id = $1 RETURNING *",
+            id,
+            name,
+            Tag::slugify(name)
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "tag_repo/merge", skip_all, fields(source_id = %source_id, target_id = %target_id))]
+    async fn merge(
+        &self,
+        con: &mut PgConnection,
+        source_id: i32,
+        target_id: i32,
+    ) -> Result<(), DbRepoError> {
+        // Records already having both tags keep a single association
+        query!(
+            "INSERT INTO records_tags (record_id, tag_id)
+             SELECT record_id, $2 FROM records_tags WHERE tag_id = $1
+             ON CONFLICT DO NOTHING",
+            source_id,
+            target_id
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+
+        self.delete(con, source_id).await
+    }
+
+    #[instrument(name = "tag_repo/delete", skip_all, fields(id = %id))]
+    async fn delete(&self, con: &mut PgConnection, id: i32) -> Result<(), DbRepoError> {
+        // Deleting House keeps Deep House under Electronic
+        query!(
+            "UPDATE tags SET parent_id = (SELECT parent_id FROM tags WHERE id = $1) WHERE parent_id = $1",
+            id
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+
+        query!("DELETE FROM tags WHERE id = $1", id)
+            .execute(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::test::db::create_db_con_for_test;
+    use crate::repositories::record_repo::{RecordRepo, RecordRepoImpl};
+    use crate::dto::record_dto::{RecordInput, RecordPagination};
+    use crate::models::tag_model::TagNode;
+    use crate::test::fixture::record::record_input_fixture;
+    use crate::test::repositories::prepare::user::create_user;
     use sqlx::Connection;
 
     #[tokio::test]
@@ -208,11 +646,167 @@ mod tests {
         let mut tx = db_con.begin().await.unwrap();
         
         let repo = TagRepoImpl::new();
-        let result = repo.create(&mut tx, "Test Tag").await;
+        let result = repo.create(&mut tx, 1, "Test Tag").await;
         
         assert!(result.is_ok());
         assert_eq!(result.unwrap().name, "Test Tag");
         
         tx.rollback().await.unwrap();
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_tags_are_scoped_by_user() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+
+        let other_user_id = create_user(&mut tx).await.unwrap().id;
+
+        let repo = TagRepoImpl::new();
+        let mine = repo.find_or_create(&mut tx, 1, "Test Jazz").await.unwrap();
+        let theirs = repo.find_or_create(&mut tx, other_user_id, "TEST JAZZ").await.unwrap();
+
+        // Same slug, but each user keeps their own tag and spelling
+        assert_ne!(mine.id, theirs.id);
+        assert_eq!(theirs.name, "TEST JAZZ");
+        assert_eq!(repo.find_or_create(&mut tx, 1, "test jazz").await.unwrap().id, mine.id);
+        let found = repo.find_by_slug(&mut tx, other_user_id, "test-jazz").await.unwrap().unwrap();
+        assert_eq!(found.id, theirs.id);
+        let tags = repo.find_all_by_user_id(&mut tx, other_user_id).await.unwrap();
+        assert_eq!(tags.len(), 1);
+
+        // An imported kind is kept, and given to a custom tag with the same slug
+        let label = repo.find_or_create(&mut tx, 1, "label:Test Label").await.unwrap();
+        assert_eq!((label.name.as_str(), label.kind.as_str()), ("Test Label", "label"));
+        assert_eq!(repo.find_or_create(&mut tx, 1, "Test Label").await.unwrap().kind, "label");
+        assert_eq!(mine.kind, "custom");
+        let genre = repo.find_or_create(&mut tx, 1, "genre:Test Jazz").await.unwrap();
+        assert_eq!((genre.id, genre.kind.as_str()), (mine.id, "genre"));
+
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_merge_tags() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+
+        let repo = TagRepoImpl::new();
+        let source = repo.create(&mut tx, 1, "Test Merge Source").await.unwrap();
+        let target = repo.create(&mut tx, 1, "Test Merge Target").await.unwrap();
+        let record_repo = RecordRepoImpl::new();
+        let both = record_repo.create(&mut tx, 1, record_input_fixture(901)).await.unwrap();
+        let source_only = record_repo.create(&mut tx, 1, record_input_fixture(902)).await.unwrap();
+        repo.attach_tags_to_record(&mut tx, both.id, &[source.id, target.id]).await.unwrap();
+        repo.attach_tags_to_record(&mut tx, source_only.id, &[source.id, source.id]).await.unwrap();
+
+        repo.merge(&mut tx, source.id, target.id).await.unwrap();
+
+        // The record having both tags keeps a single one
+        for record in [both, source_only] {
+            let tags = repo.find_all_by_record_id(&mut tx, record.id).await.unwrap();
+            assert_eq!(tags.iter().filter(|tag| tag.id == target.id).count(), 1);
+            assert!(!tags.iter().any(|tag| tag.id == source.id));
+        }
+        assert!(repo.find_by_id(&mut tx, source.id).await.unwrap().is_none());
+
+        let usages = repo.find_all_usages_by_user_id(&mut tx, 1).await.unwrap();
+        let usage = usages.iter().find(|usage| usage.slug == "test-merge-target").unwrap();
+        assert_eq!(usage.records, 2);
+
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tag_tree() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+
+        let repo = TagRepoImpl::new();
+        let electronic = repo.create(&mut tx, user.id, "genre:Electronic").await.unwrap();
+        let house = repo.create(&mut tx, user.id, "style:House").await.unwrap();
+        let deep_house = repo.create(&mut tx, user.id, "style:Deep House").await.unwrap();
+        repo.set_parent(&mut tx, house.id, Some(electronic.id)).await.unwrap();
+        repo.set_parent(&mut tx, deep_house.id, Some(house.id)).await.unwrap();
+
+        let record_repo = RecordRepoImpl::new();
+        let tags = [vec!["style:Deep House"], vec!["genre:Electronic", "style:House"], vec!["Jazz"]];
+        let inputs = (1..=3)
+            .map(|id| RecordInput {
+                tags: Some(tags[id - 1].iter().map(|tag| tag.to_string()).collect()),
+                ..record_input_fixture(id)
+            })
+            .collect();
+        record_repo.create_multiple(&mut tx, user.id, inputs).await.unwrap();
+
+        // The second record has two tags of the Electronic subtree, it is counted once
+        let tree = TagNode::build_tree(repo.find_tree_by_user_id(&mut tx, user.id).await.unwrap());
+        let counts = |node: &TagNode| (node.slug.clone(), node.records, node.total_records);
+        assert_eq!(tree.iter().map(counts).collect::<Vec<_>>(), [("electronic".to_string(), 1, 2), ("jazz".to_string(), 1, 1)]);
+        assert_eq!(counts(&tree[0].children[0]), ("house".to_string(), 1, 2));
+        assert_eq!(counts(&tree[0].children[0].children[0]), ("deep-house".to_string(), 1, 1));
+
+        // Filtering by a tag includes the records of its descendants
+        let filters = [
+            ("electronic", vec!["title1", "title2"]),
+            ("genre:electronic", vec!["title1", "title2"]),
+            ("house", vec!["title1", "title2"]),
+            ("deep-house", vec!["title1"]),
+        ];
+        for (tag, expected) in filters {
+            let filter = RecordFilter { tags: vec![tag.to_string()], ..Default::default() };
+            let page = record_repo
+                .find_all_by_user_id(&mut tx, user.id, &filter, &RecordPagination::default())
+                .await
+                .unwrap();
+            let mut titles: Vec<String> = page.items.into_iter().map(|record| record.title).collect();
+            titles.sort();
+            assert_eq!(titles, expected);
+        }
+
+        let subtree = repo.find_subtree_ids(&mut tx, house.id).await.unwrap();
+        assert_eq!(subtree.len(), 2);
+        assert!(subtree.contains(&deep_house.id));
+
+        // Deleting House moves Deep House up under Electronic
+        repo.delete(&mut tx, house.id).await.unwrap();
+        let deep_house = repo.find_by_id(&mut tx, deep_house.id).await.unwrap().unwrap();
+        assert_eq!(deep_house.parent_id, Some(electronic.id));
+
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_suggest_tags() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+
+        let repo = TagRepoImpl::new();
+        for name in ["genre:R&B / Soul", "Rnb Classics", "style:Deep House", "House", "House Music", "Jazz"] {
+            repo.create(&mut tx, user.id, name).await.unwrap();
+        }
+        RecordRepoImpl::new()
+            .create(&mut tx, user.id, RecordInput { tags: Some(vec!["House Music".to_string()]), ..record_input_fixture(1) })
+            .await
+            .unwrap();
+
+        let soul = Tag::slugify("R&B / Soul");
+        let suggestions = [
+            // Prefix matches first, then fuzzy ones
+            ("rnb", None, vec!["rnb-classics", soul.as_str()]),
+            ("soul", None, vec![soul.as_str()]),
+            // Among equal matches the most used tag wins
+            ("hou", None, vec!["house-music", "house", "deep-house"]),
+            ("style:house", Some(TagKind::Style), vec!["deep-house"]),
+            ("", None, vec!["house-music", "deep-house", "house"]),
+        ];
+        for (q, kind, expected) in suggestions {
+            let tags = repo.suggest(&mut tx, user.id, &Tag::slug_of(q), kind, 3).await.unwrap();
+            let slugs: Vec<&str> = tags.iter().map(|tag| tag.slug.as_str()).collect();
+            assert_eq!(slugs, expected, "suggestions for {:?}", q);
+        }
+
+        tx.rollback().await.unwrap();
+    }
+}
@@ -46,6 +46,13 @@ pub trait UserRepo: Send + Sync {
         email: &String,
         username: &String,
     ) -> Result<User, DbRepoError>;
+    /// Gives an account restored from a backup the creation date it had
+    async fn set_created_at(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        created_at: chrono::NaiveDateTime,
+    ) -> Result<(), DbRepoError>;
     async fn delete(&self, con: &mut PgConnection, id: i32) -> Result<(), DbRepoError>;
 }
 
@@ -136,6 +143,20 @@ impl UserRepo for UserRepoImpl {
         .map_err(|e| log_into!(e, DbRepoError))
     }
 
+    #[instrument(name = "user_repo/set_created_at", skip_all, fields(id = %id))]
+    async fn set_created_at(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+        created_at: chrono::NaiveDateTime,
+    ) -> Result<(), DbRepoError> {
+        query!("UPDATE users SET created_at = $1 WHERE id = $2", created_at, id)
+            .execute(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
+
     #[instrument(name = "user_repo/delete", skip_all, fields(id = %id))]
     async fn delete(&self, con: &mut PgConnection, id: i32) -> Result<(), DbRepoError> {
         query!("DELETE FROM users WHERE id = $1", id)
@@ -182,6 +203,11 @@ mod tests {
         let new_email = "new_email@mail.com".to_string();
         let result = repo.update(&mut tx, user.id, &new_email, &user.username).await;
         assert!(result.is_ok());
+
+        let created_at = chrono::NaiveDate::from_ymd_opt(2020, 5, 1).unwrap().and_hms_opt(8, 0, 0).unwrap();
+        repo.set_created_at(&mut tx, user.id, created_at).await.unwrap();
+        let user = repo.find_by_id(&mut tx, user.id).await.unwrap().unwrap();
+        assert_eq!(user.created_at, created_at);
         tx.rollback().await.unwrap();
     }
 
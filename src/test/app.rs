@@ -1,17 +1,35 @@
 use crate::app::App;
+use crate::providers::{
+    metadata_provider::{MockCoverProvider, MockMetadataProvider},
+    providers::Providers,
+};
 use crate::repositories::{
     record_repo::MockRecordRepo, repositories::Repositories, tag_repo::MockTagRepo,
-    user_repo::MockUserRepo, collection_token_repo::MockCollectionTokenRepo
+    user_repo::MockUserRepo, collection_token_repo::MockCollectionTokenRepo,
+    import_job_repo::MockImportJobRepo, search_cache_repo::MockSearchCacheRepo,
+    enrichment_job_repo::MockEnrichmentJobRepo
 };
 use crate::use_cases::{
     auth_use_case::MockAuthUseCase, record_use_case::MockRecordUseCase, use_cases::UseCases,
-    user_use_case::MockUserUseCase, collection_use_case::MockCollectionUseCase
+    user_use_case::MockUserUseCase, collection_use_case::MockCollectionUseCase,
+    import_job_use_case::MockImportJobUseCase, backup_use_case::MockBackupUseCase,
+    enrichment_job_use_case::MockEnrichmentJobUseCase, tag_use_case::MockTagUseCase
 };
 
 pub fn create_app_for_test() -> App {
     let repos = create_repos_for_test();
     let use_cases = create_use_cases_for_test();
-    App::new(use_cases, repos)
+    let providers = create_providers_for_test();
+    App::new(use_cases, repos, providers)
+}
+
+pub fn create_providers_for_test() -> Providers {
+    Providers {
+        discogs: Box::new(MockMetadataProvider::new()),
+        spotify: Box::new(MockMetadataProvider::new()),
+        musicbrainz: Box::new(MockMetadataProvider::new()),
+        cover_art: Box::new(MockCoverProvider::new()),
+    }
 }
 
 pub fn create_repos_for_test() -> Repositories {
@@ -19,11 +37,17 @@ pub fn create_repos_for_test() -> Repositories {
     let record_repo = Box::new(MockRecordRepo::new());
     let tag_repo = Box::new(MockTagRepo::new());
     let collection_token_repo = Box::new(MockCollectionTokenRepo::new());
+    let import_job_repo = Box::new(MockImportJobRepo::new());
+    let search_cache_repo = Box::new(MockSearchCacheRepo::new());
+    let enrichment_job_repo = Box::new(MockEnrichmentJobRepo::new());
     Repositories {
         user: user_repo,
         record: record_repo,
         tag: tag_repo,
         collection_token: collection_token_repo,
+        import_job: import_job_repo,
+        search_cache: search_cache_repo,
+        enrichment_job: enrichment_job_repo,
     }
 }
 
@@ -32,10 +56,18 @@ pub fn create_use_cases_for_test() -> UseCases {
     let record = Box::new(MockRecordUseCase::new());
     let auth = Box::new(MockAuthUseCase::new());
     let collection = Box::new(MockCollectionUseCase::new());
+    let import_job = Box::new(MockImportJobUseCase::new());
+    let backup = Box::new(MockBackupUseCase::new());
+    let enrichment_job = Box::new(MockEnrichmentJobUseCase::new());
+    let tag = Box::new(MockTagUseCase::new());
     UseCases {
         user,
         record,
         auth,
         collection,
+        import_job,
+        backup,
+        enrichment_job,
+        tag,
     }
 }
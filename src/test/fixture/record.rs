@@ -1,5 +1,6 @@
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate};
 
+use crate::dto::record_dto::RecordInput;
 use crate::models::{record_model::Record, tag_model::TagResponse};
 
 pub fn record_fixture(id: usize) -> Record {
@@ -11,19 +12,33 @@ pub fn record_fixture(id: usize) -> Record {
         cover_url: format!("cover_url{}", id),
         discogs_url: Some(format!("discogs_url{}", id)),
         spotify_url: Some(format!("spotify_url{}", id)),
+        musicbrainz_id: None,
         user_id: 1,
         owned: true,
         wanted: false,
+        created_at: DateTime::parse_from_rfc3339("2021-01-01T00:00:00Z")
+            .unwrap()
+            .naive_utc(),
+        catalog_number: None,
+        format: None,
+        rating: None,
+        collection_folder: None,
+        media_condition: None,
+        sleeve_condition: None,
+        notes: None,
         tags: Some(vec![
             TagResponse {
                 name: format!("tag{}-1", id),
                 slug: format!("tag{}-1", id),
+                kind: "custom".to_string(),
             },
             TagResponse {
                 name: format!("tag{}-2", id),
                 slug: format!("tag{}-2", id),
+                kind: "custom".to_string(),
             },
         ]),
+        match_confidence: None,
     }
 }
 
@@ -34,3 +49,26 @@ pub fn records_fixture(num: usize) -> Vec<Record> {
     }
     records
 }
+
+pub fn record_input_fixture(id: usize) -> RecordInput {
+    RecordInput {
+        title: format!("title{}", id),
+        artist: format!("artist{}", id),
+        release_date: "2021-01-01".to_string(),
+        cover_url: format!("https://example.com/cover{}.jpg", id),
+        discogs_url: Some(format!("https://www.discogs.com/release/{}", id)),
+        spotify_url: None,
+        musicbrainz_id: None,
+        owned: Some(true),
+        wanted: Some(false),
+        created_at: None,
+        catalog_number: None,
+        format: None,
+        rating: None,
+        collection_folder: None,
+        media_condition: None,
+        sleeve_condition: None,
+        notes: None,
+        tags: Some(vec![format!("tag{}-1", id), format!("tag{}-2", id)]),
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Local HTTP server standing in for an external API
+pub struct StubServer {
+    pub url: String,
+    hits: Arc<AtomicUsize>,
+}
+
+impl StubServer {
+    /// Number of requests received so far
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::SeqCst)
+    }
+}
+
+/// Status, extra headers and JSON body of a stub response
+pub type StubResponse = (u16, Vec<(&'static str, String)>, String);
+
+/// Starts a stub server answering with the given status and JSON bodies in order,
+/// the last one is repeated
+pub async fn serve(responses: Vec<(u16, String)>) -> StubServer {
+    let responses = responses
+        .into_iter()
+        .map(|(status, body)| (status, Vec::new(), body))
+        .collect();
+    serve_with_headers(responses).await
+}
+
+/// Same as `serve`, with extra headers on each response
+pub async fn serve_with_headers(responses: Vec<StubResponse>) -> StubServer {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let hits = Arc::new(AtomicUsize::new(0));
+
+    let counter = hits.clone();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let index = counter.fetch_add(1, Ordering::SeqCst);
+            let (status, headers, body) = responses[index.min(responses.len() - 1)].clone();
+
+            // The requests of the providers fit in a single read
+            let mut request = vec![0; 8192];
+            let _ = socket.read(&mut request).await;
+
+            let headers: String = headers
+                .iter()
+                .map(|(name, value)| format!("{}: {}\r\n", name, value))
+                .collect();
+            let response = format!(
+                "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+                status,
+                body.len(),
+                headers,
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+            let _ = socket.shutdown().await;
+        }
+    });
+
+    StubServer { url, hits }
+}
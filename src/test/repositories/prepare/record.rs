@@ -29,8 +29,17 @@ pub async fn create_record(db_con: &mut PgConnection) -> Result<Record, DbRepoEr
                 cover_url,
                 discogs_url,
                 spotify_url,
+                musicbrainz_id: None,
                 owned: Some(owned),
                 wanted: Some(wanted),
+                created_at: None,
+                catalog_number: None,
+                format: None,
+                rating: None,
+                collection_folder: None,
+                media_condition: None,
+                sleeve_condition: None,
+                notes: None,
                 tags: Some(vec!["tag1".to_string(), "tag2".to_string()]),
             },
         )
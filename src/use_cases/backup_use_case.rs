@@ -0,0 +1,550 @@
+use crate::db::DbCon;
+use crate::dto::backup_dto::{AccountBackup, BackupProfile, BackupRecord, BackupTag, RestoreReport, BACKUP_VERSION};
+use crate::dto::record_dto::{RecordFilter, RecordInput, RecordPagination, RecordSort, SortDirection, EXPORT_PAGE_SIZE};
+use crate::error::app_error::AppError;
+use crate::models::tag_model::TagResponse;
+use crate::repositories::error::DbRepoError;
+use crate::repositories::repositories::Repositories;
+use crate::{app_err, app_err_ensure, log_into};
+use mockall::automock;
+use sqlx::Connection;
+use std::collections::HashMap;
+use tracing::instrument;
+use validator::Validate;
+
+pub struct BackupUseCaseImpl {}
+
+impl BackupUseCaseImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait BackupUseCase: Send + Sync {
+    /// Collect the profile, records, tags and collection token of a user
+    async fn export(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<AccountBackup, AppError>;
+
+    /// Recreate a backup in a single transaction.
+    /// With a `user_id` the collection of that account is replaced,
+    /// otherwise a new account is created from the backup profile
+    async fn restore(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: Option<i32>,
+        backup: AccountBackup,
+    ) -> Result<RestoreReport, AppError>;
+}
+
+#[async_trait]
+impl BackupUseCase for BackupUseCaseImpl {
+    #[instrument(name = "backup_use_case/export", skip_all, fields(user_id = %user_id))]
+    async fn export(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<AccountBackup, AppError> {
+        let user = repos
+            .user
+            .find_by_id(&mut *db_con, user_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+
+        let mut records: Vec<BackupRecord> = Vec::new();
+        let filter = RecordFilter::default();
+        let mut after = None;
+        loop {
+            let pagination = RecordPagination {
+                limit: Some(EXPORT_PAGE_SIZE),
+                after: after.take(),
+                sort: Some(RecordSort::Added),
+                direction: Some(SortDirection::Asc),
+            };
+            let page = repos
+                .record
+                .find_all_by_user_id(&mut *db_con, user_id, &filter, &pagination)
+                .await?;
+            records.extend(page.items.into_iter().map(BackupRecord::from));
+
+            match page.next_cursor {
+                Some(cursor) => after = Some(cursor),
+                None => break,
+            }
+        }
+
+        // Parents are kept by slug, ids differ on the instance the backup is restored on
+        let user_tags = repos.tag.find_all_by_user_id(&mut *db_con, user_id).await?;
+        let slugs: HashMap<i32, String> = user_tags.iter().map(|tag| (tag.id, tag.slug.clone())).collect();
+        let tags: Vec<BackupTag> = user_tags
+            .into_iter()
+            .map(|tag| BackupTag {
+                parent: tag.parent_id.and_then(|parent_id| slugs.get(&parent_id).cloned()),
+                tag: TagResponse::from(tag),
+            })
+            .collect();
+
+        // Users without a shared collection have no token
+        let collection_token = match repos.collection_token.find_by_user_id(&mut *db_con, user_id).await {
+            Ok(token) => Some(token.token),
+            Err(DbRepoError::SqlxError(sqlx::Error::RowNotFound)) => None,
+            Err(e) => return Err(AppError::from(e)),
+        };
+
+        Ok(AccountBackup {
+            version: BACKUP_VERSION,
+            exported_at: chrono::Utc::now().naive_utc(),
+            profile: BackupProfile {
+                email: user.email,
+                username: user.username,
+                password_hash: user.password,
+                created_at: user.created_at,
+            },
+            tags,
+            records,
+            collection_token,
+        })
+    }
+
+    #[instrument(name = "backup_use_case/restore", skip_all)]
+    async fn restore(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: Option<i32>,
+        backup: AccountBackup,
+    ) -> Result<RestoreReport, AppError> {
+        app_err_ensure!(
+            backup.version <= BACKUP_VERSION,
+            400,
+            &format!("Unsupported backup version {}", backup.version)
+        );
+
+        if user_id.is_none() {
+            backup
+                .profile
+                .validate()
+                .map_err(|errors| AppError::ValidationError { errors })?;
+        }
+        let inputs: Vec<RecordInput> = backup.records.into_iter().map(RecordInput::from).collect();
+        for input in &inputs {
+            input
+                .validate()
+                .map_err(|errors| AppError::ValidationError { errors })?;
+        }
+
+        // Nothing is kept if any step fails
+        let mut tx = db_con.begin().await.map_err(|e| log_into!(e, DbRepoError))?;
+
+        let user_id = match user_id {
+            Some(user_id) => {
+                repos
+                    .user
+                    .find_by_id(&mut tx, user_id)
+                    .await?
+                    .ok_or(AppError::NotFound)?;
+                repos.record.delete_all_by_user_id(&mut tx, user_id).await?;
+                repos.collection_token.delete_all_by_user_id(&mut tx, user_id).await?;
+                user_id
+            }
+            None => {
+                let profile = &backup.profile;
+                if repos.user.find_by_email(&mut tx, &profile.email).await?.is_some() {
+                    return app_err!(400, "Email already in use");
+                }
+                if repos.user.find_by_username(&mut tx, &profile.username).await?.is_some() {
+                    return app_err!(400, "Username already taken");
+                }
+                let user = repos
+                    .user
+                    .create(&mut tx, &profile.email, &profile.username, &profile.password_hash)
+                    .await?;
+                repos.user.set_created_at(&mut tx, user.id, profile.created_at).await?;
+                user.id
+            }
+        };
+
+        // Tags are created before being nested, a parent may come after its children
+        let mut tag_ids: HashMap<&str, i32> = HashMap::new();
+        for backup_tag in &backup.tags {
+            let tag = repos.tag.find_or_create(&mut tx, user_id, &backup_tag.tag.qualified_name()).await?;
+            tag_ids.insert(&backup_tag.tag.slug, tag.id);
+        }
+        for backup_tag in &backup.tags {
+            let parent_id = backup_tag.parent.as_deref().and_then(|parent| tag_ids.get(parent));
+            if let (Some(&id), Some(&parent_id)) = (tag_ids.get(backup_tag.tag.slug.as_str()), parent_id) {
+                repos.tag.set_parent(&mut tx, id, Some(parent_id)).await?;
+            }
+        }
+
+        let mut records = 0;
+        let mut inputs = inputs.into_iter().peekable();
+        while inputs.peek().is_some() {
+            let chunk: Vec<RecordInput> = inputs.by_ref().take(EXPORT_PAGE_SIZE as usize).collect();
+            records += repos.record.create_multiple(&mut tx, user_id, chunk).await?.len();
+        }
+
+        // A token already used on this instance would leak another collection, a new one is issued
+        let collection_token = match backup.collection_token {
+            Some(token) => {
+                let token = match repos.collection_token.find_by_token(&mut tx, &token).await? {
+                    Some(_) => repos.collection_token.create(&mut tx, user_id).await?,
+                    None => repos.collection_token.create_with_token(&mut tx, user_id, &token).await?,
+                };
+                Some(token.token)
+            }
+            None => None,
+        };
+
+        tx.commit().await.map_err(|e| log_into!(e, DbRepoError))?;
+
+        Ok(RestoreReport {
+            user_id,
+            records,
+            tags: backup.tags.len(),
+            collection_token,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::collection_model::CollectionToken;
+    use crate::models::record_model::RecordPage;
+    use crate::models::tag_model::Tag;
+    use crate::repositories::collection_token_repo::MockCollectionTokenRepo;
+    use crate::repositories::record_repo::MockRecordRepo;
+    use crate::repositories::tag_repo::MockTagRepo;
+    use crate::repositories::user_repo::MockUserRepo;
+    use crate::test::app::create_repos_for_test;
+    use crate::test::db::create_db_con_for_test;
+    use crate::test::fixture::record::record_fixture;
+    use crate::test::fixture::user::user_fixture;
+
+    fn backup_fixture(version: u32) -> AccountBackup {
+        let records = (1..=2)
+            .map(|id| {
+                let mut record = BackupRecord::from(record_fixture(id));
+                record.cover_url = format!("https://example.com/cover{}.jpg", id);
+                record.discogs_url = Some(format!("https://www.discogs.com/release/{}", id));
+                record.spotify_url = None;
+                record
+            })
+            .collect();
+
+        AccountBackup {
+            version,
+            exported_at: chrono::NaiveDateTime::default(),
+            profile: BackupProfile {
+                email: "restored@mail.com".to_string(),
+                username: "restored".to_string(),
+                password_hash: bcrypt::hash("password", 4).unwrap(),
+                created_at: chrono::NaiveDate::from_ymd_opt(2020, 5, 1).unwrap().and_hms_opt(8, 0, 0).unwrap(),
+            },
+            tags: vec![BackupTag {
+                tag: TagResponse::from(Tag::new(7, "Jazz".to_string())),
+                parent: None,
+            }],
+            records,
+            collection_token: Some("shared-token".to_string()),
+        }
+    }
+
+    #[rocket::async_test]
+    async fn test_export_reads_every_page() {
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_find_by_id()
+            .returning(|_, id| Ok(Some(user_fixture(id as usize))));
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_user_id()
+            .times(2)
+            .returning(|_, _, _, pagination| {
+                let (items, next_cursor) = match pagination.after {
+                    None => (vec![record_fixture(1), record_fixture(2)], Some("cursor".to_string())),
+                    Some(_) => (vec![record_fixture(1)], None),
+                };
+                Ok(RecordPage { total: 3, next_cursor, items })
+            });
+        let mut mock_tag_repo = MockTagRepo::new();
+        mock_tag_repo
+            .expect_find_all_by_user_id()
+            .returning(|_, user_id| Ok(vec![Tag::new(user_id, "Jazz".to_string())]));
+        let mut mock_collection_token_repo = MockCollectionTokenRepo::new();
+        mock_collection_token_repo
+            .expect_find_by_user_id()
+            .returning(|_, _| Err(DbRepoError::SqlxError(sqlx::Error::RowNotFound)));
+
+        let mut repos = create_repos_for_test();
+        repos.user = Box::new(mock_user_repo);
+        repos.record = Box::new(mock_record_repo);
+        repos.tag = Box::new(mock_tag_repo);
+        repos.collection_token = Box::new(mock_collection_token_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let backup_use_case = BackupUseCaseImpl::new();
+        let backup = backup_use_case.export(&repos, &mut db_con, 1).await.unwrap();
+
+        assert_eq!(backup.version, BACKUP_VERSION);
+        assert_eq!(backup.profile.password_hash, "password");
+        assert_eq!(backup.records.len(), 3);
+        assert_eq!(backup.tags.len(), 1);
+        assert!(backup.collection_token.is_none());
+
+        // Both encodings can be read back
+        for gzip in [false, true] {
+            let decoded = AccountBackup::decode(&backup.encode(gzip).unwrap()).unwrap();
+            assert_eq!(decoded.records.len(), 3);
+        }
+    }
+
+    #[rocket::async_test]
+    async fn test_restore_replaces_existing_collection() {
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_find_by_id()
+            .returning(|_, id| Ok(Some(user_fixture(id as usize))));
+        mock_user_repo.expect_create().never();
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_delete_all_by_user_id()
+            .times(1)
+            .returning(|_, _| Ok(5));
+        mock_record_repo
+            .expect_create_multiple()
+            .times(1)
+            .withf(|_, user_id, inputs| {
+                *user_id == 7 && inputs.len() == 2 && inputs[0].created_at.as_deref() == Some("2021-01-01 00:00:00")
+            })
+            .returning(|_, _, inputs| Ok((1..=inputs.len()).map(record_fixture).collect()));
+        let mut mock_tag_repo = MockTagRepo::new();
+        mock_tag_repo
+            .expect_find_or_create()
+            .returning(|_, user_id, name| Ok(Tag::new(user_id, name.to_string())));
+        let mut mock_collection_token_repo = MockCollectionTokenRepo::new();
+        mock_collection_token_repo
+            .expect_delete_all_by_user_id()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock_collection_token_repo
+            .expect_find_by_token()
+            .returning(|_, _| Ok(None));
+        mock_collection_token_repo
+            .expect_create_with_token()
+            .withf(|_, user_id, token| *user_id == 7 && token == "shared-token")
+            .returning(|_, user_id, token| {
+                Ok(CollectionToken {
+                    id: 1,
+                    token: token.to_string(),
+                    user_id,
+                    created_at: chrono::NaiveDateTime::default(),
+                })
+            });
+
+        let mut repos = create_repos_for_test();
+        repos.user = Box::new(mock_user_repo);
+        repos.record = Box::new(mock_record_repo);
+        repos.tag = Box::new(mock_tag_repo);
+        repos.collection_token = Box::new(mock_collection_token_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let backup_use_case = BackupUseCaseImpl::new();
+        let report = backup_use_case
+            .restore(&repos, &mut db_con, Some(7), backup_fixture(BACKUP_VERSION))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            report,
+            RestoreReport {
+                user_id: 7,
+                records: 2,
+                tags: 1,
+                collection_token: Some("shared-token".to_string()),
+            }
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_tag_tree_round_trip() {
+        // Electronic > House > Deep House, ids differ between the two instances
+        let tree = [(1, "genre:Electronic", None), (2, "style:House", Some(1)), (3, "style:Deep House", Some(2))];
+        let mut mock_tag_repo = MockTagRepo::new();
+        mock_tag_repo.expect_find_all_by_user_id().returning(move |_, user_id| {
+            Ok(tree
+                .iter()
+                .map(|&(id, name, parent_id)| Tag { id, parent_id, ..Tag::new(user_id, name.to_string()) })
+                .collect())
+        });
+        mock_tag_repo
+            .expect_find_or_create()
+            .returning(|_, user_id, name| {
+                let id = match Tag::slug_of(name).as_str() {
+                    "electronic" => 11,
+                    "house" => 12,
+                    _ => 13,
+                };
+                Ok(Tag { id, ..Tag::new(user_id, name.to_string()) })
+            });
+        mock_tag_repo
+            .expect_set_parent()
+            .times(1)
+            .withf(|_, id, parent_id| *id == 12 && *parent_id == Some(11))
+            .returning(|_, id, parent_id| Ok(Tag { id, parent_id, ..Tag::new(7, "House".to_string()) }));
+        mock_tag_repo
+            .expect_set_parent()
+            .times(1)
+            .withf(|_, id, parent_id| *id == 13 && *parent_id == Some(12))
+            .returning(|_, id, parent_id| Ok(Tag { id, parent_id, ..Tag::new(7, "Deep House".to_string()) }));
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_find_by_id()
+            .returning(|_, id| Ok(Some(user_fixture(id as usize))));
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_user_id()
+            .returning(|_, _, _, _| Ok(RecordPage { total: 0, next_cursor: None, items: Vec::new() }));
+        mock_record_repo.expect_delete_all_by_user_id().returning(|_, _| Ok(0));
+        mock_record_repo.expect_create_multiple().never();
+        let mut mock_collection_token_repo = MockCollectionTokenRepo::new();
+        mock_collection_token_repo
+            .expect_find_by_user_id()
+            .returning(|_, _| Err(DbRepoError::SqlxError(sqlx::Error::RowNotFound)));
+        mock_collection_token_repo
+            .expect_delete_all_by_user_id()
+            .returning(|_, _| Ok(()));
+
+        let mut repos = create_repos_for_test();
+        repos.user = Box::new(mock_user_repo);
+        repos.record = Box::new(mock_record_repo);
+        repos.tag = Box::new(mock_tag_repo);
+        repos.collection_token = Box::new(mock_collection_token_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let backup_use_case = BackupUseCaseImpl::new();
+
+        let backup = backup_use_case.export(&repos, &mut db_con, 1).await.unwrap();
+        let backup = AccountBackup::decode(&backup.encode(true).unwrap()).unwrap();
+        let parents: Vec<Option<&str>> = backup.tags.iter().map(|tag| tag.parent.as_deref()).collect();
+        assert_eq!(parents, [None, Some("electronic"), Some("house")]);
+        assert_eq!(backup.tags[1].tag.kind, "style");
+
+        let report = backup_use_case.restore(&repos, &mut db_con, Some(7), backup).await.unwrap();
+        assert_eq!(report.tags, 3);
+    }
+
+    #[test]
+    fn test_decode_version_1_tags() {
+        let json = r#"{"version":1,"exported_at":"2025-01-01T00:00:00","profile":{"email":"a@mail.com",
+            "username":"a","password_hash":"hash","created_at":"2025-01-01T00:00:00"},
+            "tags":[{"name":"Jazz","slug":"jazz"}],"records":[],"collection_token":null}"#;
+        let backup = AccountBackup::decode(json.as_bytes()).unwrap();
+        assert_eq!(backup.tags[0].tag.qualified_name(), "Jazz");
+        assert!(backup.tags[0].parent.is_none());
+    }
+
+    #[rocket::async_test]
+    async fn test_restore_refuses_unknown_version_and_taken_email() {
+        let mut repos = create_repos_for_test();
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let backup_use_case = BackupUseCaseImpl::new();
+        let result = backup_use_case
+            .restore(&repos, &mut db_con, None, backup_fixture(BACKUP_VERSION + 1))
+            .await;
+        assert!(matches!(result, Err(AppError::CustomError { status_code: 400, .. })));
+
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_find_by_email()
+            .returning(|_, _| Ok(Some(user_fixture(2))));
+        mock_user_repo.expect_create().never();
+        repos.user = Box::new(mock_user_repo);
+        let result = backup_use_case
+            .restore(&repos, &mut db_con, None, backup_fixture(BACKUP_VERSION))
+            .await;
+        assert!(matches!(result, Err(AppError::CustomError { status_code: 400, .. })));
+    }
+
+    #[rocket::async_test]
+    async fn test_restore_creates_account() {
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo.expect_find_by_email().returning(|_, _| Ok(None));
+        mock_user_repo.expect_find_by_username().returning(|_, _| Ok(None));
+        mock_user_repo
+            .expect_create()
+            .times(1)
+            .withf(|_, email, username, password| {
+                email == "restored@mail.com" && username == "restored" && password.starts_with("$2b$")
+            })
+            .returning(|_, _, _, _| Ok(user_fixture(9)));
+        mock_user_repo
+            .expect_set_created_at()
+            .times(1)
+            .withf(|_, id, created_at| *id == 9 && created_at.to_string() == "2020-05-01 08:00:00")
+            .returning(|_, _, _| Ok(()));
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_create_multiple()
+            .returning(|_, _, inputs| Ok((1..=inputs.len()).map(record_fixture).collect()));
+        let mut mock_tag_repo = MockTagRepo::new();
+        mock_tag_repo
+            .expect_find_or_create()
+            .returning(|_, user_id, name| Ok(Tag::new(user_id, name.to_string())));
+        let mut mock_collection_token_repo = MockCollectionTokenRepo::new();
+        mock_collection_token_repo
+            .expect_find_by_token()
+            .returning(|_, _| Ok(None));
+        mock_collection_token_repo
+            .expect_create_with_token()
+            .returning(|_, user_id, token| {
+                Ok(CollectionToken {
+                    id: 1,
+                    token: token.to_string(),
+                    user_id,
+                    created_at: chrono::NaiveDateTime::default(),
+                })
+            });
+
+        let mut repos = create_repos_for_test();
+        repos.user = Box::new(mock_user_repo);
+        repos.record = Box::new(mock_record_repo);
+        repos.tag = Box::new(mock_tag_repo);
+        repos.collection_token = Box::new(mock_collection_token_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let backup_use_case = BackupUseCaseImpl::new();
+        let report = backup_use_case
+            .restore(&repos, &mut db_con, None, backup_fixture(BACKUP_VERSION))
+            .await
+            .unwrap();
+        assert_eq!((report.user_id, report.records), (9, 2));
+    }
+
+    #[rocket::async_test]
+    async fn test_restore_validates_new_profile() {
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo.expect_create().never();
+        let mut repos = create_repos_for_test();
+        repos.user = Box::new(mock_user_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let backup_use_case = BackupUseCaseImpl::new();
+
+        let profiles: [fn(&mut BackupProfile); 3] = [
+            |profile| profile.email = "not an email".to_string(),
+            |profile| profile.username = "no spaces".to_string(),
+            // Login could not verify a password against it
+            |profile| profile.password_hash = "plain password".to_string(),
+        ];
+        for change in profiles {
+            let mut backup = backup_fixture(BACKUP_VERSION);
+            change(&mut backup.profile);
+            let result = backup_use_case.restore(&repos, &mut db_con, None, backup).await;
+            assert!(matches!(result, Err(AppError::ValidationError { .. })));
+        }
+    }
+}
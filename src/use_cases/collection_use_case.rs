@@ -2,7 +2,9 @@
 use crate::db::ConnectionDb;
 use crate::error::app_error::AppError;
 use crate::models::collection_model::CollectionToken;
-use crate::models::record_model::Record;
+use crate::app_err_ensure;
+use crate::dto::record_dto::{RecordFilter, RecordPagination};
+use crate::models::record_model::RecordPage;
 use crate::repositories::repositories::Repositories;
 use mockall::automock;
 use tracing::instrument;
@@ -49,9 +51,9 @@ pub trait CollectionUseCase: Send + Sync {
         repos: &Repositories, 
         db: &mut ConnectionDb, 
         token: &str,
-        owned: Option<bool>,
-        wanted: Option<bool>
-    ) -> Result<Vec<Record>, AppError>;
+        filter: RecordFilter,
+        pagination: RecordPagination
+    ) -> Result<RecordPage, AppError>;
 
     /// Get user_id associated with a token
     async fn get_user_id_by_token(
@@ -134,9 +136,11 @@ impl CollectionUseCase for CollectionUseCaseImpl {
         repos: &Repositories, 
         db: &mut ConnectionDb, 
         token: &str,
-        owned: Option<bool>,
-        wanted: Option<bool>
-    ) -> Result<Vec<Record>, AppError> {
+        filter: RecordFilter,
+        pagination: RecordPagination
+    ) -> Result<RecordPage, AppError> {
+        app_err_ensure!(pagination.is_valid(), 400, "Invalid pagination cursor");
+
         // Find the token to get the user_id
         let token_opt = repos
             .collection_token
@@ -146,14 +150,14 @@ impl CollectionUseCase for CollectionUseCaseImpl {
             
         let user_token = token_opt.ok_or(AppError::NotFound)?;
         
-        // Get the user's collection
-        let records = repos
+        // Get a page of the user's collection
+        let page = repos
             .record
-            .find_all_by_user_id(&mut **db, user_token.user_id, owned, wanted)
+            .find_all_by_user_id(&mut **db, user_token.user_id, &filter, &pagination)
             .await
             .map_err(|e| AppError::from(e))?;
 
-        Ok(records)
+        Ok(page)
     }
 
     #[instrument(name = "collection_use_case/get_user_id_by_token", skip_all)]
@@ -0,0 +1,484 @@
+use crate::db::DbCon;
+use crate::dto::enrichment_dto::{EnrichmentError, EnrichmentReport};
+use crate::dto::metadata_dto::ProviderAlbum;
+use crate::dto::record_dto::{RecordInput, PLACEHOLDER_COVER_PREFIX};
+use crate::error::app_error::AppError;
+use crate::models::enrichment_job_model::EnrichmentJob;
+use crate::models::import_job_model::ImportJobStatus;
+use crate::models::record_model::Record;
+use crate::models::tag_model::Tag;
+use crate::providers::album_matcher;
+use crate::providers::discogs_provider::parse_discogs_url;
+use crate::providers::providers::Providers;
+use crate::repositories::repositories::Repositories;
+use crate::app_err_ensure;
+use chrono::{Datelike, NaiveDate};
+use mockall::automock;
+use tracing::instrument;
+
+pub struct EnrichmentJobUseCaseImpl {}
+
+impl EnrichmentJobUseCaseImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait EnrichmentJobUseCase: Send + Sync {
+    /// Queue the lookups of the user's records missing a cover, a Spotify link or tags
+    async fn create(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        providers: &Providers,
+        user_id: i32,
+    ) -> Result<EnrichmentJob, AppError>;
+
+    /// Get an enrichment job of the given user
+    async fn find_by_id(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+    ) -> Result<EnrichmentJob, AppError>;
+
+    /// Process the oldest pending job chunk by chunk, returns `None` when the queue is empty
+    async fn process_next(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        providers: &Providers,
+        chunk_size: usize,
+    ) -> Result<Option<EnrichmentJob>, AppError>;
+
+    /// Queue again the jobs interrupted by a restart, they resume after their processed records
+    async fn resume_interrupted(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+    ) -> Result<u64, AppError>;
+}
+
+/// Outcome of the lookup of a single record
+enum Enrichment {
+    Enriched,
+    Unchanged,
+    Failed(String),
+}
+
+/// Fills in what the providers know and the record misses: a real cover, the exact
+/// release date when only the year is known, the Discogs genres, styles and label as tags
+/// and the Spotify link of the matching album
+fn enrich_input(record: Record, album: &ProviderAlbum, spotify: Option<&ProviderAlbum>) -> Option<RecordInput> {
+    let mut input = RecordInput::from(record);
+    let mut changed = false;
+
+    if input.cover_url.starts_with(PLACEHOLDER_COVER_PREFIX) {
+        let cover_url = album
+            .cover_url
+            .clone()
+            .or(spotify.and_then(|spotify| spotify.cover_url.clone()));
+        if let Some(cover_url) = cover_url {
+            input.cover_url = cover_url;
+            changed = true;
+        }
+    }
+
+    // Imports only know the year, which is stored as the first of January
+    let release_date = NaiveDate::parse_from_str(&input.release_date, "%Y-%m-%d").unwrap_or_default();
+    if release_date.ordinal() == 1 && album.release_date != NaiveDate::default() && album.release_date != release_date {
+        input.release_date = album.release_date.format("%Y-%m-%d").to_string();
+        changed = true;
+    }
+
+    let tags = input.tags.get_or_insert_with(Vec::new);
+    for tag in album.tags() {
+        let slug = Tag::slug_of(&tag);
+        if !tags.iter().any(|name| Tag::slug_of(name) == slug) {
+            tags.push(tag);
+            changed = true;
+        }
+    }
+
+    if input.spotify_url.is_none() {
+        if let Some(spotify_url) = spotify.and_then(|spotify| spotify.spotify_url.clone()) {
+            input.spotify_url = Some(spotify_url);
+            changed = true;
+        }
+    }
+
+    changed.then_some(input)
+}
+
+/// Looks a record up on the providers and updates it with what they know, along with
+/// the Spotify error when only the Discogs data could be applied.
+/// Provider errors only fail the record, database errors fail the job
+async fn enrich_record(
+    repos: &Repositories,
+    db_con: &mut DbCon,
+    providers: &Providers,
+    user_id: i32,
+    id: i32,
+) -> Result<(Enrichment, Option<String>), AppError> {
+    // Records deleted since the job was queued are left out
+    let Some(record) = repos.record.find_by_id(&mut *db_con, id).await? else {
+        return Ok((Enrichment::Unchanged, None));
+    };
+    if record.user_id != user_id {
+        return Ok((Enrichment::Unchanged, None));
+    }
+
+    let Some(release_id) = record.discogs_url.as_deref().and_then(parse_discogs_url) else {
+        return Ok((Enrichment::Failed("Unrecognized Discogs url".to_string()), None));
+    };
+    let album = match providers.discogs.release(&release_id).await {
+        Ok(Some(album)) => album,
+        Ok(None) => return Ok((Enrichment::Failed("Not found on Discogs".to_string()), None)),
+        Err(e) => return Ok((Enrichment::Failed(e.to_string()), None)),
+    };
+
+    // Spotify only adds its link, the Discogs data is applied without it
+    let mut spotify_error = None;
+    let spotify = match record.spotify_url.is_none() && providers.spotify.is_enabled() {
+        true => {
+            let query = format!("{} {}", album.artist, album.title);
+            match providers.spotify.search(&query).await {
+                Ok(candidates) => {
+                    let matched = album_matcher::match_albums(std::slice::from_ref(&album), &candidates);
+                    matched[0].0.map(|index| candidates[index].clone())
+                }
+                Err(e) => {
+                    spotify_error = Some(format!("Spotify link not looked up: {}", e));
+                    None
+                }
+            }
+        }
+        false => None,
+    };
+
+    let Some(input) = enrich_input(record, &album, spotify.as_ref()) else {
+        return Ok((Enrichment::Unchanged, spotify_error));
+    };
+    repos.record.update(&mut *db_con, id, input).await?;
+    Ok((Enrichment::Enriched, spotify_error))
+}
+
+#[async_trait]
+impl EnrichmentJobUseCase for EnrichmentJobUseCaseImpl {
+    #[instrument(name = "enrichment_job_use_case/create", skip_all, fields(user_id = %user_id))]
+    async fn create(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        providers: &Providers,
+        user_id: i32,
+    ) -> Result<EnrichmentJob, AppError> {
+        app_err_ensure!(providers.discogs.is_enabled(), 503, "Discogs is not configured");
+
+        let record_ids = repos.record.find_ids_to_enrich(&mut *db_con, user_id).await?;
+        let job = repos.enrichment_job.create(&mut *db_con, user_id, &record_ids).await?;
+        Ok(job)
+    }
+
+    #[instrument(name = "enrichment_job_use_case/find_by_id", skip_all, fields(id = %id))]
+    async fn find_by_id(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+    ) -> Result<EnrichmentJob, AppError> {
+        let job = repos
+            .enrichment_job
+            .find_by_id(&mut *db_con, id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+
+        if job.user_id != user_id {
+            return Err(AppError::Unauthorized);
+        }
+
+        Ok(job)
+    }
+
+    #[instrument(name = "enrichment_job_use_case/process_next", skip_all)]
+    async fn process_next(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        providers: &Providers,
+        chunk_size: usize,
+    ) -> Result<Option<EnrichmentJob>, AppError> {
+        let Some(mut job) = repos.enrichment_job.claim_next(&mut *db_con).await? else {
+            return Ok(None);
+        };
+        tracing::info!("Processing enrichment job {} ({} records)", job.id, job.total_records);
+
+        let record_ids = repos.enrichment_job.find_record_ids(&mut *db_con, job.id).await?;
+
+        // Skip the records already handled before an interruption
+        let mut remaining = record_ids.into_iter().skip(job.processed_records as usize).peekable();
+
+        while remaining.peek().is_some() {
+            let chunk: Vec<i32> = remaining.by_ref().take(chunk_size.max(1)).collect();
+
+            let mut report = EnrichmentReport::default();
+            for &id in &chunk {
+                match enrich_record(repos, db_con, providers, job.user_id, id).await {
+                    Ok((enrichment, spotify_error)) => {
+                        match enrichment {
+                            Enrichment::Enriched => report.enriched += 1,
+                            Enrichment::Unchanged => report.unchanged += 1,
+                            Enrichment::Failed(message) => {
+                                tracing::warn!("Record {} not enriched: {}", id, message);
+                                report.failed += 1;
+                                report.errors.push(EnrichmentError::new(id, &message));
+                            }
+                        }
+                        if let Some(message) = spotify_error {
+                            tracing::warn!("Record {} enriched without Spotify: {}", id, message);
+                            report.errors.push(EnrichmentError::new(id, &message));
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Enrichment job {} failed: {}", job.id, e);
+                        let failure = Some(e.to_string());
+                        let job = repos
+                            .enrichment_job
+                            .finish(&mut *db_con, job.id, ImportJobStatus::Failed, failure)
+                            .await?;
+                        return Ok(Some(job));
+                    }
+                }
+            }
+
+            job = repos
+                .enrichment_job
+                .record_progress(&mut *db_con, job.id, chunk.len() as i32, &report)
+                .await?;
+        }
+
+        let job = repos
+            .enrichment_job
+            .finish(&mut *db_con, job.id, ImportJobStatus::Completed, None)
+            .await?;
+        Ok(Some(job))
+    }
+
+    #[instrument(name = "enrichment_job_use_case/resume_interrupted", skip_all)]
+    async fn resume_interrupted(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+    ) -> Result<u64, AppError> {
+        let count = repos.enrichment_job.requeue_running(&mut *db_con).await?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::metadata_dto::{DiscogsKind, ReleaseId};
+    use crate::dto::record_dto::placeholder_cover_url;
+    use crate::providers::metadata_provider::MockMetadataProvider;
+    use crate::repositories::enrichment_job_repo::MockEnrichmentJobRepo;
+    use crate::repositories::record_repo::MockRecordRepo;
+    use crate::test::app::{create_providers_for_test, create_repos_for_test};
+    use crate::test::db::create_db_con_for_test;
+    use crate::test::fixture::record::record_fixture;
+    use sqlx::types::Json;
+
+    fn enrichment_job_fixture(status: ImportJobStatus, processed_records: i32) -> EnrichmentJob {
+        EnrichmentJob {
+            id: 1,
+            user_id: 1,
+            status: status.as_str().to_string(),
+            total_records: 3,
+            processed_records,
+            enriched_count: 0,
+            unchanged_count: 0,
+            failed_count: 0,
+            errors: Json(Vec::new()),
+            failure: None,
+            created_at: chrono::NaiveDateTime::default(),
+            updated_at: chrono::NaiveDateTime::default(),
+        }
+    }
+
+    /// An imported record, only its release year is known
+    fn imported_record(id: usize) -> Record {
+        let mut record = record_fixture(id);
+        record.cover_url = placeholder_cover_url(&record.artist, &record.title);
+        record.release_date = NaiveDate::from_ymd_opt(2001, 1, 1).unwrap();
+        record.discogs_url = Some(format!("https://www.discogs.com/release/{}", id));
+        record.spotify_url = None;
+        record
+    }
+
+    fn discogs_album() -> ProviderAlbum {
+        ProviderAlbum {
+            title: "title1".to_string(),
+            artist: "artist1".to_string(),
+            release_date: NaiveDate::from_ymd_opt(2001, 3, 12).unwrap(),
+            cover_url: Some("https://i.discogs.com/1.jpg".to_string()),
+            genres: vec!["Electronic".to_string(), "tag1-1".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_enrich_input() {
+        let spotify = ProviderAlbum {
+            spotify_url: Some("https://open.spotify.com/album/1".to_string()),
+            ..discogs_album()
+        };
+        let input = enrich_input(imported_record(1), &discogs_album(), Some(&spotify)).unwrap();
+        assert_eq!(input.cover_url, "https://i.discogs.com/1.jpg");
+        assert_eq!(input.release_date, "2001-03-12");
+        assert_eq!(input.spotify_url.as_deref(), Some("https://open.spotify.com/album/1"));
+        assert_eq!(
+            input.tags,
+            Some(vec!["tag1-1".to_string(), "tag1-2".to_string(), "genre:Electronic".to_string()])
+        );
+
+        // A complete record is left alone, its exact date included
+        let mut record = record_fixture(1);
+        record.release_date = NaiveDate::from_ymd_opt(2001, 3, 20).unwrap();
+        let album = ProviderAlbum {
+            genres: vec!["Tag1 1".to_string()],
+            ..discogs_album()
+        };
+        assert!(enrich_input(record, &album, None).is_none());
+    }
+
+    #[rocket::async_test]
+    async fn test_process_next_reports_each_record() {
+        let mut mock_enrichment_job_repo = MockEnrichmentJobRepo::new();
+        mock_enrichment_job_repo
+            .expect_claim_next()
+            .returning(|_| Ok(Some(enrichment_job_fixture(ImportJobStatus::Running, 0))));
+        mock_enrichment_job_repo
+            .expect_find_record_ids()
+            .returning(|_, _| Ok(vec![1, 2, 3]));
+        mock_enrichment_job_repo
+            .expect_record_progress()
+            .times(1)
+            .withf(|_, _, processed_records, report| {
+                *processed_records == 3
+                    && report.enriched == 1
+                    && report.unchanged == 1
+                    && report.failed == 1
+                    && report.errors == vec![EnrichmentError::new(2, "Not found on Discogs")]
+            })
+            .returning(|_, _, _, _| Ok(enrichment_job_fixture(ImportJobStatus::Running, 3)));
+        mock_enrichment_job_repo
+            .expect_finish()
+            .withf(|_, _, status, failure| *status == ImportJobStatus::Completed && failure.is_none())
+            .returning(|_, _, status, _| Ok(enrichment_job_fixture(status, 3)));
+
+        // Record 3 no longer exists
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_by_id()
+            .returning(|_, id| Ok((id < 3).then(|| imported_record(id as usize))));
+        mock_record_repo
+            .expect_update()
+            .times(1)
+            .withf(|_, id, input| *id == 1 && input.spotify_url.as_deref() == Some("https://open.spotify.com/album/1"))
+            .returning(|_, id, _| Ok(record_fixture(id as usize)));
+
+        let mut mock_discogs = MockMetadataProvider::new();
+        mock_discogs.expect_release().returning(|id| {
+            let found = *id == ReleaseId::Discogs { kind: DiscogsKind::Release, id: 1 };
+            Ok(found.then(discogs_album))
+        });
+        let mut mock_spotify = MockMetadataProvider::new();
+        mock_spotify.expect_is_enabled().return_const(true);
+        mock_spotify.expect_search().returning(|_| {
+            Ok(vec![ProviderAlbum {
+                spotify_url: Some("https://open.spotify.com/album/1".to_string()),
+                ..discogs_album()
+            }])
+        });
+        let mut providers = create_providers_for_test();
+        providers.discogs = Box::new(mock_discogs);
+        providers.spotify = Box::new(mock_spotify);
+
+        let mut repos = create_repos_for_test();
+        repos.enrichment_job = Box::new(mock_enrichment_job_repo);
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let enrichment_job_use_case = EnrichmentJobUseCaseImpl::new();
+        let job = enrichment_job_use_case
+            .process_next(&repos, &mut db_con, &providers, 10)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(job.status, "completed");
+    }
+
+    #[rocket::async_test]
+    async fn test_process_next_keeps_discogs_data_when_spotify_fails() {
+        let mut mock_enrichment_job_repo = MockEnrichmentJobRepo::new();
+        mock_enrichment_job_repo
+            .expect_claim_next()
+            .returning(|_| Ok(Some(enrichment_job_fixture(ImportJobStatus::Running, 0))));
+        mock_enrichment_job_repo
+            .expect_find_record_ids()
+            .returning(|_, _| Ok(vec![1]));
+        mock_enrichment_job_repo
+            .expect_record_progress()
+            .times(1)
+            .withf(|_, _, _, report| {
+                report.enriched == 1
+                    && report.failed == 0
+                    && report.errors
+                        == vec![EnrichmentError::new(1, "Spotify link not looked up: Spotify is unavailable")]
+            })
+            .returning(|_, _, _, _| Ok(enrichment_job_fixture(ImportJobStatus::Running, 1)));
+        mock_enrichment_job_repo
+            .expect_finish()
+            .returning(|_, _, status, _| Ok(enrichment_job_fixture(status, 1)));
+
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_by_id()
+            .returning(|_, id| Ok(Some(imported_record(id as usize))));
+        mock_record_repo
+            .expect_update()
+            .times(1)
+            .withf(|_, _, input| {
+                input.cover_url == "https://i.discogs.com/1.jpg"
+                    && input.release_date == "2001-03-12"
+                    && input.spotify_url.is_none()
+            })
+            .returning(|_, id, _| Ok(record_fixture(id as usize)));
+
+        let mut mock_discogs = MockMetadataProvider::new();
+        mock_discogs.expect_release().returning(|_| Ok(Some(discogs_album())));
+        let mut mock_spotify = MockMetadataProvider::new();
+        mock_spotify.expect_is_enabled().return_const(true);
+        mock_spotify
+            .expect_search()
+            .returning(|_| Err(AppError::UpstreamUnavailable { provider: "Spotify" }));
+        let mut providers = create_providers_for_test();
+        providers.discogs = Box::new(mock_discogs);
+        providers.spotify = Box::new(mock_spotify);
+
+        let mut repos = create_repos_for_test();
+        repos.enrichment_job = Box::new(mock_enrichment_job_repo);
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let enrichment_job_use_case = EnrichmentJobUseCaseImpl::new();
+        let job = enrichment_job_use_case
+            .process_next(&repos, &mut db_con, &providers, 10)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(job.status, "completed");
+    }
+}
@@ -0,0 +1,262 @@
+use crate::db::DbCon;
+use crate::dto::import_dto::{ImportMode, ImportRow};
+use crate::error::app_error::AppError;
+use crate::models::import_job_model::{ImportJob, ImportJobStatus};
+use crate::repositories::repositories::Repositories;
+use crate::use_cases::record_use_case::import_rows;
+use crate::app_err_ensure;
+use mockall::automock;
+use tracing::instrument;
+
+pub struct ImportJobUseCaseImpl {}
+
+impl ImportJobUseCaseImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait ImportJobUseCase: Send + Sync {
+    /// Queue an import to be processed in the background
+    async fn create(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        rows: Vec<ImportRow>,
+        mode: ImportMode,
+    ) -> Result<ImportJob, AppError>;
+
+    /// Get an import job of the given user
+    async fn find_by_id(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+    ) -> Result<ImportJob, AppError>;
+
+    /// Process the oldest pending job chunk by chunk, returns `None` when the queue is empty
+    async fn process_next(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        chunk_size: usize,
+    ) -> Result<Option<ImportJob>, AppError>;
+
+    /// Queue again the jobs interrupted by a restart, they resume after their processed rows
+    async fn resume_interrupted(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+    ) -> Result<u64, AppError>;
+}
+
+#[async_trait]
+impl ImportJobUseCase for ImportJobUseCaseImpl {
+    #[instrument(name = "import_job_use_case/create", skip_all, fields(user_id = %user_id))]
+    async fn create(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        rows: Vec<ImportRow>,
+        mode: ImportMode,
+    ) -> Result<ImportJob, AppError> {
+        app_err_ensure!(!rows.is_empty(), 400, "No records found in the CSV file");
+
+        let job = repos.import_job.create(&mut *db_con, user_id, mode, &rows).await?;
+        Ok(job)
+    }
+
+    #[instrument(name = "import_job_use_case/find_by_id", skip_all, fields(id = %id))]
+    async fn find_by_id(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+    ) -> Result<ImportJob, AppError> {
+        let job = repos
+            .import_job
+            .find_by_id(&mut *db_con, id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+
+        if job.user_id != user_id {
+            return Err(AppError::Unauthorized);
+        }
+
+        Ok(job)
+    }
+
+    #[instrument(name = "import_job_use_case/process_next", skip_all)]
+    async fn process_next(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        chunk_size: usize,
+    ) -> Result<Option<ImportJob>, AppError> {
+        let Some(mut job) = repos.import_job.claim_next(&mut *db_con).await? else {
+            return Ok(None);
+        };
+        tracing::info!("Processing import job {} ({} rows)", job.id, job.total_rows);
+
+        let mode = ImportMode::parse(&job.mode).unwrap_or_default();
+        let rows = repos.import_job.find_rows(&mut *db_con, job.id).await?;
+
+        // Skip the rows already handled before an interruption
+        let mut remaining = rows.into_iter().skip(job.processed_rows as usize).peekable();
+
+        while remaining.peek().is_some() {
+            let chunk: Vec<ImportRow> = remaining.by_ref().take(chunk_size.max(1)).collect();
+            let processed_rows = chunk.len() as i32;
+
+            match import_rows(repos, db_con, job.user_id, chunk, mode).await {
+                Ok(report) => {
+                    job = repos
+                        .import_job
+                        .record_progress(&mut *db_con, job.id, processed_rows, &report)
+                        .await?;
+                }
+                Err(e) => {
+                    tracing::error!("Import job {} failed: {}", job.id, e);
+                    let failure = Some(e.to_string());
+                    let job = repos
+                        .import_job
+                        .finish(&mut *db_con, job.id, ImportJobStatus::Failed, failure)
+                        .await?;
+                    return Ok(Some(job));
+                }
+            }
+        }
+
+        let job = repos
+            .import_job
+            .finish(&mut *db_con, job.id, ImportJobStatus::Completed, None)
+            .await?;
+        Ok(Some(job))
+    }
+
+    #[instrument(name = "import_job_use_case/resume_interrupted", skip_all)]
+    async fn resume_interrupted(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+    ) -> Result<u64, AppError> {
+        let count = repos.import_job.requeue_running(&mut *db_con).await?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::import_dto::ImportRowError;
+    use crate::repositories::import_job_repo::MockImportJobRepo;
+    use crate::repositories::record_repo::MockRecordRepo;
+    use crate::test::app::create_repos_for_test;
+    use crate::test::db::create_db_con_for_test;
+    use crate::test::fixture::record::{record_fixture, record_input_fixture};
+    use mockall::Sequence;
+    use sqlx::types::Json;
+
+    fn import_job_fixture(status: ImportJobStatus, processed_rows: i32) -> ImportJob {
+        ImportJob {
+            id: 1,
+            user_id: 1,
+            status: status.as_str().to_string(),
+            mode: "create".to_string(),
+            total_rows: 3,
+            processed_rows,
+            created_count: 0,
+            updated_count: 0,
+            unchanged_count: 0,
+            skipped_count: 0,
+            errors: Json(Vec::new()),
+            failure: None,
+            created_at: chrono::NaiveDateTime::default(),
+            updated_at: chrono::NaiveDateTime::default(),
+        }
+    }
+
+    #[rocket::async_test]
+    async fn test_process_next_resumes_in_chunks() {
+        let mut mock_import_job_repo = MockImportJobRepo::new();
+        mock_import_job_repo
+            .expect_claim_next()
+            .returning(|_| Ok(Some(import_job_fixture(ImportJobStatus::Running, 1))));
+        mock_import_job_repo.expect_find_rows().returning(|_, _| {
+            Ok((1..=3)
+                .map(|row| ImportRow { row, record: Ok(record_input_fixture(row)) })
+                .collect())
+        });
+        mock_import_job_repo
+            .expect_record_progress()
+            .times(2)
+            .withf(|_, _, processed_rows, report| *processed_rows == 1 && report.created == 1)
+            .returning(|_, _, _, _| Ok(import_job_fixture(ImportJobStatus::Running, 2)));
+        mock_import_job_repo
+            .expect_finish()
+            .withf(|_, _, status, failure| *status == ImportJobStatus::Completed && failure.is_none())
+            .returning(|_, _, status, _| Ok(import_job_fixture(status, 3)));
+
+        // The row processed before the interruption is not imported again
+        let mut seq = Sequence::new();
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_discogs_urls()
+            .returning(|_, _, _| Ok(Vec::new()));
+        for title in ["title2", "title3"] {
+            mock_record_repo
+                .expect_create_multiple()
+                .times(1)
+                .in_sequence(&mut seq)
+                .withf(move |_, _, inputs| inputs.len() == 1 && inputs[0].title == title)
+                .returning(|_, _, _| Ok(vec![record_fixture(1)]));
+        }
+
+        let mut repos = create_repos_for_test();
+        repos.import_job = Box::new(mock_import_job_repo);
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let import_job_use_case = ImportJobUseCaseImpl::new();
+        let job = import_job_use_case
+            .process_next(&repos, &mut db_con, 1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(job.status, "completed");
+    }
+
+    #[rocket::async_test]
+    async fn test_create_rejects_empty_import() {
+        let mut mock_import_job_repo = MockImportJobRepo::new();
+        mock_import_job_repo.expect_create().never();
+        let mut repos = create_repos_for_test();
+        repos.import_job = Box::new(mock_import_job_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let import_job_use_case = ImportJobUseCaseImpl::new();
+        let result = import_job_use_case
+            .create(&repos, &mut db_con, 1, Vec::new(), ImportMode::Create)
+            .await;
+        assert!(matches!(result, Err(AppError::CustomError { status_code: 400, .. })));
+
+        let rows = vec![ImportRow {
+            row: 1,
+            record: Err(vec![ImportRowError::new(1, "csv", "Not enough fields")]),
+        }];
+        let mut mock_import_job_repo = MockImportJobRepo::new();
+        mock_import_job_repo
+            .expect_create()
+            .returning(|_, _, _, _| Ok(import_job_fixture(ImportJobStatus::Pending, 0)));
+        repos.import_job = Box::new(mock_import_job_repo);
+        let job = import_job_use_case
+            .create(&repos, &mut db_con, 1, rows, ImportMode::Create)
+            .await
+            .unwrap();
+        assert_eq!(job.status, "pending");
+    }
+}
@@ -2,4 +2,8 @@ pub mod auth_use_case;
 pub mod record_use_case;
 pub mod user_use_case;
 pub mod collection_use_case;
+pub mod import_job_use_case;
+pub mod enrichment_job_use_case;
+pub mod backup_use_case;
+pub mod tag_use_case;
 pub mod use_cases;
\ No newline at end of file
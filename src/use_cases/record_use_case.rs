@@ -1,16 +1,30 @@
-use std::env;
+use std::collections::{HashMap, HashSet};
 
-use crate::db::DbCon;
-use crate::dto::discogs_dto::DiscogsRoot;
-use crate::dto::record_dto::RecordInput;
-use crate::dto::spotify_dto::{SpotifyAccessTokenRoot, SpotifyRoot};
+use crate::db::{CacheCon, DbCon};
+use crate::dto::import_dto::{
+    ImportConflict, ImportMode, ImportPreview, ImportPreviewRow, ImportReport, ImportRow,
+    ImportRowError, ImportRowStatus,
+};
+use crate::dto::record_dto::{
+    placeholder_cover_url, RecordFilter, RecordInput, RecordPagination, RecordPatchInput,
+};
+use crate::dto::metadata_dto::{ProviderAlbum, ReleaseCode, ReleaseId};
 use crate::error::app_error::AppError;
-use crate::models::record_model::Record;
+use crate::models::record_model::{Record, RecordPage, SearchResults};
+use crate::models::tag_model::{Tag, TagResponse};
+use crate::repositories::error::DbRepoError;
+use crate::providers::album_matcher;
+use crate::providers::metadata_provider::MetadataProvider;
+use crate::providers::providers::Providers;
 use crate::repositories::repositories::Repositories;
-use base64::Engine;
-use chrono::DateTime;
+use crate::use_cases::tag_use_case::find_user_tag;
+use crate::{app_err_ensure, log_into};
+use chrono::{Datelike, NaiveDate};
 use mockall::automock;
+use rocket::futures::future::{join3, join_all};
+use sqlx::Connection;
 use tracing::instrument;
+use validator::Validate;
 
 pub struct RecordUseCaseImpl {}
 
@@ -37,6 +51,32 @@ pub trait RecordUseCase: Send + Sync {
         user_id: i32,
         records: Vec<RecordInput>,
     ) -> Result<Vec<Record>, AppError>;
+    /// Creates a record from the details of a Discogs release or master,
+    /// owned unless `wanted` is set
+    async fn create_from_discogs(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        providers: &Providers,
+        user_id: i32,
+        release_id: ReleaseId,
+        wanted: bool,
+    ) -> Result<Record, AppError>;
+    async fn import(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        rows: Vec<ImportRow>,
+        mode: ImportMode,
+    ) -> Result<ImportReport, AppError>;
+    async fn preview_import(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        rows: Vec<ImportRow>,
+    ) -> Result<ImportPreview, AppError>;
 
     async fn find_all(&self, repos: &Repositories, db_con: &mut DbCon) -> Result<Vec<Record>, AppError>;
     async fn find_by_id(
@@ -50,19 +90,461 @@ pub trait RecordUseCase: Send + Sync {
         repos: &Repositories,
         db_con: &mut DbCon,
         user_id: i32,
-        owned: Option<bool>,
-        wanted: Option<bool>,
-    ) -> Result<Vec<Record>, AppError>;
+        filter: RecordFilter,
+        pagination: RecordPagination,
+    ) -> Result<RecordPage, AppError>;
     async fn get_random_by_user_id(
         &self,
         repos: &Repositories,
         db_con: &mut DbCon,
         user_id: i32,
-        owned: Option<bool>,
-        wanted: Option<bool>,
+        filter: RecordFilter,
     ) -> Result<Option<Record>, AppError>;
 
-    async fn search(&self, query: &String) -> Result<Vec<Record>, AppError>;
+    async fn update(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+        record: RecordInput,
+    ) -> Result<Record, AppError>;
+    async fn patch(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+        patch: RecordPatchInput,
+    ) -> Result<Record, AppError>;
+    async fn delete(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+    ) -> Result<(), AppError>;
+
+    /// Adds tags to a record, the user's tags are reused by slug and the others created
+    async fn add_tags(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+        tags: Vec<String>,
+    ) -> Result<Record, AppError>;
+
+    /// Removes a tag from a record, the tag itself is kept
+    async fn remove_tag(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+        slug: &str,
+    ) -> Result<Record, AppError>;
+
+    /// Search the metadata providers, Discogs results are completed with Spotify ones.
+    /// Results are cached for `cache_ttl` seconds when a cache connection is given,
+    /// then the ones already in the user's records get their id and flags
+    #[allow(clippy::too_many_arguments)]
+    async fn search(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        cache: &mut Option<CacheCon>,
+        providers: &Providers,
+        user_id: i32,
+        query: &String,
+        cache_ttl: u64,
+    ) -> Result<SearchResults, AppError>;
+
+    /// Records carrying a barcode or a catalog number. The user's records with the catalog number
+    /// come first, Discogs is only asked when there are none, its releases already in the user's
+    /// records get their id and flags
+    async fn lookup(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        providers: &Providers,
+        user_id: i32,
+        code: ReleaseCode,
+    ) -> Result<Vec<Record>, AppError>;
+}
+
+/// Record built from a provider album, not stored yet
+fn search_result(album: ProviderAlbum) -> Record {
+    let tags = album
+        .tags()
+        .into_iter()
+        .map(|name| TagResponse::from(Tag::new(0, name)))
+        .collect();
+
+    Record {
+        id: 0,
+        user_id: 0,
+        title: album.title,
+        artist: album.artist,
+        release_date: album.release_date,
+        cover_url: album.cover_url.unwrap_or_default(),
+        discogs_url: album.discogs_url,
+        spotify_url: album.spotify_url,
+        musicbrainz_id: album.musicbrainz_id,
+        owned: false,
+        wanted: false,
+        created_at: chrono::NaiveDateTime::default(),
+        catalog_number: album.catalog_number,
+        format: album.format,
+        rating: None,
+        collection_folder: None,
+        media_condition: None,
+        sleeve_condition: None,
+        notes: None,
+        tags: Some(tags),
+        match_confidence: None,
+    }
+}
+
+/// Completes the Discogs albums, or the MusicBrainz ones without Discogs results, with the id
+/// and release date of the MusicBrainz album matching them, then with the release date, cover
+/// and link of the Spotify album matching them. Fields stay empty when no album is close enough.
+/// Spotify albums are used alone without Discogs or MusicBrainz results
+fn merge_search_results(
+    discogs: Vec<ProviderAlbum>,
+    musicbrainz: Vec<ProviderAlbum>,
+    spotify: Vec<ProviderAlbum>,
+) -> Vec<Record> {
+    let (mut albums, musicbrainz) = match discogs.is_empty() {
+        true => (musicbrainz, Vec::new()),
+        false => (discogs, musicbrainz),
+    };
+    if albums.is_empty() {
+        return spotify.into_iter().map(search_result).collect();
+    }
+
+    let matches = album_matcher::match_albums(&albums, &musicbrainz);
+    for (album, (matched, _)) in albums.iter_mut().zip(matches) {
+        if let Some(musicbrainz_album) = matched.map(|index| &musicbrainz[index]) {
+            album.musicbrainz_id = musicbrainz_album.musicbrainz_id.clone();
+            // Discogs searches only know the year
+            if musicbrainz_album.release_date != NaiveDate::default() {
+                album.release_date = musicbrainz_album.release_date;
+            }
+        }
+    }
+
+    let matches = album_matcher::match_albums(&albums, &spotify);
+    albums
+        .into_iter()
+        .zip(matches)
+        .map(|(mut album, (matched, confidence))| {
+            if let Some(spotify_album) = matched.map(|index| &spotify[index]) {
+                album.release_date = spotify_album.release_date;
+                album.cover_url = spotify_album.cover_url.clone().or(album.cover_url);
+                album.spotify_url = spotify_album.spotify_url.clone();
+            }
+            Record {
+                match_confidence: confidence,
+                ..search_result(album)
+            }
+        })
+        .collect()
+}
+
+/// Results of a single provider, `None` when it failed. A disabled provider has no results,
+/// a failing one is logged and left out instead of failing the search
+async fn search_provider(
+    name: &str,
+    provider: &dyn MetadataProvider,
+    query: &str,
+) -> Option<Vec<ProviderAlbum>> {
+    if !provider.is_enabled() {
+        return Some(Vec::new());
+    }
+    match provider.search(query).await {
+        Ok(albums) => Some(albums),
+        Err(e) => {
+            tracing::warn!("{} search failed: {}", name, e);
+            None
+        }
+    }
+}
+
+/// Looks up the covers of the results found on MusicBrainz only, a failed lookup
+/// leaves the cover empty instead of failing the search
+async fn add_missing_covers(providers: &Providers, records: &mut [Record]) {
+    let lookups = records
+        .iter_mut()
+        .filter(|record| record.cover_url.is_empty())
+        .filter_map(|record| Some((record.musicbrainz_id.clone()?, record)))
+        .map(|(musicbrainz_id, record)| async move {
+            match providers.cover_art.front_cover(&musicbrainz_id).await {
+                Ok(cover_url) => record.cover_url = cover_url.unwrap_or_default(),
+                Err(e) => tracing::warn!("Cover of {} not found: {}", musicbrainz_id, e),
+            }
+        });
+    join_all(lookups).await;
+}
+
+/// Record of a single release, its genres, styles and label become tags of their kind
+fn album_record_input(album: ProviderAlbum, wanted: bool) -> RecordInput {
+    let tags = album.tags();
+
+    RecordInput {
+        cover_url: album
+            .cover_url
+            .unwrap_or_else(|| placeholder_cover_url(&album.artist, &album.title)),
+        title: album.title,
+        artist: album.artist,
+        release_date: album.release_date.format("%Y-%m-%d").to_string(),
+        discogs_url: album.discogs_url,
+        spotify_url: album.spotify_url,
+        musicbrainz_id: album.musicbrainz_id,
+        owned: Some(!wanted),
+        wanted: Some(wanted),
+        created_at: None,
+        catalog_number: album.catalog_number,
+        format: album.format,
+        rating: None,
+        collection_folder: None,
+        media_condition: None,
+        sleeve_condition: None,
+        notes: None,
+        tags: Some(tags),
+    }
+}
+
+/// Gives the search results already in the user's records, by Discogs url, Spotify url
+/// or MusicBrainz id, the id and flags of that record
+async fn mark_collected(
+    repos: &Repositories,
+    db_con: &mut DbCon,
+    user_id: i32,
+    mut records: Vec<Record>,
+) -> Result<Vec<Record>, AppError> {
+    let discogs_urls: Vec<String> = records.iter().filter_map(|r| r.discogs_url.clone()).collect();
+    let spotify_urls: Vec<String> = records.iter().filter_map(|r| r.spotify_url.clone()).collect();
+    let musicbrainz_ids: Vec<String> = records.iter().filter_map(|r| r.musicbrainz_id.clone()).collect();
+    let collected = repos
+        .record
+        .find_all_by_external_urls(&mut *db_con, user_id, &discogs_urls, &spotify_urls, &musicbrainz_ids)
+        .await?;
+    if collected.is_empty() {
+        return Ok(records);
+    }
+
+    // The Discogs url is the more precise one, MusicBrainz and Spotify only link the album
+    let by_discogs: HashMap<&str, &Record> = collected
+        .iter()
+        .filter_map(|record| Some((record.discogs_url.as_deref()?, record)))
+        .collect();
+    let by_spotify: HashMap<&str, &Record> = collected
+        .iter()
+        .filter_map(|record| Some((record.spotify_url.as_deref()?, record)))
+        .collect();
+    let by_musicbrainz: HashMap<&str, &Record> = collected
+        .iter()
+        .filter_map(|record| Some((record.musicbrainz_id.as_deref()?, record)))
+        .collect();
+
+    for result in records.iter_mut() {
+        let existing = result
+            .discogs_url
+            .as_deref()
+            .and_then(|url| by_discogs.get(url))
+            .or_else(|| result.musicbrainz_id.as_deref().and_then(|id| by_musicbrainz.get(id)))
+            .or_else(|| result.spotify_url.as_deref().and_then(|url| by_spotify.get(url)));
+        if let Some(existing) = existing {
+            result.id = existing.id;
+            result.user_id = existing.user_id;
+            result.owned = existing.owned;
+            result.wanted = existing.wanted;
+        }
+    }
+    Ok(records)
+}
+
+/// Fetches a record and makes sure it belongs to the given user
+async fn find_owned_record(
+    repos: &Repositories,
+    db_con: &mut DbCon,
+    user_id: i32,
+    id: i32,
+) -> Result<Record, AppError> {
+    let record = repos
+        .record
+        .find_by_id(&mut *db_con, id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if record.user_id != user_id {
+        return Err(AppError::Unauthorized);
+    }
+
+    Ok(record)
+}
+
+/// Fetches the user's records sharing a discogs_url with the imported rows, keyed by that url
+async fn find_imported_records(
+    repos: &Repositories,
+    db_con: &mut DbCon,
+    user_id: i32,
+    rows: &[ImportRow],
+) -> Result<HashMap<String, Record>, AppError> {
+    let discogs_urls: Vec<String> = rows
+        .iter()
+        .filter_map(|row| row.record.as_ref().ok()?.discogs_url.clone())
+        .collect();
+
+    let records = repos
+        .record
+        .find_all_by_discogs_urls(&mut *db_con, user_id, &discogs_urls)
+        .await?;
+
+    Ok(records
+        .into_iter()
+        .filter_map(|record| Some((record.discogs_url.clone()?, record)))
+        .collect())
+}
+
+/// Merges an imported row over an existing record, keeping what the CSV does not know about
+fn merge_import(existing: &Record, input: RecordInput) -> RecordInput {
+    // The CSV only has the release year, keep a more precise date from the same year
+    let release_date = match NaiveDate::parse_from_str(&input.release_date, "%Y-%m-%d") {
+        Ok(date) if date.year() == existing.release_date.year() => {
+            existing.release_date.format("%Y-%m-%d").to_string()
+        }
+        _ => input.release_date,
+    };
+
+    // Imported tags are added to the ones already on the record
+    let mut tags: Vec<String> = existing
+        .tags
+        .iter()
+        .flatten()
+        .map(|tag| tag.qualified_name())
+        .collect();
+    for tag in input.tags.into_iter().flatten() {
+        let slug = Tag::slug_of(&tag);
+        if !slug.is_empty() && !tags.iter().any(|name| Tag::slug_of(name) == slug) {
+            tags.push(tag);
+        }
+    }
+
+    RecordInput {
+        title: input.title,
+        artist: input.artist,
+        release_date,
+        cover_url: existing.cover_url.clone(),
+        discogs_url: existing.discogs_url.clone(),
+        spotify_url: existing.spotify_url.clone(),
+        musicbrainz_id: existing.musicbrainz_id.clone(),
+        owned: input.owned,
+        wanted: input.wanted,
+        created_at: input.created_at,
+        catalog_number: input.catalog_number,
+        format: input.format,
+        rating: input.rating,
+        collection_folder: input.collection_folder,
+        media_condition: input.media_condition,
+        sleeve_condition: input.sleeve_condition,
+        notes: input.notes,
+        tags: Some(tags),
+    }
+}
+
+/// Whether saving a merged import would leave the record as it is
+fn is_unchanged(existing: &Record, merged: &RecordInput) -> bool {
+    let tags_count = existing.tags.as_ref().map_or(0, |tags| tags.len());
+
+    existing.title == merged.title
+        && existing.artist == merged.artist
+        && existing.release_date.format("%Y-%m-%d").to_string() == merged.release_date
+        && Some(existing.owned) == merged.owned
+        && Some(existing.wanted) == merged.wanted
+        && merged.added_at().is_none_or(|added_at| added_at == existing.created_at)
+        && existing.catalog_number == merged.catalog_number
+        && existing.format == merged.format
+        && existing.rating == merged.rating
+        && existing.collection_folder == merged.collection_folder
+        && existing.media_condition == merged.media_condition
+        && existing.sleeve_condition == merged.sleeve_condition
+        && existing.notes == merged.notes
+        // Tags are only ever added by an import, so the same count means no new tag
+        && merged.tags.as_ref().map_or(0, |tags| tags.len()) == tags_count
+}
+
+/// Imports parsed CSV rows, rows already in the collection are matched on their discogs_url
+pub async fn import_rows(
+    repos: &Repositories,
+    db_con: &mut DbCon,
+    user_id: i32,
+    rows: Vec<ImportRow>,
+    mode: ImportMode,
+) -> Result<ImportReport, AppError> {
+    app_err_ensure!(!rows.is_empty(), 400, "No records found in the CSV file");
+
+    // Fetch the records already imported from the same releases
+    let existing = find_imported_records(repos, db_con, user_id, &rows).await?;
+
+    let mut report = ImportReport::default();
+    let mut seen_urls = HashSet::new();
+    let mut to_create = Vec::new();
+    let mut to_update = Vec::new();
+
+    for ImportRow { row, record } in rows {
+        let input = match record {
+            Ok(input) => input,
+            Err(errors) => {
+                report.push_invalid(row, errors);
+                continue;
+            }
+        };
+
+        if let Some(url) = &input.discogs_url {
+            if !seen_urls.insert(url.clone()) {
+                let message = "Duplicate release in the CSV file".to_string();
+                report.push(row, ImportRowStatus::Skipped, None, Some(message));
+                continue;
+            }
+        }
+
+        match input.discogs_url.as_ref().and_then(|url| existing.get(url)) {
+            None => to_create.push((row, input)),
+            Some(record) if mode == ImportMode::Create => {
+                let message = "Record already in the collection".to_string();
+                report.push(row, ImportRowStatus::Skipped, Some(record.id), Some(message));
+            }
+            Some(record) => {
+                let merged = merge_import(record, input);
+                if is_unchanged(record, &merged) {
+                    report.push(row, ImportRowStatus::Unchanged, Some(record.id), None);
+                } else {
+                    to_update.push((row, record.id, merged));
+                }
+            }
+        }
+    }
+
+    // Write everything at once so a failing row does not leave a partial import
+    let mut tx = db_con.begin().await.map_err(|e| log_into!(e, DbRepoError))?;
+
+    let (created_rows, inputs): (Vec<usize>, Vec<RecordInput>) = to_create.into_iter().unzip();
+    let created_records = repos.record.create_multiple(&mut tx, user_id, inputs).await?;
+    for (row, record) in created_rows.into_iter().zip(created_records) {
+        report.push(row, ImportRowStatus::Created, Some(record.id), None);
+    }
+
+    for (row, id, input) in to_update {
+        repos.record.update(&mut tx, id, input).await?;
+        report.push(row, ImportRowStatus::Updated, Some(id), None);
+    }
+
+    tx.commit().await.map_err(|e| log_into!(e, DbRepoError))?;
+
+    report.rows.sort_by_key(|result| result.row);
+    Ok(report)
 }
 
 #[async_trait]
@@ -91,6 +573,103 @@ impl RecordUseCase for RecordUseCaseImpl {
         Ok(created_records)
     }
 
+    #[instrument(name = "record_use_case/create_from_discogs", skip_all, fields(user_id = %user_id))]
+    async fn create_from_discogs(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        providers: &Providers,
+        user_id: i32,
+        release_id: ReleaseId,
+        wanted: bool,
+    ) -> Result<Record, AppError> {
+        app_err_ensure!(providers.discogs.is_enabled(), 503, "Discogs is not configured");
+
+        let album = providers
+            .discogs
+            .release(&release_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        let input = album_record_input(album, wanted);
+        input
+            .validate()
+            .map_err(|e| AppError::ValidationError { errors: e })?;
+
+        let discogs_urls: Vec<String> = input.discogs_url.iter().cloned().collect();
+        let existing = repos
+            .record
+            .find_all_by_discogs_urls(&mut *db_con, user_id, &discogs_urls)
+            .await?;
+        app_err_ensure!(existing.is_empty(), 409, "This release is already in your records");
+
+        let created_record = repos.record.create(&mut *db_con, user_id, input).await?;
+        Ok(created_record)
+    }
+
+    #[instrument(name = "record_use_case/import", skip_all, fields(user_id = %user_id))]
+    async fn import(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        rows: Vec<ImportRow>,
+        mode: ImportMode,
+    ) -> Result<ImportReport, AppError> {
+        import_rows(repos, db_con, user_id, rows, mode).await
+    }
+
+    #[instrument(name = "record_use_case/preview_import", skip_all, fields(user_id = %user_id))]
+    async fn preview_import(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        rows: Vec<ImportRow>,
+    ) -> Result<ImportPreview, AppError> {
+        app_err_ensure!(!rows.is_empty(), 400, "No records found in the CSV file");
+
+        let existing = find_imported_records(repos, db_con, user_id, &rows).await?;
+
+        let mut preview = ImportPreview::default();
+        let mut seen_urls = HashSet::new();
+
+        for ImportRow { row, record } in rows {
+            let input = match record {
+                Ok(input) => input,
+                Err(errors) => {
+                    preview.errors.extend(errors);
+                    continue;
+                }
+            };
+
+            if let Some(url) = &input.discogs_url {
+                if !seen_urls.insert(url.clone()) {
+                    let message = "Duplicate release in the CSV file";
+                    preview.errors.push(ImportRowError::new(row, "discogs_url", message));
+                    continue;
+                }
+            }
+
+            match input.discogs_url.as_ref().and_then(|url| existing.get(url)) {
+                Some(record) => preview.conflicts.push(ImportConflict {
+                    row,
+                    record_id: record.id,
+                    title: record.title.clone(),
+                    artist: record.artist.clone(),
+                    discogs_url: input.discogs_url.unwrap_or_default(),
+                }),
+                None => preview.to_create.push(ImportPreviewRow {
+                    row,
+                    title: input.title,
+                    artist: input.artist,
+                    discogs_url: input.discogs_url,
+                }),
+            }
+        }
+
+        Ok(preview)
+    }
+
     #[instrument(name = "record_use_case/find_all", skip_all)]
     async fn find_all(&self, repos: &Repositories, db_con: &mut DbCon) -> Result<Vec<Record>, AppError> {
         let records = repos.record.find_all(&mut *db_con).await?;
@@ -103,14 +682,16 @@ impl RecordUseCase for RecordUseCaseImpl {
         repos: &Repositories,
         db_con: &mut DbCon,
         user_id: i32,
-        owned: Option<bool>,
-        wanted: Option<bool>,
-    ) -> Result<Vec<Record>, AppError> {
-        let records = repos
+        filter: RecordFilter,
+        pagination: RecordPagination,
+    ) -> Result<RecordPage, AppError> {
+        app_err_ensure!(pagination.is_valid(), 400, "Invalid pagination cursor");
+
+        let page = repos
             .record
-            .find_all_by_user_id(&mut *db_con, user_id, owned, wanted)
+            .find_all_by_user_id(&mut *db_con, user_id, &filter, &pagination)
             .await?;
-        Ok(records)
+        Ok(page)
     }
 
     #[instrument(name = "record_use_case/get_random_by_user_id", skip_all)]
@@ -119,12 +700,11 @@ impl RecordUseCase for RecordUseCaseImpl {
         repos: &Repositories,
         db_con: &mut DbCon,
         user_id: i32,
-        owned: Option<bool>,
-        wanted: Option<bool>,
+        filter: RecordFilter,
     ) -> Result<Option<Record>, AppError> {
         let record = repos
             .record
-            .get_random_by_user_id(&mut *db_con, user_id, owned, wanted)
+            .get_random_by_user_id(&mut *db_con, user_id, &filter)
             .await?;
         Ok(record)
     }
@@ -140,207 +720,922 @@ impl RecordUseCase for RecordUseCaseImpl {
         Ok(record)
     }
 
+    #[instrument(name = "record_use_case/update", skip_all, fields(id = %id))]
+    async fn update(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+        record: RecordInput,
+    ) -> Result<Record, AppError> {
+        find_owned_record(repos, db_con, user_id, id).await?;
+
+        let updated_record = repos.record.update(&mut *db_con, id, record).await?;
+        Ok(updated_record)
+    }
+
+    #[instrument(name = "record_use_case/patch", skip_all, fields(id = %id))]
+    async fn patch(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+        patch: RecordPatchInput,
+    ) -> Result<Record, AppError> {
+        let existing = find_owned_record(repos, db_con, user_id, id).await?;
+
+        // Merge the provided fields over the current record
+        let record = RecordInput {
+            title: patch.title.unwrap_or(existing.title),
+            artist: patch.artist.unwrap_or(existing.artist),
+            release_date: patch
+                .release_date
+                .unwrap_or_else(|| existing.release_date.format("%Y-%m-%d").to_string()),
+            cover_url: patch.cover_url.unwrap_or(existing.cover_url),
+            discogs_url: patch.discogs_url.unwrap_or(existing.discogs_url),
+            spotify_url: patch.spotify_url.unwrap_or(existing.spotify_url),
+            musicbrainz_id: patch.musicbrainz_id.unwrap_or(existing.musicbrainz_id),
+            owned: Some(patch.owned.unwrap_or(existing.owned)),
+            wanted: Some(patch.wanted.unwrap_or(existing.wanted)),
+            created_at: patch.created_at,
+            catalog_number: patch.catalog_number.unwrap_or(existing.catalog_number),
+            format: patch.format.unwrap_or(existing.format),
+            rating: patch.rating.unwrap_or(existing.rating),
+            collection_folder: patch.collection_folder.unwrap_or(existing.collection_folder),
+            media_condition: patch.media_condition.unwrap_or(existing.media_condition),
+            sleeve_condition: patch.sleeve_condition.unwrap_or(existing.sleeve_condition),
+            notes: patch.notes.unwrap_or(existing.notes),
+            tags: patch.tags,
+        };
+
+        let updated_record = repos.record.update(&mut *db_con, id, record).await?;
+        Ok(updated_record)
+    }
+
+    #[instrument(name = "record_use_case/delete", skip_all, fields(id = %id))]
+    async fn delete(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+    ) -> Result<(), AppError> {
+        find_owned_record(repos, db_con, user_id, id).await?;
+
+        repos.record.delete(&mut *db_con, id).await?;
+        Ok(())
+    }
+
+    #[instrument(name = "record_use_case/add_tags", skip_all, fields(id = %id))]
+    async fn add_tags(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+        tags: Vec<String>,
+    ) -> Result<Record, AppError> {
+        find_owned_record(repos, db_con, user_id, id).await?;
+        app_err_ensure!(
+            tags.iter().all(|name| !Tag::slug_of(name).is_empty()),
+            400,
+            "Tag names must contain letters or digits"
+        );
+
+        let mut tx = db_con.begin().await.map_err(|e| log_into!(e, DbRepoError))?;
+        let mut tag_ids = Vec::with_capacity(tags.len());
+        for name in tags {
+            tag_ids.push(repos.tag.find_or_create(&mut tx, user_id, name.trim()).await?.id);
+        }
+        repos.tag.attach_tags_to_record(&mut tx, id, &tag_ids).await?;
+        tx.commit().await.map_err(|e| log_into!(e, DbRepoError))?;
+
+        find_owned_record(repos, db_con, user_id, id).await
+    }
+
+    #[instrument(name = "record_use_case/remove_tag", skip_all, fields(id = %id, slug = %slug))]
+    async fn remove_tag(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+        slug: &str,
+    ) -> Result<Record, AppError> {
+        find_owned_record(repos, db_con, user_id, id).await?;
+        let tag = find_user_tag(repos, db_con, user_id, slug).await?;
+
+        repos.tag.detach_tag_from_record(&mut *db_con, id, tag.id).await?;
+        find_owned_record(repos, db_con, user_id, id).await
+    }
+
     #[instrument(name = "record_use_case/search", skip_all)]
-    async fn search(&self, query: &String) -> Result<Vec<Record>, AppError> {
-        // Get records result from discogs
-        let discogs_secret = env::var("DISCOGS_SECRET").expect("DISCOGS_SECRET must be set.");
-        let spotify_client_id =
-            env::var("SPOTIFY_CLIENT_ID").expect("SPOTIFY_CLIENT_ID must be set.");
-        let spotify_client_secret =
-            env::var("SPOTIFY_CLIENT_SECRET").expect("SPOTIFY_CLIENT_SECRET must be set.");
-        let spotify_refresh_token =
-            env::var("SPOTIFY_REFRESH_TOKEN").expect("SPOTIFY_REFRESH_TOKEN must be set.");
-
-        // Authenticate with spotify
-        let auth_string = format!("{}:{}", spotify_client_id, spotify_client_secret);
-        let auth_encoded = base64::engine::general_purpose::STANDARD.encode(auth_string);
-        
-        tracing::info!("Authenticating with Spotify");
-        let spotify_access_token: String = match reqwest::Client::new()
-            .post("https://accounts.spotify.com/api/token")
-            .header(
-                "Authorization",
-                format!("Basic {}", auth_encoded),
-            )
-            .header("Content-Type", "application/x-www-form-urlencoded")
-            .form(&[
-                ("grant_type", "refresh_token"),
-                ("refresh_token", &spotify_refresh_token),
-            ])
-            .send()
-            .await
-        {
-            Ok(response) => {
-                // Get the status code for debugging
-                let status = response.status();
-                // Clone the response so we can look at the body and still use it later
-                let response_text = response.text().await;
-                
-                match response_text {
-                    Ok(body) => {
-                        // Log the raw response for debugging
-                        tracing::info!("Spotify API response status: {}, body: {}", status, body);
-                        
-                        // Try parsing again with the raw text
-                        match serde_json::from_str::<SpotifyAccessTokenRoot>(&body) {
-                            Ok(token_data) => token_data.access_token,
-                            Err(e) => {
-                                tracing::error!("[SpotifyAccessTokenRoot parse error] Error: {}", e);
-                                
-                                // Check if it's an error response from Spotify
-                                if body.contains("error") {
-                                    tracing::error!("Spotify API returned error: {}", body);
-                                }
-                                
-                                // Try to extract access token with a simple approach if possible
-                                if body.contains("access_token") {
-                                    let parts: Vec<&str> = body.split("\"access_token\":\"").collect();
-                                    if parts.len() > 1 {
-                                        let token_part = parts[1];
-                                        let end_idx = token_part.find('"').unwrap_or(token_part.len());
-                                        let token = &token_part[0..end_idx];
-                                        tracing::info!("Extracted access token: {}", token);
-                                        
-                                        token.to_string();
-                                    }
-                                }
-                                
-                                return Err(AppError::new(500, &format!("Failed to parse Spotify response: {}", e)));
-                            }
-                        }
-                    },
-                    Err(e) => {
-                        tracing::error!("[SpotifyAccessToken.text] Error: {}", e);
-                        return Err(AppError::new(500, &e.to_string()));
-                    }
+    async fn search(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        cache: &mut Option<CacheCon>,
+        providers: &Providers,
+        user_id: i32,
+        query: &String,
+        cache_ttl: u64,
+    ) -> Result<SearchResults, AppError> {
+        // The cache is only a shortcut, its errors never fail the search
+        if let Some(con) = cache.as_mut().filter(|_| cache_ttl > 0) {
+            match repos.search_cache.get(con, query).await {
+                Ok(Some(records)) => {
+                    let records = mark_collected(repos, db_con, user_id, records).await?;
+                    return Ok(SearchResults { records, cache_hit: true });
                 }
-            },
-            Err(e) => {
-                tracing::error!("[SpotifyAccessToken.send] Error: {}", e);
-                return Err(AppError::new(500, &e.to_string()));
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Search cache unavailable: {}", e),
             }
-        };
+        }
 
-        let spotify_json: Option<SpotifyRoot> = match reqwest::Client::new()
-            .get("https://api.spotify.com/v1/search")
-            .query(&[("q", query)])
-            .query(&[("type", "album")])
-            .header("Authorization", format!("Bearer {}", spotify_access_token))
-            .header("Content-Type", "application/json")
-            .send()
-            .await
-        {
-            Ok(result) => match result.json::<SpotifyRoot>().await {
-                Ok(result) => Some(result),
-                Err(e) => {
-                    println!("[SpotifyRoot.json] Error: {}", e);
-                    return Err(AppError::new(500, &e.to_string()));
-                }
-            },
-            Err(e) => {
-                println!("[SpotifyRoot.send] Error: {}", e);
-                return Err(AppError::new(500, &e.to_string()));
+        // The providers are queried at once, disabled or failing ones are left out
+        let (discogs, musicbrainz, spotify) = join3(
+            search_provider("Discogs", providers.discogs.as_ref(), query),
+            search_provider("MusicBrainz", providers.musicbrainz.as_ref(), query),
+            search_provider("Spotify", providers.spotify.as_ref(), query),
+        )
+        .await;
+        let complete = discogs.is_some() && musicbrainz.is_some() && spotify.is_some();
+
+        let mut records = merge_search_results(
+            discogs.unwrap_or_default(),
+            musicbrainz.unwrap_or_default(),
+            spotify.unwrap_or_default(),
+        );
+        add_missing_covers(providers, &mut records).await;
+
+        // Empty results usually mean the providers are disabled, they are not kept, nor are
+        // the results missing a failed provider
+        if let Some(con) = cache.as_mut().filter(|_| cache_ttl > 0 && complete && !records.is_empty()) {
+            if let Err(e) = repos.search_cache.set(con, query, &records, cache_ttl).await {
+                tracing::warn!("Search results not cached: {}", e);
             }
+        }
+
+        // Cached results are shared between users, the collection is checked afterwards
+        let records = mark_collected(repos, db_con, user_id, records).await?;
+        Ok(SearchResults { records, cache_hit: false })
+    }
+
+    #[instrument(name = "record_use_case/lookup", skip_all)]
+    async fn lookup(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        providers: &Providers,
+        user_id: i32,
+        code: ReleaseCode,
+    ) -> Result<Vec<Record>, AppError> {
+        app_err_ensure!(!code.normalized().is_empty(), 400, "The barcode or catalog number is empty");
+
+        // Barcodes are not kept, only catalog numbers can be found in the collection
+        if let ReleaseCode::CatalogNumber(_) = code {
+            let collected = repos
+                .record
+                .find_all_by_catalog_number(&mut *db_con, user_id, &code.normalized())
+                .await?;
+            if !collected.is_empty() {
+                return Ok(collected);
+            }
+        }
+
+        app_err_ensure!(providers.discogs.is_enabled(), 503, "Discogs is not configured");
+        let records = providers
+            .discogs
+            .lookup(&code)
+            .await?
+            .into_iter()
+            .map(search_result)
+            .collect();
+        mark_collected(repos, db_con, user_id, records).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::metadata_dto::DiscogsKind;
+    use crate::providers::discogs_provider::DiscogsProvider;
+    use crate::providers::metadata_provider::{MockCoverProvider, MockMetadataProvider};
+    use crate::repositories::record_repo::MockRecordRepo;
+    use crate::repositories::search_cache_repo::MockSearchCacheRepo;
+    use crate::test::app::{create_providers_for_test, create_repos_for_test};
+    use crate::test::db::create_db_con_for_test;
+    use crate::test::fixture::record::{record_fixture, record_input_fixture};
+    use crate::test::http::serve;
+
+    #[rocket::async_test]
+    async fn test_delete_not_owned() {
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_by_id()
+            .returning(|_, id| Ok(Some(record_fixture(id as usize))));
+        mock_record_repo.expect_delete().never();
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let record_use_case = RecordUseCaseImpl::new();
+        let result = record_use_case.delete(&repos, &mut db_con, 2, 1).await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[rocket::async_test]
+    async fn test_patch_keeps_missing_fields() {
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_by_id()
+            .returning(|_, id| Ok(Some(record_fixture(id as usize))));
+        mock_record_repo
+            .expect_update()
+            .withf(|_, id, input| {
+                *id == 1 && input.title == "new title" && input.artist == "artist1" && input.tags.is_none()
+            })
+            .returning(|_, id, _| Ok(record_fixture(id as usize)));
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let record_use_case = RecordUseCaseImpl::new();
+        let patch = RecordPatchInput {
+            title: Some("new title".to_string()),
+            ..Default::default()
         };
+        let result = record_use_case.patch(&repos, &mut db_con, 1, 1, patch).await;
+        assert!(result.is_ok());
+    }
 
-        let discogs_json = match reqwest::Client::new()
-            .get("https://api.discogs.com/database/search")
-            .query(&[("q", query)])
-            .query(&[("type", "master")])
-            .header("Authorization", format!("Discogs token={}", discogs_secret))
-            .header("User-Agent", "vinyl-api")
-            .header("Content-Type", "application/json")
-            .send()
-            .await
-        {
-            Ok(result) => match result.json::<DiscogsRoot>().await {
-                Ok(result) => result.results,
-                Err(e) => return Err(AppError::new(500, &e.to_string())),
-            },
-            Err(e) => return Err(AppError::new(500, &e.to_string())),
+    #[rocket::async_test]
+    async fn test_patch_clears_null_fields() {
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_by_id()
+            .returning(|_, id| Ok(Some(record_fixture(id as usize))));
+        mock_record_repo
+            .expect_update()
+            .times(1)
+            .withf(|_, _, input| {
+                // Only the field sent as null is cleared
+                input.spotify_url.is_none() && input.discogs_url == record_fixture(1).discogs_url
+            })
+            .returning(|_, id, _| Ok(record_fixture(id as usize)));
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let record_use_case = RecordUseCaseImpl::new();
+        let patch = RecordPatchInput {
+            spotify_url: Some(None),
+            ..Default::default()
         };
+        let result = record_use_case.patch(&repos, &mut db_con, 1, 1, patch).await;
+        assert!(result.is_ok());
+    }
 
-        let records = discogs_json
-            .iter()
-            .map(|record| {
-                let release_date = DateTime::parse_from_rfc3339(
-                    format!(
-                        "{}-01-01T00:00:00Z",
-                        record.year.as_ref().unwrap_or(&"0000".to_string())
-                    )
-                    .as_str(),
-                )
-                .unwrap_or_default()
-                .date_naive();
-
-                Record {
-                    id: 0,
-                    user_id: 0,
-                    title: record.title.clone(),
-                    artist: record.title.clone().split(" - ").collect::<Vec<&str>>()[0].to_string(),
-                    release_date: release_date, // TODO: get release day from discogs - or spotify
-                    cover_url: record.cover_image.clone(), // TODO: get cover image from spotify
-                    discogs_url: Some(record.master_url.clone()),
-                    spotify_url: None, // TODO: get spotify url
-                    owned: false,
-                    wanted: false,
-                    tags: Some(Vec::new()),
-                }
+    #[rocket::async_test]
+    async fn test_import_upsert_reports_each_row() {
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_discogs_urls()
+            .returning(|_, _, _| {
+                // Release 1 is already up to date, release 2 has an outdated title
+                let mut unchanged = record_fixture(1);
+                unchanged.discogs_url = record_input_fixture(1).discogs_url;
+                let mut outdated = record_fixture(2);
+                outdated.title = "old title".to_string();
+                outdated.discogs_url = record_input_fixture(2).discogs_url;
+                Ok(vec![unchanged, outdated])
+            });
+        mock_record_repo
+            .expect_create_multiple()
+            .withf(|_, _, inputs| inputs.len() == 1 && inputs[0].title == "title3")
+            .returning(|_, _, _| Ok(vec![record_fixture(3)]));
+        mock_record_repo
+            .expect_update()
+            .times(1)
+            .withf(|_, id, input| {
+                // Fields missing from the CSV are kept
+                *id == 2 && input.title == "title2" && input.cover_url == "cover_url2"
             })
+            .returning(|_, id, _| Ok(record_fixture(id as usize)));
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let record_use_case = RecordUseCaseImpl::new();
+        let rows = vec![
+            ImportRow { row: 1, record: Ok(record_input_fixture(1)) },
+            ImportRow { row: 2, record: Ok(record_input_fixture(2)) },
+            ImportRow { row: 3, record: Ok(record_input_fixture(3)) },
+            ImportRow { row: 4, record: Ok(record_input_fixture(3)) },
+            ImportRow {
+                row: 5,
+                record: Err(vec![ImportRowError::new(5, "title", "Title is required")]),
+            },
+        ];
+        let report = record_use_case
+            .import(&repos, &mut db_con, 1, rows, ImportMode::Upsert)
+            .await
+            .unwrap();
+        assert_eq!(
+            (report.created, report.updated, report.unchanged, report.skipped),
+            (1, 1, 1, 2)
+        );
+        let statuses: Vec<ImportRowStatus> = report.rows.iter().map(|row| row.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ImportRowStatus::Unchanged,
+                ImportRowStatus::Updated,
+                ImportRowStatus::Created,
+                ImportRowStatus::Skipped,
+                ImportRowStatus::Skipped,
+            ]
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_import_create_skips_existing() {
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_discogs_urls()
+            .returning(|_, _, _| {
+                let mut existing = record_fixture(1);
+                existing.discogs_url = record_input_fixture(1).discogs_url;
+                Ok(vec![existing])
+            });
+        mock_record_repo
+            .expect_create_multiple()
+            .withf(|_, _, inputs| inputs.is_empty())
+            .returning(|_, _, _| Ok(Vec::new()));
+        mock_record_repo.expect_update().never();
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let record_use_case = RecordUseCaseImpl::new();
+        let rows = vec![ImportRow { row: 1, record: Ok(record_input_fixture(1)) }];
+        let report = record_use_case
+            .import(&repos, &mut db_con, 1, rows, ImportMode::Create)
+            .await
+            .unwrap();
+        assert_eq!(report.skipped, 1);
+        assert_eq!(report.rows[0].record_id, Some(1));
+    }
+
+    #[rocket::async_test]
+    async fn test_preview_import_does_not_write() {
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_discogs_urls()
+            .returning(|_, _, _| {
+                let mut existing = record_fixture(1);
+                existing.discogs_url = record_input_fixture(1).discogs_url;
+                Ok(vec![existing])
+            });
+        mock_record_repo.expect_create_multiple().never();
+        mock_record_repo.expect_update().never();
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let record_use_case = RecordUseCaseImpl::new();
+        let rows = vec![
+            ImportRow { row: 1, record: Ok(record_input_fixture(1)) },
+            ImportRow { row: 2, record: Ok(record_input_fixture(2)) },
+            ImportRow { row: 3, record: Ok(record_input_fixture(2)) },
+            ImportRow {
+                row: 4,
+                record: Err(vec![ImportRowError::new(4, "title", "Title is required")]),
+            },
+        ];
+        let preview = record_use_case
+            .preview_import(&repos, &mut db_con, 1, rows)
+            .await
+            .unwrap();
+        assert_eq!(preview.conflicts.len(), 1);
+        assert_eq!(preview.conflicts[0].record_id, 1);
+        assert_eq!(preview.to_create.len(), 1);
+        assert_eq!(preview.to_create[0].row, 2);
+        let error_rows: Vec<(usize, &str)> = preview
+            .errors
+            .iter()
+            .map(|error| (error.row, error.field.as_str()))
             .collect();
+        assert_eq!(error_rows, vec![(3, "discogs_url"), (4, "title")]);
+    }
+
+    fn album(title: &str, discogs_url: Option<&str>, spotify_url: Option<&str>) -> ProviderAlbum {
+        ProviderAlbum {
+            title: title.to_string(),
+            artist: "artist".to_string(),
+            release_date: NaiveDate::from_ymd_opt(2001, 1, 1).unwrap(),
+            cover_url: Some(format!("https://example.com/{}.jpg", title)),
+            discogs_url: discogs_url.map(String::from),
+            spotify_url: spotify_url.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    fn disabled_provider() -> MockMetadataProvider {
+        let mut provider = MockMetadataProvider::new();
+        provider.expect_is_enabled().return_const(false);
+        provider.expect_search().never();
+        provider
+    }
 
-        // Increment results via spotify search results
+    #[rocket::async_test]
+    async fn test_search_merges_provider_results() {
+        let mut mock_discogs = MockMetadataProvider::new();
+        mock_discogs.expect_is_enabled().return_const(true);
+        mock_discogs.expect_search().returning(|_| {
+            Ok(vec![
+                album("Discovery", Some("https://discogs/1"), None),
+                album("Homework", Some("https://discogs/2"), None),
+            ])
+        });
+        let mut mock_spotify = MockMetadataProvider::new();
+        mock_spotify.expect_is_enabled().return_const(true);
+        mock_spotify.expect_search().returning(|_| {
+            let mut homework = album("Homework", None, Some("https://spotify/2"));
+            homework.release_date = NaiveDate::from_ymd_opt(1997, 1, 20).unwrap();
+            Ok(vec![album("Alive", None, Some("https://spotify/1")), homework])
+        });
+
+        let mut providers = create_providers_for_test();
+        providers.discogs = Box::new(mock_discogs);
+        providers.spotify = Box::new(mock_spotify);
+        providers.musicbrainz = Box::new(disabled_provider());
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_external_urls()
+            .returning(|_, _, _, _, _| Ok(Vec::new()));
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let record_use_case = RecordUseCaseImpl::new();
+        let records = record_use_case
+            .search(&repos, &mut db_con, &mut None, &providers, 1, &"daft punk".to_string(), 60)
+            .await
+            .unwrap()
+            .records;
 
-        let spotify_json = match spotify_json {
-            Some(spotify_json) => spotify_json,
-            None => return Ok(records),
+        assert_eq!(records.len(), 2);
+        // An unrelated Spotify album is not attached
+        assert_eq!(records[0].spotify_url, None);
+        assert_eq!(records[0].cover_url, "https://example.com/Discovery.jpg");
+        assert!(records[0].match_confidence.unwrap() < album_matcher::MATCH_THRESHOLD);
+        assert_eq!(records[1].spotify_url.as_deref(), Some("https://spotify/2"));
+        assert!(records[1].match_confidence.unwrap() >= album_matcher::MATCH_THRESHOLD);
+        assert_eq!(records[1].release_date, NaiveDate::from_ymd_opt(1997, 1, 20).unwrap());
+        assert_eq!(records[1].discogs_url.as_deref(), Some("https://discogs/2"));
+    }
+
+    fn musicbrainz_album(title: &str, id: &str, release_date: NaiveDate) -> ProviderAlbum {
+        ProviderAlbum {
+            release_date,
+            cover_url: None,
+            musicbrainz_id: Some(id.to_string()),
+            ..album(title, None, None)
+        }
+    }
+
+    #[test]
+    fn test_merge_search_results_adds_musicbrainz_ids() {
+        let discogs = vec![
+            album("Discovery", Some("https://discogs/1"), None),
+            album("Homework", Some("https://discogs/2"), None),
+        ];
+        let musicbrainz = vec![
+            musicbrainz_album("Homework", "mbid-2", NaiveDate::from_ymd_opt(2001, 1, 20).unwrap()),
+            musicbrainz_album("Discovery", "mbid-1", NaiveDate::default()),
+        ];
+        let records = merge_search_results(discogs, musicbrainz, Vec::new());
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].musicbrainz_id.as_deref(), Some("mbid-1"));
+        // An unknown date doesn't replace the Discogs year
+        assert_eq!(records[0].release_date, NaiveDate::from_ymd_opt(2001, 1, 1).unwrap());
+        assert_eq!(records[1].musicbrainz_id.as_deref(), Some("mbid-2"));
+        assert_eq!(records[1].release_date, NaiveDate::from_ymd_opt(2001, 1, 20).unwrap());
+        assert_eq!(records[1].cover_url, "https://example.com/Homework.jpg");
+    }
+
+    #[rocket::async_test]
+    async fn test_search_without_discogs_uses_musicbrainz_and_its_covers() {
+        let mut mock_musicbrainz = MockMetadataProvider::new();
+        mock_musicbrainz.expect_is_enabled().return_const(true);
+        mock_musicbrainz.expect_search().returning(|_| {
+            let date = NaiveDate::from_ymd_opt(2001, 3, 12).unwrap();
+            Ok(vec![
+                musicbrainz_album("Discovery", "mbid-1", date),
+                musicbrainz_album("Homework", "mbid-2", date),
+                musicbrainz_album("Alive 1997", "mbid-3", date),
+            ])
+        });
+        let mut mock_spotify = MockMetadataProvider::new();
+        mock_spotify.expect_is_enabled().return_const(true);
+        mock_spotify
+            .expect_search()
+            .returning(|_| Ok(vec![album("Homework", None, Some("https://spotify/2"))]));
+        // Spotify already has the cover of the second album
+        let mut mock_cover_art = MockCoverProvider::new();
+        mock_cover_art
+            .expect_front_cover()
+            .times(2)
+            .returning(|musicbrainz_id| match musicbrainz_id {
+                "mbid-1" => Ok(Some("https://coverartarchive.org/1.jpg".to_string())),
+                _ => Err(AppError::UpstreamTimeout { provider: "Cover Art Archive" }),
+            });
+        let mut providers = create_providers_for_test();
+        providers.discogs = Box::new(disabled_provider());
+        providers.musicbrainz = Box::new(mock_musicbrainz);
+        providers.spotify = Box::new(mock_spotify);
+        providers.cover_art = Box::new(mock_cover_art);
+
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_external_urls()
+            .withf(|_, _, discogs_urls, _, musicbrainz_ids| discogs_urls.is_empty() && musicbrainz_ids.len() == 3)
+            .returning(|_, _, _, _, _| Ok(Vec::new()));
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let record_use_case = RecordUseCaseImpl::new();
+        let records = record_use_case
+            .search(&repos, &mut db_con, &mut None, &providers, 1, &"daft punk".to_string(), 60)
+            .await
+            .unwrap()
+            .records;
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].cover_url, "https://coverartarchive.org/1.jpg");
+        assert_eq!(records[0].release_date, NaiveDate::from_ymd_opt(2001, 3, 12).unwrap());
+        assert_eq!(records[1].cover_url, "https://example.com/Homework.jpg");
+        assert_eq!(records[1].spotify_url.as_deref(), Some("https://spotify/2"));
+        assert_eq!(records[1].musicbrainz_id.as_deref(), Some("mbid-2"));
+        // A failed lookup doesn't fail the search
+        assert_eq!(records[2].cover_url, "");
+    }
+
+    #[rocket::async_test]
+    async fn test_search_skips_disabled_providers() {
+        let mut mock_discogs = MockMetadataProvider::new();
+        mock_discogs.expect_is_enabled().return_const(false);
+        mock_discogs.expect_search().never();
+        let mut mock_spotify = MockMetadataProvider::new();
+        mock_spotify.expect_is_enabled().return_const(true);
+        mock_spotify
+            .expect_search()
+            .returning(|_| Ok(vec![album("Alive", None, Some("https://spotify/1"))]));
+
+        let mut providers = create_providers_for_test();
+        providers.discogs = Box::new(mock_discogs);
+        providers.spotify = Box::new(mock_spotify);
+        providers.musicbrainz = Box::new(disabled_provider());
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_external_urls()
+            .returning(|_, _, _, _, _| Ok(Vec::new()));
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let record_use_case = RecordUseCaseImpl::new();
+        let records = record_use_case
+            .search(&repos, &mut db_con, &mut None, &providers, 1, &"daft punk".to_string(), 60)
+            .await
+            .unwrap()
+            .records;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].title, "Alive");
+
+        // Nothing to search with
+        let mut mock_spotify = MockMetadataProvider::new();
+        mock_spotify.expect_is_enabled().return_const(false);
+        mock_spotify.expect_search().never();
+        providers.spotify = Box::new(mock_spotify);
+        let records = record_use_case
+            .search(&repos, &mut db_con, &mut None, &providers, 1, &"daft punk".to_string(), 60)
+            .await
+            .unwrap()
+            .records;
+        assert!(records.is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn test_search_skips_failing_providers() {
+        let mut mock_discogs = MockMetadataProvider::new();
+        mock_discogs.expect_is_enabled().return_const(true);
+        mock_discogs
+            .expect_search()
+            .returning(|_| Ok(vec![album("Discovery", Some("https://discogs/1"), None)]));
+        let mut mock_spotify = MockMetadataProvider::new();
+        mock_spotify.expect_is_enabled().return_const(true);
+        mock_spotify
+            .expect_search()
+            .times(1)
+            .returning(|_| Err(AppError::UpstreamUnavailable { provider: "Spotify" }));
+
+        let mut providers = create_providers_for_test();
+        providers.discogs = Box::new(mock_discogs);
+        providers.spotify = Box::new(mock_spotify);
+        providers.musicbrainz = Box::new(disabled_provider());
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_external_urls()
+            .returning(|_, _, _, _, _| Ok(Vec::new()));
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let record_use_case = RecordUseCaseImpl::new();
+        let records = record_use_case
+            .search(&repos, &mut db_con, &mut None, &providers, 1, &"discovery".to_string(), 60)
+            .await
+            .unwrap()
+            .records;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].title, "Discovery");
+    }
+
+    #[rocket::async_test]
+    async fn test_search_without_cache_queries_the_providers() {
+        let mut mock_discogs = MockMetadataProvider::new();
+        mock_discogs.expect_is_enabled().return_const(true);
+        mock_discogs
+            .expect_search()
+            .times(1)
+            .returning(|_| Ok(vec![album("Discovery", Some("https://discogs/1"), None)]));
+        let mut mock_spotify = MockMetadataProvider::new();
+        mock_spotify.expect_is_enabled().return_const(false);
+        let mut providers = create_providers_for_test();
+        providers.discogs = Box::new(mock_discogs);
+        providers.spotify = Box::new(mock_spotify);
+        providers.musicbrainz = Box::new(disabled_provider());
+
+        // Redis being unreachable leaves no connection, the cache is not touched
+        let mut mock_search_cache_repo = MockSearchCacheRepo::new();
+        mock_search_cache_repo.expect_get().never();
+        mock_search_cache_repo.expect_set().never();
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_external_urls()
+            .returning(|_, _, _, _, _| Ok(Vec::new()));
+        let mut repos = create_repos_for_test();
+        repos.search_cache = Box::new(mock_search_cache_repo);
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let record_use_case = RecordUseCaseImpl::new();
+        let results = record_use_case
+            .search(&repos, &mut db_con, &mut None, &providers, 1, &"discovery".to_string(), 60)
+            .await
+            .unwrap();
+        assert!(!results.cache_hit);
+        assert_eq!(results.records.len(), 1);
+    }
+
+    #[rocket::async_test]
+    async fn test_search_marks_collected_records() {
+        // Discogs answers with API urls, records hold website ones
+        let result = |id: u32, title: &str| {
+            format!(
+                r#"{{
+                    "country": "France", "year": "2001", "format": [], "label": [], "type": "master",
+                    "genre": [], "style": [], "id": {id}, "barcode": [],
+                    "user_data": {{ "in_wantlist": false, "in_collection": false }},
+                    "master_id": {id}, "master_url": "https://api.discogs.com/masters/{id}",
+                    "uri": "/master/{id}", "catno": "", "title": "artist - {title}",
+                    "thumb": "", "cover_image": "", "resource_url": "", "community": {{ "want": 0, "have": 0 }}
+                }}"#
+            )
         };
+        let server = serve(vec![(
+            200,
+            format!(
+                r#"{{
+                    "pagination": {{ "page": 1, "pages": 1, "per_page": 50, "items": 2, "urls": {{}} }},
+                    "results": [{}, {}]
+                }}"#,
+                result(1, "Discovery"),
+                result(2, "Homework")
+            ),
+        )])
+        .await;
+        let mut mock_spotify = MockMetadataProvider::new();
+        mock_spotify.expect_is_enabled().return_const(true);
+        mock_spotify
+            .expect_search()
+            .returning(|_| Ok(vec![album("Homework", None, Some("https://spotify/2"))]));
+        let mut providers = create_providers_for_test();
+        providers.discogs = Box::new(DiscogsProvider::new(&server.url, Some("token".to_string())));
+        providers.spotify = Box::new(mock_spotify);
+        providers.musicbrainz = Box::new(disabled_provider());
 
-        let records = records
-            .iter()
-            .map(|record| {
-                let spotify_record = spotify_json
-                    .albums
-                    .items
-                    .iter()
-                    .find(|item| item.name == record.title)
-                    .unwrap_or(&spotify_json.albums.items[0]);
-
-                let release_date = match spotify_record.release_date_precision.as_str() {
-                    // For format like "2013-05-20" (day precision)
-                    "day" => DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", spotify_record.release_date))
-                        .unwrap_or_default()
-                        .date_naive(),
-                    // For format like "2013-05" (month precision)
-                    "month" => DateTime::parse_from_rfc3339(&format!("{}-01T00:00:00Z", spotify_record.release_date))
-                        .unwrap_or_default()
-                        .date_naive(),
-                    // For format like "2013" (year precision)
-                    "year" => DateTime::parse_from_rfc3339(&format!("{}-01-01T00:00:00Z", spotify_record.release_date))
-                        .unwrap_or_default()
-                        .date_naive(),
-                    // Default case
-                    _ => DateTime::parse_from_rfc3339(&format!("{}-01-01T00:00:00Z", spotify_record.release_date))
-                        .unwrap_or_default()
-                        .date_naive(),
-                };
-
-                Record {
-                    id: 0,
-                    user_id: 0,
-                    title: record.title.clone(),
-                    artist: record.artist.clone(),
-                    release_date: release_date,
-                    cover_url: spotify_record.images[0].url.clone(),
-                    discogs_url: record.discogs_url.clone(),
-                    spotify_url: Some(spotify_record.external_urls.spotify.clone()),
-                    owned: false,
-                    wanted: false,
-                    tags: Some(Vec::new()),
-                }
+        // One record is found by its Discogs url, the other by its Spotify url only
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_external_urls()
+            .withf(|_, user_id, discogs_urls, spotify_urls, _| {
+                *user_id == 3
+                    && discogs_urls
+                        == [
+                            "https://www.discogs.com/master/1".to_string(),
+                            "https://www.discogs.com/master/2".to_string(),
+                        ]
+                    && spotify_urls == ["https://spotify/2".to_string()]
             })
-            .collect();
+            .returning(|_, _, _, _, _| {
+                let mut owned = record_fixture(7);
+                owned.discogs_url = Some("https://www.discogs.com/master/1".to_string());
+                let mut wanted = record_fixture(8);
+                wanted.discogs_url = None;
+                wanted.spotify_url = Some("https://spotify/2".to_string());
+                wanted.owned = false;
+                wanted.wanted = true;
+                Ok(vec![owned, wanted])
+            });
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let record_use_case = RecordUseCaseImpl::new();
+        let records = record_use_case
+            .search(&repos, &mut db_con, &mut None, &providers, 3, &"daft punk".to_string(), 60)
+            .await
+            .unwrap()
+            .records;
 
-        Ok(records)
+        assert_eq!((records[0].id, records[0].owned, records[0].wanted), (7, true, false));
+        assert_eq!((records[1].id, records[1].owned, records[1].wanted), (8, false, true));
+        assert_eq!(records[1].title, "Homework");
+    }
+
+    fn discogs_release() -> ProviderAlbum {
+        ProviderAlbum {
+            title: "Discovery".to_string(),
+            artist: "Daft Punk".to_string(),
+            release_date: NaiveDate::from_ymd_opt(2001, 3, 12).unwrap(),
+            cover_url: None,
+            discogs_url: Some("https://www.discogs.com/release/20".to_string()),
+            genres: vec!["Electronic".to_string(), "electronic".to_string()],
+            styles: vec!["House".to_string()],
+            label: Some("Virgin".to_string()),
+            catalog_number: Some("7243 8 49606 1 2".to_string()),
+            format: Some("Vinyl, LP, Album".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[rocket::async_test]
+    async fn test_lookup_prefers_the_collection() {
+        let mut mock_discogs = MockMetadataProvider::new();
+        mock_discogs.expect_is_enabled().return_const(true);
+        mock_discogs.expect_lookup().never();
+        let mut providers = create_providers_for_test();
+        providers.discogs = Box::new(mock_discogs);
+
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_catalog_number()
+            .withf(|_, user_id, normalized| *user_id == 3 && normalized == "724384960612")
+            .returning(|_, _, _| Ok(vec![record_fixture(1)]));
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+
+        let record_use_case = RecordUseCaseImpl::new();
+        let code = ReleaseCode::CatalogNumber("7243 8 49606 1 2".to_string());
+        let records = record_use_case
+            .lookup(&repos, &mut db_con, &providers, 3, code)
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id, 1);
+
+        let code = ReleaseCode::Barcode(" - ".to_string());
+        let result = record_use_case.lookup(&repos, &mut db_con, &providers, 3, code).await;
+        assert_eq!(result.unwrap_err().status_code(), 400);
+    }
+
+    #[rocket::async_test]
+    async fn test_lookup_asks_discogs() {
+        let mut mock_discogs = MockMetadataProvider::new();
+        mock_discogs.expect_is_enabled().return_const(true);
+        mock_discogs
+            .expect_lookup()
+            .withf(|code| *code == ReleaseCode::Barcode("724384960612".to_string()))
+            .returning(|_| {
+                let mut other = discogs_release();
+                other.discogs_url = Some("https://www.discogs.com/release/21".to_string());
+                Ok(vec![discogs_release(), other])
+            });
+        let mut providers = create_providers_for_test();
+        providers.discogs = Box::new(mock_discogs);
+
+        // Barcodes are never looked up in the collection, the Discogs url tells what's there
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo.expect_find_all_by_catalog_number().never();
+        mock_record_repo
+            .expect_find_all_by_external_urls()
+            .returning(|_, _, _, _, _| {
+                let mut wanted = record_fixture(9);
+                wanted.discogs_url = Some("https://www.discogs.com/release/21".to_string());
+                wanted.owned = false;
+                wanted.wanted = true;
+                Ok(vec![wanted])
+            });
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+
+        let record_use_case = RecordUseCaseImpl::new();
+        let code = ReleaseCode::Barcode("724384960612".to_string());
+        let records = record_use_case
+            .lookup(&repos, &mut db_con, &providers, 3, code)
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        // Ready to add with the details of the pressing
+        assert_eq!(records[0].id, 0);
+        assert_eq!(records[0].catalog_number.as_deref(), Some("7243 8 49606 1 2"));
+        assert_eq!(records[0].format.as_deref(), Some("Vinyl, LP, Album"));
+        assert_eq!((records[1].id, records[1].wanted), (9, true));
+    }
+
+    #[rocket::async_test]
+    async fn test_create_from_discogs() {
+        let mut mock_discogs = MockMetadataProvider::new();
+        mock_discogs.expect_is_enabled().return_const(true);
+        mock_discogs
+            .expect_release()
+            .withf(|id| *id == ReleaseId::Discogs { kind: DiscogsKind::Release, id: 20 })
+            .returning(|_| Ok(Some(discogs_release())));
+        let mut providers = create_providers_for_test();
+        providers.discogs = Box::new(mock_discogs);
+
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_discogs_urls()
+            .returning(|_, _, _| Ok(Vec::new()));
+        mock_record_repo
+            .expect_create()
+            .withf(|_, user_id, input| {
+                *user_id == 3
+                    && input.release_date == "2001-03-12"
+                    && input.cover_url.starts_with("https://via.placeholder.com/")
+                    && input.wanted == Some(true)
+                    && input.owned == Some(false)
+                    && input.catalog_number.as_deref() == Some("7243 8 49606 1 2")
+                    && input.tags
+                        == Some(vec![
+                            "genre:Electronic".to_string(),
+                            "style:House".to_string(),
+                            "label:Virgin".to_string(),
+                        ])
+            })
+            .returning(|_, _, _| Ok(record_fixture(1)));
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+
+        let record_use_case = RecordUseCaseImpl::new();
+        let release_id = ReleaseId::Discogs { kind: DiscogsKind::Release, id: 20 };
+        let record = record_use_case
+            .create_from_discogs(&repos, &mut db_con, &providers, 3, release_id, true)
+            .await
+            .unwrap();
+        assert_eq!(record.id, 1);
+    }
+
+    #[rocket::async_test]
+    async fn test_create_from_discogs_fails_on_unknown_or_existing_release() {
+        let mut mock_discogs = MockMetadataProvider::new();
+        mock_discogs.expect_is_enabled().return_const(true);
+        let mut calls = 0;
+        mock_discogs.expect_release().returning(move |_| {
+            calls += 1;
+            Ok((calls > 1).then(discogs_release))
+        });
+        let mut providers = create_providers_for_test();
+        providers.discogs = Box::new(mock_discogs);
+
+        let mut mock_record_repo = MockRecordRepo::new();
+        mock_record_repo
+            .expect_find_all_by_discogs_urls()
+            .returning(|_, _, _| Ok(vec![record_fixture(1)]));
+        mock_record_repo.expect_create().never();
+        let mut repos = create_repos_for_test();
+        repos.record = Box::new(mock_record_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+
+        let record_use_case = RecordUseCaseImpl::new();
+        let release_id = ReleaseId::Discogs { kind: DiscogsKind::Release, id: 20 };
+        let result = record_use_case
+            .create_from_discogs(&repos, &mut db_con, &providers, 3, release_id, false)
+            .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+
+        let result = record_use_case
+            .create_from_discogs(&repos, &mut db_con, &providers, 3, release_id, false)
+            .await;
+        assert!(matches!(result, Err(e) if e.status_code() == 409));
     }
 }
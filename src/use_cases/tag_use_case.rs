@@ -0,0 +1,373 @@
+use crate::db::DbCon;
+use crate::dto::tag_dto::{TagPatchInput, DEFAULT_SUGGEST_LIMIT, MAX_SUGGEST_LIMIT};
+use crate::error::app_error::AppError;
+use crate::models::tag_model::{Tag, TagNode, TagUsage};
+use crate::repositories::error::DbRepoError;
+use crate::repositories::repositories::Repositories;
+use crate::{app_err_ensure, log_into};
+use mockall::automock;
+use sqlx::Connection;
+use tracing::instrument;
+
+pub struct TagUseCaseImpl {}
+
+impl TagUseCaseImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Finds a tag of the user by slug, tags of other users are not found
+pub async fn find_user_tag(
+    repos: &Repositories,
+    db_con: &mut DbCon,
+    user_id: i32,
+    slug: &str,
+) -> Result<Tag, AppError> {
+    repos
+        .tag
+        .find_by_slug(&mut *db_con, user_id, slug)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+#[automock]
+#[async_trait]
+pub trait TagUseCase: Send + Sync {
+    /// Tags of the user with the number of records using them
+    async fn find_all(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<Vec<TagUsage>, AppError>;
+
+    /// Tags of the user matching what is being typed, compared by slug so that "rnb" finds
+    /// "R&B / Soul". A kind prefix only suggests tags of that kind
+    async fn suggest(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        q: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<TagUsage>, AppError>;
+
+    /// Tags of the user nested under their parent, with the record counts of each subtree
+    async fn find_tree(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<Vec<TagNode>, AppError>;
+
+    /// Renames a tag, regenerating its slug, changes its kind and moves it in the tree
+    async fn update(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        slug: &str,
+        patch: TagPatchInput,
+    ) -> Result<Tag, AppError>;
+
+    /// Moves the records of a tag to another one and deletes it, returns the remaining tag
+    async fn merge(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        slug: &str,
+        into: &str,
+    ) -> Result<Tag, AppError>;
+
+    async fn delete(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        slug: &str,
+    ) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl TagUseCase for TagUseCaseImpl {
+    #[instrument(name = "tag_use_case/find_all", skip_all)]
+    async fn find_all(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<Vec<TagUsage>, AppError> {
+        let tags = repos.tag.find_all_usages_by_user_id(&mut *db_con, user_id).await?;
+        Ok(tags)
+    }
+
+    #[instrument(name = "tag_use_case/suggest", skip_all, fields(q = %q))]
+    async fn suggest(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        q: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<TagUsage>, AppError> {
+        let (kind, name) = Tag::parse(q);
+        let limit = limit.unwrap_or(DEFAULT_SUGGEST_LIMIT).clamp(1, MAX_SUGGEST_LIMIT);
+
+        // An empty query suggests the most used tags
+        let tags = repos
+            .tag
+            .suggest(&mut *db_con, user_id, &Tag::slugify(name), kind, limit)
+            .await?;
+        Ok(tags)
+    }
+
+    #[instrument(name = "tag_use_case/find_tree", skip_all)]
+    async fn find_tree(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<Vec<TagNode>, AppError> {
+        let rows = repos.tag.find_tree_by_user_id(&mut *db_con, user_id).await?;
+        Ok(TagNode::build_tree(rows))
+    }
+
+    #[instrument(name = "tag_use_case/update", skip_all, fields(slug = %slug))]
+    async fn update(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        slug: &str,
+        patch: TagPatchInput,
+    ) -> Result<Tag, AppError> {
+        let mut tag = find_user_tag(repos, db_con, user_id, slug).await?;
+
+        // Everything is checked before the first write, a rejected patch changes nothing
+        if let Some(name) = &patch.name {
+            let new_slug = Tag::slugify(name);
+            app_err_ensure!(!new_slug.is_empty(), 400, "Name must contain letters or digits");
+
+            // Renaming "jazz" to "Jazz" keeps the slug, anything else must be free
+            if let Some(existing) = repos.tag.find_by_slug(&mut *db_con, user_id, &new_slug).await? {
+                app_err_ensure!(
+                    existing.id == tag.id,
+                    409,
+                    "A tag with this name already exists, merge them instead"
+                );
+            }
+        }
+
+        let parent_id = match &patch.parent {
+            Some(Some(parent_slug)) => {
+                let parent = find_user_tag(repos, db_con, user_id, parent_slug).await?;
+                let subtree = repos.tag.find_subtree_ids(&mut *db_con, tag.id).await?;
+                app_err_ensure!(
+                    !subtree.contains(&parent.id),
+                    400,
+                    "A tag cannot be nested under itself or one of its descendants"
+                );
+                Some(Some(parent.id))
+            }
+            Some(None) => Some(None),
+            None => None,
+        };
+
+        let mut tx = db_con.begin().await.map_err(|e| log_into!(e, DbRepoError))?;
+        if let Some(name) = patch.name {
+            tag = repos.tag.rename(&mut tx, tag.id, name.trim()).await?;
+        }
+        if let Some(kind) = patch.kind {
+            tag = repos.tag.update_kind(&mut tx, tag.id, kind).await?;
+        }
+        if let Some(parent_id) = parent_id {
+            tag = repos.tag.set_parent(&mut tx, tag.id, parent_id).await?;
+        }
+        tx.commit().await.map_err(|e| log_into!(e, DbRepoError))?;
+
+        Ok(tag)
+    }
+
+    #[instrument(name = "tag_use_case/merge", skip_all, fields(slug = %slug, into = %into))]
+    async fn merge(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        slug: &str,
+        into: &str,
+    ) -> Result<Tag, AppError> {
+        let source = find_user_tag(repos, db_con, user_id, slug).await?;
+        let target = find_user_tag(repos, db_con, user_id, into).await?;
+        app_err_ensure!(source.id != target.id, 400, "A tag cannot be merged into itself");
+
+        let mut tx = db_con.begin().await.map_err(|e| log_into!(e, DbRepoError))?;
+        repos.tag.merge(&mut tx, source.id, target.id).await?;
+        tx.commit().await.map_err(|e| log_into!(e, DbRepoError))?;
+
+        Ok(target)
+    }
+
+    #[instrument(name = "tag_use_case/delete", skip_all, fields(slug = %slug))]
+    async fn delete(
+        &self,
+        repos: &Repositories,
+        db_con: &mut DbCon,
+        user_id: i32,
+        slug: &str,
+    ) -> Result<(), AppError> {
+        let tag = find_user_tag(repos, db_con, user_id, slug).await?;
+
+        repos.tag.delete(&mut *db_con, tag.id).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tag_model::TagKind;
+    use crate::repositories::tag_repo::MockTagRepo;
+    use crate::test::app::create_repos_for_test;
+    use crate::test::db::create_db_con_for_test;
+
+    fn tag(id: i32, name: &str) -> Tag {
+        Tag {
+            id,
+            ..Tag::new(1, name.to_string())
+        }
+    }
+
+    #[rocket::async_test]
+    async fn test_update_to_an_existing_tag() {
+        let mut mock_tag_repo = MockTagRepo::new();
+        mock_tag_repo
+            .expect_find_by_slug()
+            .returning(|_, _, slug| {
+                Ok(match slug {
+                    "warp" => Some(tag(1, "Warp")),
+                    "warp-records" => Some(tag(2, "Warp Records")),
+                    _ => None,
+                })
+            });
+        mock_tag_repo
+            .expect_rename()
+            .times(1)
+            .returning(|_, id, name| Ok(tag(id, name)));
+        mock_tag_repo
+            .expect_update_kind()
+            .times(1)
+            .returning(|_, id, kind| Ok(Tag { kind: kind.as_str().to_string(), ..tag(id, "WARP") }));
+        let mut repos = create_repos_for_test();
+        repos.tag = Box::new(mock_tag_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let tag_use_case = TagUseCaseImpl::new();
+
+        let rename = |name: &str| TagPatchInput { name: Some(name.to_string()), ..Default::default() };
+        let result = tag_use_case.update(&repos, &mut db_con, 1, "warp", rename("Warp Records")).await;
+        assert!(matches!(result, Err(e) if e.status_code() == 409));
+
+        let result = tag_use_case.update(&repos, &mut db_con, 1, "warp", rename("!!!")).await;
+        assert!(matches!(result, Err(e) if e.status_code() == 400));
+
+        // Changing the case keeps the tag
+        let patch = TagPatchInput { kind: Some(TagKind::Label), ..rename("WARP") };
+        let updated = tag_use_case.update(&repos, &mut db_con, 1, "warp", patch).await.unwrap();
+        assert_eq!(updated.id, 1);
+        assert_eq!((updated.slug.as_str(), updated.kind.as_str()), ("warp", "label"));
+    }
+
+    #[rocket::async_test]
+    async fn test_update_parent_to_a_descendant() {
+        let mut mock_tag_repo = MockTagRepo::new();
+        mock_tag_repo
+            .expect_find_by_slug()
+            .returning(|_, _, slug| {
+                Ok(match slug {
+                    "electronic" => Some(tag(1, "Electronic")),
+                    "house" => Some(tag(2, "House")),
+                    "deep-house" => Some(tag(3, "Deep House")),
+                    _ => None,
+                })
+            });
+        // Electronic > House > Deep House
+        mock_tag_repo
+            .expect_find_subtree_ids()
+            .returning(|_, id| Ok((id..=3).collect()));
+        mock_tag_repo
+            .expect_set_parent()
+            .times(2)
+            .returning(|_, id, parent_id| Ok(Tag { parent_id, ..tag(id, "Deep House") }));
+        // A rejected parent leaves the rest of the patch unapplied
+        mock_tag_repo.expect_rename().never();
+        let mut repos = create_repos_for_test();
+        repos.tag = Box::new(mock_tag_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let tag_use_case = TagUseCaseImpl::new();
+
+        let nest = |parent: Option<&str>| TagPatchInput {
+            parent: Some(parent.map(str::to_string)),
+            ..Default::default()
+        };
+        for (slug, parent) in [("electronic", "deep-house"), ("house", "house")] {
+            let result = tag_use_case.update(&repos, &mut db_con, 1, slug, nest(Some(parent))).await;
+            assert!(matches!(result, Err(e) if e.status_code() == 400));
+        }
+
+        let result = tag_use_case.update(&repos, &mut db_con, 1, "deep-house", nest(Some("unknown"))).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+
+        let patch = TagPatchInput { name: Some("Electronica".to_string()), ..nest(Some("house")) };
+        let result = tag_use_case.update(&repos, &mut db_con, 1, "electronic", patch).await;
+        assert!(matches!(result, Err(e) if e.status_code() == 400));
+
+        let nested = tag_use_case.update(&repos, &mut db_con, 1, "deep-house", nest(Some("electronic"))).await.unwrap();
+        assert_eq!(nested.parent_id, Some(1));
+        let root = tag_use_case.update(&repos, &mut db_con, 1, "deep-house", nest(None)).await.unwrap();
+        assert_eq!(root.parent_id, None);
+    }
+
+    #[rocket::async_test]
+    async fn test_suggest() {
+        let mut mock_tag_repo = MockTagRepo::new();
+        mock_tag_repo
+            .expect_suggest()
+            .withf(|_, _, slug, kind, limit| slug == "rnb" && kind.is_none() && *limit == DEFAULT_SUGGEST_LIMIT)
+            .times(1)
+            .returning(|_, _, _, _, _| Ok(Vec::new()));
+        mock_tag_repo
+            .expect_suggest()
+            .withf(|_, _, slug, kind, limit| slug == "hou" && *kind == Some(TagKind::Style) && *limit == MAX_SUGGEST_LIMIT)
+            .times(1)
+            .returning(|_, _, _, _, _| Ok(Vec::new()));
+        let mut repos = create_repos_for_test();
+        repos.tag = Box::new(mock_tag_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let tag_use_case = TagUseCaseImpl::new();
+
+        // The query is compared by slug, a kind prefix restricts the kind
+        tag_use_case.suggest(&repos, &mut db_con, 1, " RnB ", None).await.unwrap();
+        tag_use_case.suggest(&repos, &mut db_con, 1, "style:Hou", Some(1000)).await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_merge_into_itself() {
+        let mut mock_tag_repo = MockTagRepo::new();
+        mock_tag_repo
+            .expect_find_by_slug()
+            .returning(|_, _, slug| Ok((slug == "warp").then(|| tag(1, "Warp"))));
+        mock_tag_repo.expect_merge().never();
+        let mut repos = create_repos_for_test();
+        repos.tag = Box::new(mock_tag_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let tag_use_case = TagUseCaseImpl::new();
+
+        let result = tag_use_case.merge(&repos, &mut db_con, 1, "warp", "warp").await;
+        assert!(matches!(result, Err(e) if e.status_code() == 400));
+
+        let result = tag_use_case.merge(&repos, &mut db_con, 1, "warp", "unknown").await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
@@ -2,12 +2,20 @@ use crate::use_cases::auth_use_case::{AuthUseCase, AuthUseCaseImpl};
 use crate::use_cases::record_use_case::{RecordUseCase, RecordUseCaseImpl};
 use crate::use_cases::user_use_case::{UserUseCase, UserUseCaseImpl};
 use crate::use_cases::collection_use_case::{CollectionUseCase, CollectionUseCaseImpl};
+use crate::use_cases::import_job_use_case::{ImportJobUseCase, ImportJobUseCaseImpl};
+use crate::use_cases::enrichment_job_use_case::{EnrichmentJobUseCase, EnrichmentJobUseCaseImpl};
+use crate::use_cases::backup_use_case::{BackupUseCase, BackupUseCaseImpl};
+use crate::use_cases::tag_use_case::{TagUseCase, TagUseCaseImpl};
 
 pub struct UseCases {
     pub record: Box<dyn RecordUseCase>,
     pub user: Box<dyn UserUseCase>,
     pub auth: Box<dyn AuthUseCase>,
     pub collection: Box<dyn CollectionUseCase>,
+    pub import_job: Box<dyn ImportJobUseCase>,
+    pub enrichment_job: Box<dyn EnrichmentJobUseCase>,
+    pub backup: Box<dyn BackupUseCase>,
+    pub tag: Box<dyn TagUseCase>,
 }
 
 impl UseCases {
@@ -17,6 +25,10 @@ impl UseCases {
             user: Box::new(UserUseCaseImpl::new()),
             auth: Box::new(AuthUseCaseImpl::new()),
             collection: Box::new(CollectionUseCaseImpl::new()),
+            import_job: Box::new(ImportJobUseCaseImpl::new()),
+            enrichment_job: Box::new(EnrichmentJobUseCaseImpl::new()),
+            backup: Box::new(BackupUseCaseImpl::new()),
+            tag: Box::new(TagUseCaseImpl::new()),
         }
     }
 }
@@ -0,0 +1,62 @@
+use crate::app::App;
+use crate::db::Db;
+use rocket::fairing::AdHoc;
+use rocket_db_pools::Database;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Rows imported in a single transaction
+const CHUNK_SIZE: usize = 200;
+/// Delay before polling the queue again once it is empty
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Starts the import worker once the server is up
+pub fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Import worker", |rocket| {
+        Box::pin(async move {
+            let (Some(db), Some(app)) = (Db::fetch(rocket), rocket.state::<Arc<App>>()) else {
+                tracing::error!("Import worker not started: database or app state missing");
+                return;
+            };
+
+            tokio::spawn(run(app.clone(), PgPool::clone(db)));
+        })
+    })
+}
+
+/// Processes queued import jobs until the server stops
+pub async fn run(app: Arc<App>, pool: PgPool) {
+    match pool.acquire().await {
+        Ok(mut db_con) => match app.use_cases.import_job.resume_interrupted(&app.repos, &mut db_con).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Resuming {} interrupted import jobs", count),
+            Err(e) => tracing::error!("Failed to resume interrupted import jobs: {}", e),
+        },
+        Err(e) => tracing::error!("Import worker could not connect to the database: {}", e),
+    }
+
+    loop {
+        let processed = match pool.acquire().await {
+            Ok(mut db_con) => app
+                .use_cases
+                .import_job
+                .process_next(&app.repos, &mut db_con, CHUNK_SIZE)
+                .await
+                .map(|job| job.is_some())
+                .unwrap_or_else(|e| {
+                    tracing::error!("Import worker error: {}", e);
+                    false
+                }),
+            Err(e) => {
+                tracing::error!("Import worker could not connect to the database: {}", e);
+                false
+            }
+        };
+
+        // Keep going while there are jobs in the queue
+        if !processed {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod import_worker;
+pub mod enrichment_worker;